@@ -0,0 +1,34 @@
+//! 法令番号から「独禁法」のような法令の略称への対応表
+
+use anyhow::Result;
+use std::{collections::HashMap, path::Path};
+use tokio::fs::read_to_string;
+
+/// 法令番号から法令の略称への対応表
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Abbreviations {
+  map: HashMap<String, String>,
+}
+
+impl Abbreviations {
+  pub fn new(map: HashMap<String, String>) -> Self {
+    Abbreviations { map }
+  }
+
+  /// `{"昭和二十二年法律第五十四号": "独禁法"}`のような、法令番号をキー、略称を値とするJSONファイルを読む
+  pub async fn load(path: &Path) -> Result<Self> {
+    let s = read_to_string(path).await?;
+    let map = serde_json::from_str(&s)?;
+    Ok(Abbreviations { map })
+  }
+
+  /// 法令番号に対応する略称
+  pub fn get(&self, law_num: &str) -> Option<&str> {
+    self.map.get(law_num).map(|name| name.as_str())
+  }
+
+  /// 略称があれば略称を、無ければ法令番号をそのまま返す
+  pub fn display_name<'a>(&'a self, law_num: &'a str) -> &'a str {
+    self.get(law_num).unwrap_or(law_num)
+  }
+}
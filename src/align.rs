@@ -0,0 +1,153 @@
+//! 同じ法令の二つの版の間での条の対応付け
+//!
+//! 改正で条番号が変わっても同じ条を追いかけられるよう、
+//! 条の見出しと条文の文字bigramの類似度から、古い版の条と新しい版の条を組にする。
+
+use crate::{next_chapter, Chapter};
+use anyhow::Result;
+use encoding_rs::Encoding;
+use quick_xml::{encoding, events::Event, Reader};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tokio::io::AsyncBufRead;
+
+/// 条の見出しが同じときに類似度に足す値
+const CAPTION_BONUS: f64 = 0.5;
+
+/// 対応付けに必要な類似度の下限
+pub const DEFAULT_THRESHOLD: f64 = 0.6;
+
+/// 条一つ分の見出しとテキスト
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArticleText {
+  /// 条の位置情報（条番号と附則の改正法令番号だけを持つ）
+  pub chapter: Chapter,
+  pub caption: Option<String>,
+  pub text: String,
+}
+
+/// 古い版の条と新しい版の条の組
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ArticlePair {
+  /// 古い版の条番号（`Num`属性の値）
+  pub old_article: String,
+  /// 新しい版の条番号（`Num`属性の値）
+  pub new_article: String,
+  /// 附則の改正法令番号
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub suppl_provision_title: Option<String>,
+  pub score: f64,
+}
+
+/// 法令XMLを先頭から読み、条ごとの見出しとテキストを取り出す
+pub async fn read_article_texts<R: AsyncBufRead + Unpin>(
+  reader: &mut Reader<R>,
+) -> Result<Vec<ArticleText>> {
+  let utf8 = Encoding::for_label(b"utf-8").unwrap();
+
+  let mut articles: Vec<ArticleText> = vec![];
+  let mut buf = Vec::new();
+  let mut chapter_num = Chapter::default();
+  let mut tag_stack: Vec<Vec<u8>> = Vec::new();
+
+  reader.trim_text(true);
+  loop {
+    match reader.read_event_into_async(&mut buf).await? {
+      Event::Start(tag) => {
+        tag_stack.push(tag.name().as_ref().to_vec());
+        chapter_num = next_chapter(chapter_num, &tag);
+        if tag.name().as_ref() == b"Article" {
+          articles.push(ArticleText {
+            chapter: Chapter {
+              article: chapter_num.article.clone(),
+              suppl_provision_title: chapter_num.suppl_provision_title.clone(),
+              ..Default::default()
+            },
+            caption: None,
+            text: String::new(),
+          });
+        }
+      }
+      Event::End(_) => {
+        tag_stack.pop();
+      }
+      Event::Text(text) => {
+        let is_in_article = tag_stack.iter().any(|name| name == b"Article");
+        let is_ruby_text = tag_stack.iter().any(|name| name == b"Rt");
+        if let (true, false, Some(article)) = (is_in_article, is_ruby_text, articles.last_mut()) {
+          let text_str = encoding::decode(&text.into_inner(), utf8)?.to_string();
+          match tag_stack.last().map(|name| name.as_slice()) {
+            Some(b"ArticleCaption") => article
+              .caption
+              .get_or_insert_with(String::new)
+              .push_str(&text_str),
+            Some(b"ArticleTitle") => (),
+            _ => article.text.push_str(&text_str),
+          }
+        }
+      }
+      Event::Eof => break,
+      _ => (),
+    }
+    buf.clear();
+  }
+  Ok(articles)
+}
+
+fn bigrams(text: &str) -> HashSet<(char, char)> {
+  let chars = text.chars().collect::<Vec<_>>();
+  chars.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+/// 二つの文字bigramの集合の類似度（Dice係数）
+fn dice(a: &HashSet<(char, char)>, b: &HashSet<(char, char)>) -> f64 {
+  if a.is_empty() && b.is_empty() {
+    return 0.0;
+  }
+  let common = a.intersection(b).count();
+  (2 * common) as f64 / (a.len() + b.len()) as f64
+}
+
+/// 古い版の条と新しい版の条を、類似度の高い組から順に一対一で対応付ける。
+/// 附則は同じ改正法令番号のものどうしでだけ対応付ける
+pub fn align_articles(
+  old: &[ArticleText],
+  new: &[ArticleText],
+  threshold: f64,
+) -> Vec<ArticlePair> {
+  let old_bigrams = old.iter().map(|a| bigrams(&a.text)).collect::<Vec<_>>();
+  let new_bigrams = new.iter().map(|a| bigrams(&a.text)).collect::<Vec<_>>();
+  let mut candidates = vec![];
+  for (i, old_article) in old.iter().enumerate() {
+    for (j, new_article) in new.iter().enumerate() {
+      if old_article.chapter.suppl_provision_title != new_article.chapter.suppl_provision_title {
+        continue;
+      }
+      let mut score = dice(&old_bigrams[i], &new_bigrams[j]);
+      if old_article.caption.is_some() && old_article.caption == new_article.caption {
+        score += CAPTION_BONUS;
+      }
+      if score >= threshold {
+        candidates.push((score, i, j));
+      }
+    }
+  }
+  candidates.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+  let mut used_old = HashSet::new();
+  let mut used_new = HashSet::new();
+  let mut pairs = vec![];
+  for (score, i, j) in candidates {
+    if used_old.contains(&i) || used_new.contains(&j) {
+      continue;
+    }
+    used_old.insert(i);
+    used_new.insert(j);
+    pairs.push(ArticlePair {
+      old_article: old[i].chapter.article.clone(),
+      new_article: new[j].chapter.article.clone(),
+      suppl_provision_title: old[i].chapter.suppl_provision_title.clone(),
+      score,
+    });
+  }
+  pairs
+}
@@ -0,0 +1,86 @@
+//! アノテーションツール（doccano・Label Studio）で読み込める形式への変換
+
+use crate::hydrate::ChapterText;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// 出力するアノテーションツールの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum AnnotationFormat {
+  /// doccanoの系列ラベリング用JSONL
+  Doccano,
+  /// Label Studioのタスク形式（predictionsとしてハイライトを付ける）
+  LabelStudio,
+}
+
+/// テキスト中で検索語が出現した範囲（文字単位、終端は含まない）
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct Span {
+  pub start: usize,
+  pub end: usize,
+  pub word: String,
+}
+
+/// テキスト中の検索語の出現範囲をすべて求める
+pub fn find_spans(text: &str, words: &[String]) -> Vec<Span> {
+  let mut spans = vec![];
+  for word in words.iter().filter(|word| !word.is_empty()) {
+    for (byte_start, _) in text.match_indices(word.as_str()) {
+      let start = text[..byte_start].chars().count();
+      spans.push(Span {
+        start,
+        end: start + word.chars().count(),
+        word: word.clone(),
+      });
+    }
+  }
+  spans.sort();
+  spans
+}
+
+/// 1つの項をアノテーションツールの1タスク分のJSONに変換する
+pub fn to_annotation_json(
+  chapter_text: &ChapterText,
+  spans: &[Span],
+  format: AnnotationFormat,
+) -> Value {
+  match format {
+    AnnotationFormat::Doccano => json!({
+      "text": chapter_text.text,
+      "label": spans
+        .iter()
+        .map(|span| json!([span.start, span.end, span.word]))
+        .collect::<Vec<_>>(),
+      "num": chapter_text.num,
+      "chapter": chapter_text.chapter,
+      "match_ids": chapter_text.match_ids,
+    }),
+    AnnotationFormat::LabelStudio => json!({
+      "data": {
+        "text": chapter_text.text,
+        "num": chapter_text.num,
+        "chapter": chapter_text.chapter,
+        "match_ids": chapter_text.match_ids,
+      },
+      "predictions": [{
+        "model_version": env!("CARGO_PKG_NAME"),
+        "result": spans
+          .iter()
+          .enumerate()
+          .map(|(i, span)| json!({
+            "id": format!("span{i}"),
+            "from_name": "label",
+            "to_name": "text",
+            "type": "labels",
+            "value": {
+              "start": span.start,
+              "end": span.end,
+              "text": span.word,
+              "labels": [span.word],
+            },
+          }))
+          .collect::<Vec<_>>(),
+      }],
+    }),
+  }
+}
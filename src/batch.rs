@@ -0,0 +1,242 @@
+//! 複数の法令ファイルの検索
+//!
+//! 法令ファイルを開き、大きさに応じて選んだ読み方で検索するまでを、複数のファイルについて同時に行う。
+//! 結果は渡されたファイルの順に返す。検索できた法令ごとに、呼び出し側の処理を挟むこともできる。
+
+use crate::{
+  diagnostic::{Code, Diagnostic},
+  memory::MemoryGuard,
+  read_strategy::{parallel_parts, ReadOptions, ReadStrategy},
+  search_config::SearchConfig,
+  search_xml_bytes_parallel, search_xml_mmap, search_xml_with, LawParagraph,
+};
+use anyhow::Result;
+use quick_xml::Reader;
+use std::{
+  collections::VecDeque,
+  future::Future,
+  path::{Path, PathBuf},
+  sync::Arc,
+};
+use tokio::{
+  fs::File,
+  io::{AsyncReadExt, BufReader},
+  sync::mpsc,
+};
+use tokio_stream::{wrappers::ReceiverStream, Stream};
+use tracing::*;
+
+/// 複数の法令ファイルを検索するときの設定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchManyConfig {
+  pub search_words: Vec<String>,
+  pub search_config: SearchConfig,
+  pub read_options: ReadOptions,
+  /// 同時に検索するファイルの数
+  pub concurrency: usize,
+  /// 見積もったメモリの量の見張り。上限を超えた後に検索するファイルでは、見つかったテキストを付けない
+  pub memory_guard: MemoryGuard,
+}
+
+impl Default for SearchManyConfig {
+  fn default() -> Self {
+    SearchManyConfig {
+      search_words: vec![],
+      search_config: SearchConfig::default(),
+      read_options: ReadOptions::default(),
+      concurrency: 1,
+      memory_guard: MemoryGuard::new(None),
+    }
+  }
+}
+
+/// 法令ファイルを開く。開けないときや空のときは、コードの付いたエラーを返す
+pub async fn open_law_file(file_path: &Path) -> Result<File, Diagnostic> {
+  let file = File::open(file_path)
+    .await
+    .map_err(|e| Diagnostic::new(Code::FileOpen, e.to_string()).with_file(file_path))?;
+  let metadata = file
+    .metadata()
+    .await
+    .map_err(|e| Diagnostic::new(Code::FileOpen, e.to_string()).with_file(file_path))?;
+  if metadata.len() == 0 {
+    return Err(Diagnostic::new(Code::EmptyFile, "法令ファイルが空です").with_file(file_path));
+  }
+  Ok(file)
+}
+
+/// 開いた法令ファイルを、大きさに応じて選んだ読み方で検索する。
+/// 同期的な検索（メモリマップ・全てメモリに読み込んだもの・形態素解析を使うもの）は、
+/// tokioのワーカースレッドを塞がないように[`tokio::task::spawn_blocking`]のスレッドで行う
+pub async fn search_law_file(
+  mut file: File,
+  search_words: &[String],
+  search_config: &SearchConfig,
+  read_options: &ReadOptions,
+) -> Result<LawParagraph> {
+  let file_size = file.metadata().await?.len();
+  let strategy = read_options.strategy(file_size);
+  Span::current().record("strategy", strategy.as_str());
+  debug!(
+    file_size,
+    strategy = strategy.as_str(),
+    "chose read strategy"
+  );
+  let search_words = search_words.to_vec();
+  let search_config = search_config.clone();
+  match strategy {
+    ReadStrategy::Mmap => {
+      let file = file.into_std().await;
+      spawn_blocking_in_span(move || {
+        search_xml_mmap(
+          &search_words,
+          &search_config,
+          &file,
+          parallel_parts(file_size),
+        )
+      })
+      .await
+    }
+    ReadStrategy::InMemory => {
+      let mut bytes = Vec::with_capacity(file_size as usize);
+      file.read_to_end(&mut bytes).await?;
+      spawn_blocking_in_span(move || {
+        search_xml_bytes_parallel(
+          &search_words,
+          &search_config,
+          &bytes,
+          parallel_parts(file_size),
+        )
+      })
+      .await
+    }
+    ReadStrategy::Stream => {
+      let mut reader =
+        Reader::from_reader(BufReader::with_capacity(read_options.buffer_size, file));
+      if search_config.match_mode.is_blocking() {
+        // 読むのは非同期のままにし、検索全体を別のスレッドで進める
+        let handle = tokio::runtime::Handle::current();
+        spawn_blocking_in_span(move || {
+          handle.block_on(search_xml_with(&search_words, &search_config, &mut reader))
+        })
+        .await
+      } else {
+        search_xml_with(&search_words, &search_config, &mut reader).await
+      }
+    }
+  }
+}
+
+/// `f`を[`tokio::task::spawn_blocking`]のスレッドで、今のspanの中で呼ぶ。`f`のpanicはそのまま伝える
+async fn spawn_blocking_in_span<F>(f: F) -> Result<LawParagraph>
+where
+  F: FnOnce() -> Result<LawParagraph> + Send + 'static,
+{
+  let span = Span::current();
+  match tokio::task::spawn_blocking(move || span.in_scope(f)).await {
+    Ok(result) => result,
+    Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+    Err(e) => Err(e.into()),
+  }
+}
+
+/// 法令ファイルを開いて検索する。失敗したときは、ファイルのpathを付けたコードの付いたエラーを返す
+async fn open_and_search(
+  file_path: PathBuf,
+  config: Arc<SearchManyConfig>,
+) -> Result<LawParagraph> {
+  let file = open_law_file(&file_path).await?;
+  let positions_only;
+  let search_config = if config.memory_guard.is_exceeded() {
+    positions_only = config.search_config.positions_only();
+    &positions_only
+  } else {
+    &config.search_config
+  };
+  search_law_file(
+    file,
+    &config.search_words,
+    search_config,
+    &config.read_options,
+  )
+  .await
+  .map_err(|e| {
+    Diagnostic::from_error(Code::XmlParse, &e)
+      .with_file(&file_path)
+      .into()
+  })
+}
+
+/// 法令ファイルを最大`config.concurrency`個ずつ同時に検索し、結果を`paths`の順に返す。
+/// 失敗したファイルについては、コードの付いたエラー（[`Diagnostic`]）を返す
+pub fn search_many(
+  paths: Vec<PathBuf>,
+  config: SearchManyConfig,
+) -> impl Stream<Item = Result<LawParagraph>> {
+  search_many_with(paths, config, |law_paragraph| {
+    std::future::ready(Ok(law_paragraph))
+  })
+}
+
+/// [`search_many`]と同じように検索し、検索できた法令ごとに`hook`を呼んだ結果を返す。
+/// `hook`は外部の情報を付け加えたり、別の場所に送ったりするのに使う。
+/// `hook`が`Err`を返した法令は、そのエラーを`E006`のコードの付いたエラーにして返す
+pub fn search_many_with<F, Fut>(
+  paths: Vec<PathBuf>,
+  config: SearchManyConfig,
+  hook: F,
+) -> impl Stream<Item = Result<LawParagraph>>
+where
+  F: Fn(LawParagraph) -> Fut + Send + Sync + 'static,
+  Fut: Future<Output = Result<LawParagraph>> + Send + 'static,
+{
+  let concurrency = config.concurrency.max(1);
+  let config = Arc::new(config);
+  let hook = Arc::new(hook);
+  let (sender, receiver) = mpsc::channel(concurrency);
+  tokio::spawn(async move {
+    let mut handles = VecDeque::new();
+    for path in paths {
+      let span = info_span!(
+        "search",
+        file = %path.display(),
+        strategy = field::Empty
+      );
+      let config = config.clone();
+      let hook = hook.clone();
+      let task = async move {
+        let law_paragraph = open_and_search(path.clone(), config).await?;
+        hook(law_paragraph).await.map_err(|e| {
+          anyhow::Error::from(Diagnostic::from_error(Code::HookFailed, &e).with_file(&path))
+        })
+      }
+      .instrument(span);
+      handles.push_back(tokio::spawn(task));
+      if handles.len() >= concurrency {
+        if let Some(handle) = handles.pop_front() {
+          if !send_result(&sender, handle).await {
+            return;
+          }
+        }
+      }
+    }
+    while let Some(handle) = handles.pop_front() {
+      if !send_result(&sender, handle).await {
+        return;
+      }
+    }
+  });
+  ReceiverStream::new(receiver)
+}
+
+/// 検索し終わるのを待って結果を送る。受け取る側が無くなっていたら`false`を返す
+async fn send_result(
+  sender: &mpsc::Sender<Result<LawParagraph>>,
+  handle: tokio::task::JoinHandle<Result<LawParagraph>>,
+) -> bool {
+  let result = match handle.await {
+    Ok(result) => result,
+    Err(e) => Err(e.into()),
+  };
+  sender.send(result).await.is_ok()
+}
@@ -0,0 +1,102 @@
+//! 単語が含まれていた法令を文献として引用するための、CSL-JSON・BibTeX形式への変換
+
+use crate::{date::Date, egov::law_page_url, LawParagraph};
+use anyhow::Result;
+use serde_json::{json, Value};
+
+/// 出力する文献情報の形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum BibliographyFormat {
+  /// CSL-JSON（`type`は`legislation`）
+  CslJson,
+  /// BibTeX（`@misc`）
+  Bibtex,
+}
+
+/// 法令一つ分の文献情報
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BibEntry {
+  /// 法令ID。文献のIDに使う
+  pub law_id: String,
+  /// 法令番号
+  pub num: String,
+  /// 法令名
+  pub title: Option<String>,
+  /// 公布日
+  pub promulgation_date: Option<Date>,
+  /// e-Gov法令検索のURL
+  pub url: String,
+}
+
+impl BibEntry {
+  pub fn new(law_id: &str, law_paragraph: &LawParagraph) -> Self {
+    BibEntry {
+      law_id: law_id.to_string(),
+      num: law_paragraph.num.clone(),
+      title: law_paragraph.title.clone(),
+      promulgation_date: law_paragraph
+        .promulgation_date
+        .as_deref()
+        .and_then(Date::parse),
+      url: law_page_url(law_id),
+    }
+  }
+
+  /// 法令名が分からないときは法令番号を題名にする
+  fn title(&self) -> &str {
+    self.title.as_deref().unwrap_or(&self.num)
+  }
+}
+
+/// CSL-JSONの項目一つにする
+fn to_csl_json(entry: &BibEntry) -> Value {
+  let mut value = json!({
+    "id": entry.law_id,
+    "type": "legislation",
+    "title": entry.title(),
+    "number": entry.num,
+    "URL": entry.url,
+  });
+  if let Some(date) = entry.promulgation_date {
+    value["issued"] = json!({ "date-parts": [[date.year, date.month, date.day]] });
+  }
+  value
+}
+
+/// BibTeXの値に書けない`{`・`}`・`\`を取り除く
+fn escape_bibtex(s: &str) -> String {
+  s.chars()
+    .filter(|c| !matches!(c, '{' | '}' | '\\'))
+    .collect()
+}
+
+/// BibTeXの項目一つにする
+fn to_bibtex(entry: &BibEntry) -> String {
+  let mut fields = vec![
+    ("title", format!("{{{}}}", escape_bibtex(entry.title()))),
+    ("number", escape_bibtex(&entry.num)),
+  ];
+  if let Some(date) = entry.promulgation_date {
+    fields.push(("year", date.year.to_string()));
+    fields.push(("month", date.month.to_string()));
+    fields.push(("day", date.day.to_string()));
+  }
+  fields.push(("url", entry.url.clone()));
+  let body = fields
+    .iter()
+    .map(|(key, value)| format!("  {key} = {{{value}}}"))
+    .collect::<Vec<_>>()
+    .join(",\n");
+  format!("@misc{{{},\n{body}\n}}\n", entry.law_id)
+}
+
+/// 文献情報の一覧を`format`の形式で書く
+pub fn render_bibliography(entries: &[BibEntry], format: BibliographyFormat) -> Result<String> {
+  match format {
+    BibliographyFormat::CslJson => {
+      let values = entries.iter().map(to_csl_json).collect::<Vec<_>>();
+      Ok(serde_json::to_string_pretty(&values)?)
+    }
+    BibliographyFormat::Bibtex => Ok(entries.iter().map(to_bibtex).collect::<Vec<_>>().join("\n")),
+  }
+}
@@ -0,0 +1,88 @@
+//! 条項の位置情報を「第十一条の二第一項第三号」のような表記にする
+
+use crate::{kansuji::to_kansuji, num::Num, Chapter};
+
+/// `Num`属性の値（「11」や「11_2」など）を、「十一」や「十一の二」のような漢数字の表記にする
+pub fn num_to_kansuji(num: &str) -> Option<String> {
+  Num::parse(num).map(|num| num.number(NumberStyle::Kanji))
+}
+
+/// 条項の番号の書き方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum NumberStyle {
+  /// 「第十一条の二」のように漢数字で書く
+  #[default]
+  Kanji,
+  /// 「第11条の2」のように算用数字で書く
+  Arabic,
+}
+
+impl NumberStyle {
+  /// 数一つをこの書き方で書く
+  pub fn number(&self, n: usize) -> String {
+    match self {
+      NumberStyle::Kanji => to_kansuji(n),
+      NumberStyle::Arabic => n.to_string(),
+    }
+  }
+}
+
+/// 「第十一条の二」のように、枝番号を「の」の後ろに付けた表記にする
+fn title(num: &str, unit: char, style: NumberStyle) -> Option<String> {
+  Num::parse(num).map(|num| num.display(unit, style))
+}
+
+/// 条の`Num`属性の値を「第十一条の二」のような表記にする
+pub fn article_title(num: &str) -> Option<String> {
+  title(num, '条', NumberStyle::Kanji)
+}
+
+/// 項の`Num`属性の値を「第二項」のような表記にする
+pub fn paragraph_title(num: &str) -> Option<String> {
+  title(num, '項', NumberStyle::Kanji)
+}
+
+/// 号の`Num`属性の値を「第三号」のような表記にする
+pub fn item_title(num: &str) -> Option<String> {
+  title(num, '号', NumberStyle::Kanji)
+}
+
+/// 法令名・法令番号と条項の位置情報から、「会社法（平成十七年法律第八十六号）第三百三十一条第一項第三号」のような引用の表記にする。
+/// 法令名が分からないときは法令番号だけを書く
+pub fn law_citation(title: Option<&str>, num: &str, chapter: &Chapter) -> String {
+  match title {
+    Some(title) => format!("{title}（{num}）{}", chapter_title(chapter)),
+    None => format!("{num}{}", chapter_title(chapter)),
+  }
+}
+
+/// 条項の位置情報を「第十一条の二第一項第三号」のような表記にする。附則の条項には先頭に「附則」を付ける
+pub fn chapter_title(chapter: &Chapter) -> String {
+  chapter_title_with(chapter, NumberStyle::Kanji)
+}
+
+/// [`chapter_title`]と同じ表記を、番号を`style`で書いて作る
+pub fn chapter_title_with(chapter: &Chapter, style: NumberStyle) -> String {
+  let mut s = String::new();
+  if chapter.suppl_provision_title.is_some() {
+    s.push_str("附則");
+  }
+  if let Some(title) = title(&chapter.article, '条', style) {
+    s.push_str(&title);
+  }
+  if let Some(title) = chapter
+    .paragraph
+    .as_deref()
+    .and_then(|num| title(num, '項', style))
+  {
+    s.push_str(&title);
+  }
+  if let Some(title) = chapter
+    .item
+    .as_deref()
+    .and_then(|num| title(num, '号', style))
+  {
+    s.push_str(&title);
+  }
+  s
+}
@@ -0,0 +1,148 @@
+//! 検索のオプションをまとめて書いておくTOMLの設定ファイル
+//!
+//! キーはコマンドライン引数の長い名前（`search-words`・`dedup`など）で、値はその引数に渡すもの。
+//! 値のあるオプションは文字列か数、フラグは真偽値、複数指定できるオプションや
+//! 複数の値を取るオプション（`near`など）は配列で書く。
+//!
+//! 設定ファイルはコマンドライン引数に直してから、コマンドラインと同じ定義で解析する。
+//! そのため型の誤りや同時に使えないオプションの組み合わせも、コマンドラインと同じように見つかる。
+
+use anyhow::{anyhow, Result};
+use clap::{Arg, ArgAction, Command};
+use serde_json::{json, Map, Value};
+use std::{fmt, path::Path};
+use toml::value::Table;
+
+/// 設定ファイルの問題一つ
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConfigIssue {
+  /// 問題のあったキー。ファイル全体の問題のときは無い
+  pub key: Option<String>,
+  pub message: String,
+}
+
+impl fmt::Display for ConfigIssue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.key {
+      Some(key) => write!(f, "{key}: {}", self.message),
+      None => write!(f, "{}", self.message),
+    }
+  }
+}
+
+/// 設定ファイルを読み込む
+pub async fn load_config(path: &Path) -> Result<Table> {
+  let s = tokio::fs::read_to_string(path).await?;
+  toml::from_str(&s).map_err(|e| anyhow!("設定ファイルを解析できません: {}: {e}", path.display()))
+}
+
+/// 設定ファイルに書けるオプション。ヘルプ・バージョン・位置引数と、設定ファイルを指定するオプション自身は除く
+fn config_args(command: &Command) -> impl Iterator<Item = &Arg> {
+  command.get_arguments().filter(|arg| {
+    !matches!(arg.get_action(), ArgAction::Help | ArgAction::Version)
+      && matches!(arg.get_long(), Some(long) if long != "config")
+  })
+}
+
+/// 一度に二つ以上の値を取るオプションかどうか
+fn is_multiple_values(arg: &Arg) -> bool {
+  matches!(arg.get_num_args(), Some(range) if range.min_values() > 1)
+}
+
+/// TOMLの値一つをコマンドライン引数の値にする
+fn scalar(value: &toml::Value) -> Option<String> {
+  match value {
+    toml::Value::String(s) => Some(s.clone()),
+    toml::Value::Integer(n) => Some(n.to_string()),
+    toml::Value::Float(x) => Some(x.to_string()),
+    toml::Value::Boolean(b) => Some(b.to_string()),
+    toml::Value::Datetime(_) | toml::Value::Array(_) | toml::Value::Table(_) => None,
+  }
+}
+
+/// 設定ファイルの内容をコマンドライン引数に直す。直せなかったキーは問題として返す
+/// （`command`は[`Command::build`]を済ませたもの）
+pub fn config_to_args(config: &Table, command: &Command) -> (Vec<String>, Vec<ConfigIssue>) {
+  let mut args = vec![];
+  let mut issues = vec![];
+  for (key, value) in config.iter() {
+    let issue = |message: String| ConfigIssue {
+      key: Some(key.clone()),
+      message,
+    };
+    let arg = match config_args(command).find(|arg| arg.get_long() == Some(key.as_str())) {
+      Some(arg) => arg,
+      None => {
+        issues.push(issue("知らないキーです".to_string()));
+        continue;
+      }
+    };
+    let option = format!("--{key}");
+    match (arg.get_action(), value) {
+      (ArgAction::SetTrue, toml::Value::Boolean(true)) => args.push(option),
+      (ArgAction::SetTrue, toml::Value::Boolean(false)) => (),
+      (ArgAction::SetTrue, _) => issues.push(issue("真偽値を書いてください".to_string())),
+      (_, toml::Value::Array(values)) => {
+        let values = values.iter().map(scalar).collect::<Option<Vec<_>>>();
+        match values {
+          Some(values) if is_multiple_values(arg) => {
+            args.push(option);
+            args.extend(values);
+          }
+          Some(values) if matches!(arg.get_action(), ArgAction::Append) => {
+            args.extend(values.into_iter().map(|value| format!("--{key}={value}")));
+          }
+          Some(_) => issues.push(issue("値を一つだけ書いてください".to_string())),
+          None => issues.push(issue("配列には文字列か数を書いてください".to_string())),
+        }
+      }
+      (_, value) => match scalar(value) {
+        Some(value) if is_multiple_values(arg) => {
+          issues.push(issue(format!("配列で値を書いてください（{value}）")))
+        }
+        Some(value) => args.push(format!("--{key}={value}")),
+        None => issues.push(issue("文字列か数を書いてください".to_string())),
+      },
+    }
+  }
+  (args, issues)
+}
+
+/// 設定ファイルに書けるキーと値の型を表すJSON Schema
+pub fn config_schema(command: &Command) -> Value {
+  let mut properties = Map::new();
+  for arg in config_args(command) {
+    let scalar_schema = {
+      let possible_values = arg.get_possible_values();
+      if possible_values.is_empty() {
+        json!({ "type": ["string", "integer", "number"] })
+      } else {
+        json!({
+          "type": "string",
+          "enum": possible_values.iter().map(|value| value.get_name()).collect::<Vec<_>>(),
+        })
+      }
+    };
+    let mut schema = match arg.get_action() {
+      ArgAction::SetTrue => json!({ "type": "boolean" }),
+      _ if is_multiple_values(arg) => json!({ "type": "array", "items": scalar_schema }),
+      ArgAction::Append => json!({
+        "anyOf": [scalar_schema, { "type": "array", "items": scalar_schema }],
+      }),
+      _ => scalar_schema,
+    };
+    if let Some(help) = arg.get_help() {
+      schema["description"] = json!(help.to_string());
+    }
+    if let Some(long) = arg.get_long() {
+      properties.insert(long.to_string(), schema);
+    }
+  }
+  json!({
+    "$schema": "http://json-schema.org/draft-07/schema#",
+    "title": "search_article_with_word config",
+    "type": "object",
+    "properties": properties,
+    "additionalProperties": false,
+  })
+}
@@ -0,0 +1,222 @@
+//! 検索語と同じ文に現れる語（文字n-gram）の集計
+
+use crate::matcher::WordMatcher;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// n-gram一つを数えるのに、文字列そのもの以外に使うメモリのおおよその量（バイト）
+const NGRAM_OVERHEAD_BYTES: usize = 48;
+
+/// 検索に加える語の候補を選ぶときに、候補の数の何倍のn-gramの中から選ぶか
+const SUGGESTION_POOL_FACTOR: usize = 10;
+
+/// 検索語と共に現れた文字n-gramとその回数
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct NgramCount {
+  pub ngram: String,
+  pub count: usize,
+}
+
+/// 検索語ごとに、その検索語を含む文に現れる文字n-gramを数える。
+/// 検索に加える語の候補を選ぶために、検索語を含まない文に現れるn-gramも数えておく
+#[derive(Debug, Clone, Default)]
+pub struct CooccurrenceCounter {
+  /// 数えるn-gramの最大の長さ（最小は2）
+  max_n: usize,
+  counts: HashMap<String, HashMap<String, usize>>,
+  /// いずれかの検索語を含む文に現れたn-gramの回数と、その合計
+  matched: HashMap<String, usize>,
+  matched_total: usize,
+  /// どの検索語も含まない文に現れたn-gramの回数と、その合計
+  other: HashMap<String, usize>,
+  other_total: usize,
+  /// 数えているn-gramが使うメモリのおおよその量（バイト）
+  approx_bytes: usize,
+}
+
+impl CooccurrenceCounter {
+  pub fn new(max_n: usize) -> Self {
+    CooccurrenceCounter {
+      max_n,
+      ..Default::default()
+    }
+  }
+
+  /// テキストを「。」と改行で文に区切り、検索語を含む文に現れるn-gramを数える。
+  /// 検索語が含まれているかどうかと出現箇所は、検索語ごとの判定の仕方`matchers`で調べる
+  pub fn add_text(&mut self, text: &str, words: &[String], matchers: &[WordMatcher]) {
+    for sentence in text.split(['。', '\n']) {
+      let chars = sentence.chars().collect::<Vec<_>>();
+      let mut all_ranges = vec![];
+      for (word, matcher) in words.iter().zip(matchers) {
+        if word.is_empty() {
+          continue;
+        }
+        let ranges = matcher
+          .find_ranges(sentence)
+          .into_iter()
+          .map(|(start, end)| {
+            let start_chars = sentence[..start].chars().count();
+            (
+              start_chars,
+              start_chars + sentence[start..end].chars().count(),
+            )
+          })
+          .collect::<Vec<_>>();
+        if ranges.is_empty() {
+          continue;
+        }
+        let counts = self.counts.entry(word.to_string()).or_default();
+        count_ngrams(counts, &mut self.approx_bytes, &chars, self.max_n, &ranges);
+        all_ranges.extend(ranges);
+      }
+      if all_ranges.is_empty() {
+        self.other_total += count_ngrams(
+          &mut self.other,
+          &mut self.approx_bytes,
+          &chars,
+          self.max_n,
+          &[],
+        );
+      } else {
+        self.matched_total += count_ngrams(
+          &mut self.matched,
+          &mut self.approx_bytes,
+          &chars,
+          self.max_n,
+          &all_ranges,
+        );
+      }
+    }
+  }
+
+  /// 数えているn-gramが使うメモリのおおよその量（バイト）
+  pub fn approx_bytes(&self) -> usize {
+    self.approx_bytes
+  }
+
+  /// 検索語ごとに、回数の多い順に`top`個のn-gramを返す
+  pub fn top(&self, top: usize) -> BTreeMap<String, Vec<NgramCount>> {
+    self
+      .counts
+      .iter()
+      .map(|(word, counts)| {
+        let mut ngram_counts = counts
+          .iter()
+          .map(|(ngram, count)| NgramCount {
+            ngram: ngram.clone(),
+            count: *count,
+          })
+          .collect::<Vec<_>>();
+        ngram_counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.ngram.cmp(&b.ngram)));
+        ngram_counts.truncate(top);
+        (word.clone(), ngram_counts)
+      })
+      .collect()
+  }
+
+  /// 検索に加える語の候補として、いずれかの検索語を含む文に特によく現れるn-gramを`top`個返す。
+  /// 「する」「場合」のようにどの文にも多く現れる語が上に来ないように、検索語を含まない文での回数と比べた
+  /// 対数尤度比の大きい順に並べ、検索語を含む文のほうに多く現れるものだけを残す。
+  /// 検索語を含むものや検索語の一部分であるもの、同じ回数で現れるより長いn-gramの一部分であるものは除く
+  pub fn suggestions(&self, words: &[String], top: usize) -> Vec<NgramCount> {
+    let mut candidates = self
+      .matched
+      .iter()
+      .filter(|(ngram, _)| {
+        !words
+          .iter()
+          .any(|word| ngram.contains(word.as_str()) || word.contains(ngram.as_str()))
+      })
+      .filter_map(|(ngram, &count)| {
+        let other = self.other.get(ngram).copied().unwrap_or_default();
+        let score = log_likelihood(count, self.matched_total, other, self.other_total)?;
+        Some((
+          score,
+          NgramCount {
+            ngram: ngram.clone(),
+            count,
+          },
+        ))
+      })
+      .collect::<Vec<_>>();
+    candidates.sort_by(|(a_score, a), (b_score, b)| {
+      b_score
+        .total_cmp(a_score)
+        .then_with(|| a.ngram.cmp(&b.ngram))
+    });
+    let mut candidates = candidates
+      .into_iter()
+      .map(|(_, candidate)| candidate)
+      .collect::<Vec<_>>();
+    // 長いn-gramの一部分かどうかは、順位の高いものの中でだけ確かめる
+    candidates.truncate(top.saturating_mul(SUGGESTION_POOL_FACTOR));
+    candidates
+      .iter()
+      .filter(|a| {
+        !candidates.iter().any(|b| {
+          b.count == a.count && b.ngram.len() > a.ngram.len() && b.ngram.contains(&a.ngram)
+        })
+      })
+      .take(top)
+      .cloned()
+      .collect()
+  }
+}
+
+/// 文一つに現れるn-gramを`counts`に数え、数えた数を返す。
+/// `excluded`（文字単位の範囲）と重なるものや、記号・空白を含むものは数えない
+fn count_ngrams(
+  counts: &mut HashMap<String, usize>,
+  approx_bytes: &mut usize,
+  chars: &[char],
+  max_n: usize,
+  excluded: &[(usize, usize)],
+) -> usize {
+  let mut added = 0;
+  for n in 2..=max_n {
+    for start in 0..chars.len().saturating_sub(n - 1) {
+      let end = start + n;
+      let is_overlap = excluded.iter().any(|(s, e)| start < *e && *s < end);
+      if is_overlap || !chars[start..end].iter().all(|c| c.is_alphanumeric()) {
+        continue;
+      }
+      let ngram = chars[start..end].iter().collect::<String>();
+      let ngram_len = ngram.len();
+      let count = counts.entry(ngram).or_insert_with(|| {
+        *approx_bytes += ngram_len + NGRAM_OVERHEAD_BYTES;
+        0
+      });
+      *count += 1;
+      added += 1;
+    }
+  }
+  added
+}
+
+/// 検索語を含む文の`total`個のn-gramのうちの`count`個と、検索語を含まない文の`other_total`個のn-gramのうちの
+/// `other`個を比べた対数尤度比（Dunningの`G²`）。検索語を含む文のほうに多く現れるときだけ返す
+fn log_likelihood(count: usize, total: usize, other: usize, other_total: usize) -> Option<f64> {
+  let k11 = count as f64;
+  let k12 = total.saturating_sub(count) as f64;
+  let k21 = other as f64;
+  let k22 = other_total.saturating_sub(other) as f64;
+  if k11 * (k21 + k22) <= k21 * (k11 + k12) {
+    return None;
+  }
+  let n = k11 + k12 + k21 + k22;
+  let term = |k: f64, row: f64, col: f64| {
+    if k == 0.0 {
+      0.0
+    } else {
+      k * (k * n / (row * col)).ln()
+    }
+  };
+  Some(
+    2.0
+      * (term(k11, k11 + k12, k11 + k21)
+        + term(k12, k11 + k12, k12 + k22)
+        + term(k21, k21 + k22, k11 + k21)
+        + term(k22, k21 + k22, k12 + k22)),
+  )
+}
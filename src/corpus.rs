@@ -0,0 +1,97 @@
+//! 作業ディレクトリにある法令XMLファイルの一覧
+
+use crate::diagnostic::{report_warning, Code};
+use anyhow::Result;
+use encoding_rs::Encoding;
+use quick_xml::{encoding, events::Event, Reader};
+use std::{
+  collections::HashMap,
+  path::{Path, PathBuf},
+};
+use tokio::{
+  fs::{read_dir, File},
+  io::{AsyncBufRead, BufReader},
+};
+
+/// 法令XMLを先頭から読み、法令番号が見つかったらそこで読むのをやめて返す
+pub async fn read_law_num<R: AsyncBufRead + Unpin>(
+  reader: &mut Reader<R>,
+) -> Result<Option<String>> {
+  let utf8 = Encoding::for_label(b"utf-8").unwrap();
+  let mut buf = Vec::new();
+  let mut is_law_num_mode = false;
+  reader.trim_text(true);
+  loop {
+    match reader.read_event_into_async(&mut buf).await? {
+      Event::Start(tag) => is_law_num_mode = tag.name().as_ref() == b"LawNum",
+      Event::Text(text) if is_law_num_mode => {
+        let law_num = encoding::decode(&text.into_inner(), utf8)?.to_string();
+        return Ok(Some(law_num));
+      }
+      Event::Eof => return Ok(None),
+      _ => (),
+    }
+    buf.clear();
+  }
+}
+
+/// 作業ディレクトリ以下にあるXMLファイルを全て探し、法令番号からファイルへのpathへの対応表を作る
+pub async fn find_law_files(work_dir: &Path) -> Result<HashMap<String, PathBuf>> {
+  let mut law_files = HashMap::new();
+  let mut dirs = vec![work_dir.to_path_buf()];
+  while let Some(dir) = dirs.pop() {
+    let mut entries = read_dir(&dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+      let path = entry.path();
+      if entry.file_type().await?.is_dir() {
+        dirs.push(path);
+      } else if path.extension().map(|ext| ext == "xml").unwrap_or(false) {
+        let mut reader = Reader::from_reader(BufReader::new(File::open(&path).await?));
+        match read_law_num(&mut reader).await {
+          Ok(Some(law_num)) => {
+            law_files.insert(law_num, path);
+          }
+          Ok(None) => (),
+          Err(e) => report_warning(Code::XmlParse, format!("{:?}: {}", path, e)),
+        }
+      }
+    }
+  }
+  Ok(law_files)
+}
+
+/// e-Gov法令検索からダウンロードしたファイルの名前（`{法令ID}_{施行日}_{改正法令ID}.xml`）から法令IDを読む
+pub fn law_id_of(path: &Path) -> Option<String> {
+  let stem = path.file_stem()?.to_str()?;
+  stem.split('_').next().map(|id| id.to_string())
+}
+
+/// ファイルの名前から、版を表す部分（`{施行日}_{改正法令ID}`）を読む
+pub fn version_of(path: &Path) -> Option<String> {
+  let stem = path.file_stem()?.to_str()?;
+  stem.split_once('_').map(|(_, version)| version.to_string())
+}
+
+/// 作業ディレクトリ以下にあるXMLファイルを全て探し、法令IDごとにその法令の全ての版のファイルへのpathをまとめる。
+/// 版はファイルの名前の順（施行日の古い順）に並べる
+pub async fn find_law_versions(work_dir: &Path) -> Result<HashMap<String, Vec<PathBuf>>> {
+  let mut law_versions: HashMap<String, Vec<PathBuf>> = HashMap::new();
+  let mut dirs = vec![work_dir.to_path_buf()];
+  while let Some(dir) = dirs.pop() {
+    let mut entries = read_dir(&dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+      let path = entry.path();
+      if entry.file_type().await?.is_dir() {
+        dirs.push(path);
+      } else if path.extension().map(|ext| ext == "xml").unwrap_or(false) {
+        if let Some(law_id) = law_id_of(&path) {
+          law_versions.entry(law_id).or_default().push(path);
+        }
+      }
+    }
+  }
+  for paths in law_versions.values_mut() {
+    paths.sort_by_key(|path| path.file_name().map(|name| name.to_os_string()));
+  }
+  Ok(law_versions)
+}
@@ -0,0 +1,108 @@
+//! 西暦の日付
+
+use std::{
+  fmt,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+/// 西暦の日付
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+  pub year: usize,
+  pub month: usize,
+  pub day: usize,
+}
+
+fn is_leap_year(year: usize) -> bool {
+  match (year % 4, year % 100, year % 400) {
+    (_, _, 0) => true,
+    (_, 0, _) => false,
+    (0, _, _) => true,
+    _ => false,
+  }
+}
+
+fn days_in_month(year: usize, month: usize) -> usize {
+  match month {
+    2 if is_leap_year(year) => 29,
+    2 => 28,
+    4 | 6 | 9 | 11 => 30,
+    _ => 31,
+  }
+}
+
+impl Date {
+  pub fn new(year: usize, month: usize, day: usize) -> Option<Self> {
+    if (1..=12).contains(&month) && 1 <= day && day <= days_in_month(year, month) {
+      Some(Date { year, month, day })
+    } else {
+      None
+    }
+  }
+
+  /// 「20230401」または「2023-04-01」の形式の文字列を読む
+  pub fn parse(s: &str) -> Option<Self> {
+    let digits = s.replace('-', "");
+    if digits.len() != 8 || !digits.chars().all(|c| c.is_ascii_digit()) {
+      return None;
+    }
+    Date::new(
+      digits[0..4].parse().ok()?,
+      digits[4..6].parse().ok()?,
+      digits[6..8].parse().ok()?,
+    )
+  }
+
+  /// 次の日
+  pub fn succ(&self) -> Self {
+    if self.day < days_in_month(self.year, self.month) {
+      Date {
+        day: self.day + 1,
+        ..*self
+      }
+    } else if self.month < 12 {
+      Date {
+        year: self.year,
+        month: self.month + 1,
+        day: 1,
+      }
+    } else {
+      Date {
+        year: self.year + 1,
+        month: 1,
+        day: 1,
+      }
+    }
+  }
+
+  /// 1970年1月1日からの日数から日付を求める
+  pub fn from_unix_days(days: usize) -> Self {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + usize::from(month <= 2);
+    Date { year, month, day }
+  }
+
+  /// 今日の日付（UTC）
+  pub fn today() -> Self {
+    let secs = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_secs())
+      .unwrap_or(0);
+    Date::from_unix_days((secs / 86400) as usize)
+  }
+}
+
+/// 「20230401」の形式で表示する
+impl fmt::Display for Date {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:04}{:02}{:02}", self.year, self.month, self.day)
+  }
+}
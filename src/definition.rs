@@ -0,0 +1,106 @@
+//! 「この法律において「個人情報」とは、…をいう。」のような定義規定の判定
+
+/// 定義される語と定義を区切る言い回し
+const DEFINITION_MARKER: &str = "とは、";
+
+/// 定義の終わりの言い回し。「…をいい、…を含む。」のように続くこともある
+const DEFINITION_ENDINGS: [&str; 2] = ["をいう", "をいい"];
+
+/// 号の形の定義規定で、号の番号と定義される語と定義を区切る空白
+const ITEM_SEPARATOR: char = '　';
+
+/// 文が定義規定なら、定義される語の部分それぞれと、それが文の何バイト目から始まるかを返す。
+/// 「「甲」とは、…をいい、「乙」とは、…をいう。」のように一つの文で複数の語を定義していれば、全ての語を返す。
+/// 「とは、」の前が「」」で終わっていれば、その括弧の中だけを定義される語とする。
+/// 「とは、」が無くても、「一　個人情報　…をいう。」のような号の形の定義規定なら、号の番号の後の語を返す
+pub fn defined_terms(sentence: &str) -> Vec<(usize, &str)> {
+  let markers = sentence
+    .match_indices(DEFINITION_MARKER)
+    .map(|(position, _)| position)
+    .collect::<Vec<_>>();
+  if markers.is_empty() {
+    return item_term(sentence).into_iter().collect();
+  }
+  let mut terms = vec![];
+  for (i, &position) in markers.iter().enumerate() {
+    let definition_end = markers.get(i + 1).copied().unwrap_or(sentence.len());
+    let definition = &sentence[position + DEFINITION_MARKER.len()..definition_end];
+    if !has_ending(definition) {
+      continue;
+    }
+    // 前の語の定義の終わり（「をいい、」の後）から、この「とは、」までを定義される語の部分とする
+    let term_start = match i.checked_sub(1) {
+      None => 0,
+      Some(k) => match last_ending_end(&sentence[markers[k]..position]) {
+        Some(end) => markers[k] + end,
+        None => continue,
+      },
+    };
+    if let Some(term) = term_of(sentence, term_start, position) {
+      terms.push(term);
+    }
+  }
+  terms
+}
+
+/// 定義の終わりの言い回しを含むかどうか
+fn has_ending(definition: &str) -> bool {
+  DEFINITION_ENDINGS
+    .iter()
+    .any(|ending| definition.contains(ending))
+}
+
+/// 最後の定義の終わりの言い回しと、その後の「、」の次のバイトの位置
+fn last_ending_end(text: &str) -> Option<usize> {
+  let end = DEFINITION_ENDINGS
+    .iter()
+    .filter_map(|ending| text.rfind(ending).map(|start| start + ending.len()))
+    .max()?;
+  Some(match text[end..].strip_prefix('、') {
+    Some(_) => end + '、'.len_utf8(),
+    None => end,
+  })
+}
+
+/// 文の`start`バイト目から`end`バイト目までの、定義される語の部分。括弧で終わっていればその中だけにする
+fn term_of(sentence: &str, start: usize, end: usize) -> Option<(usize, &str)> {
+  let term = &sentence[start..end];
+  match term.strip_suffix('」') {
+    Some(quoted) => quoted.rfind('「').map(|quote_start| {
+      let quote_start = quote_start + '「'.len_utf8();
+      (start + quote_start, &quoted[quote_start..])
+    }),
+    None if term.is_empty() => None,
+    None => Some((start, term)),
+  }
+}
+
+/// 「一　個人情報　…をいう。」や、号の番号を除いた「個人情報　…をいう。」の形の定義規定の、定義される語
+fn item_term(sentence: &str) -> Option<(usize, &str)> {
+  let mut start = 0;
+  let mut parts = sentence.split(ITEM_SEPARATOR);
+  let mut term = parts.next()?;
+  // 号の番号（「一」「十二」「イ」など）を飛ばす
+  if is_item_title(term) {
+    start += term.len() + ITEM_SEPARATOR.len_utf8();
+    term = parts.next()?;
+  }
+  let definition = &sentence[start + term.len()..];
+  if term.is_empty() || term.contains(['、', '。']) || !definition.starts_with(ITEM_SEPARATOR) {
+    return None;
+  }
+  if !has_ending(definition) {
+    return None;
+  }
+  Some((start, term))
+}
+
+/// 号の番号（「一」「十二の二」のような漢数字や、「イ」のような片仮名一文字）かどうか
+fn is_item_title(text: &str) -> bool {
+  let is_iroha = text.chars().count() == 1 && text.chars().all(|c| ('ァ'..='ヶ').contains(&c));
+  let is_kansuji = !text.is_empty()
+    && text
+      .chars()
+      .all(|c| "〇一二三四五六七八九十百千の".contains(c));
+  is_iroha || is_kansuji
+}
@@ -0,0 +1,273 @@
+//! 警告・エラーの一覧と、それぞれに割り当てた変わらないコード
+//!
+//! ログには`[W001] ...`のようにコードを先頭に付けて出力し、
+//! エラーの報告ファイルにはコードを`code`として書き出す。
+//! コードの付いた警告・エラーは全て[`report`]を通し、ログと報告ファイルの両方に出す。
+
+use crate::i18n::{lang, Lang};
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs::File, io::Write, path::PathBuf, sync::Mutex};
+use tracing::*;
+
+/// コードの付いた警告・エラーを1行1件のJSONLで書き出すファイル（`--error-report`）
+static REPORT_FILE: Mutex<Option<File>> = Mutex::new(None);
+
+/// 警告・エラーの種類
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum Code {
+  /// 法令ファイルを開けなかった
+  #[serde(rename = "E001")]
+  FileOpen,
+  /// 法令XMLの読み込み・解析に失敗した
+  #[serde(rename = "E002")]
+  XmlParse,
+  /// 法令ファイルが空（0バイト）だった
+  #[serde(rename = "E003")]
+  EmptyFile,
+  /// 法令XMLが途中で切れていた
+  #[serde(rename = "E004")]
+  TruncatedXml,
+  /// 法令XMLの要素の入れ子が深すぎるか、開始タグと終了タグが対応していなかった
+  #[serde(rename = "E005")]
+  MalformedNesting,
+  /// 検索した法令に対する処理（[`search_many_with`](crate::search_many_with)の`hook`）が失敗した
+  #[serde(rename = "E006")]
+  HookFailed,
+  /// 条・項・号などの要素に`Num`属性が無かった
+  #[serde(rename = "W001")]
+  MissingNumAttribute,
+  /// 法令番号に対応する法令ファイルが作業ディレクトリに無かった
+  #[serde(rename = "W002")]
+  LawFileNotFound,
+  /// e-Gov法令APIから条文を取得できなかった
+  #[serde(rename = "W003")]
+  ApiFetchFailed,
+  /// e-Gov法令APIから更新法令一覧を取得できなかった
+  #[serde(rename = "W004")]
+  UpdateListFetchFailed,
+  /// 検索し直した結果が記録された検索結果と一致しなかった
+  #[serde(rename = "W005")]
+  VerifyMismatch,
+  /// HTTPリクエストが失敗したので再試行した
+  #[serde(rename = "W006")]
+  HttpRetry,
+  /// 見積もったメモリの量が`--max-memory`を超えたので、テキストの取り出しをやめた
+  #[serde(rename = "W007")]
+  MemoryLimitExceeded,
+  /// 同じ法令ファイルか、同じ法令番号（と版）の法令を二度検索しようとした
+  #[serde(rename = "W008")]
+  DuplicateLaw,
+  /// XMLファイルの最上位の要素が`Law`ではなく、法令XMLではなかった
+  #[serde(rename = "W009")]
+  NotLawXml,
+}
+
+impl Code {
+  pub const ALL: [Code; 15] = [
+    Code::FileOpen,
+    Code::XmlParse,
+    Code::EmptyFile,
+    Code::TruncatedXml,
+    Code::MalformedNesting,
+    Code::HookFailed,
+    Code::MissingNumAttribute,
+    Code::LawFileNotFound,
+    Code::ApiFetchFailed,
+    Code::UpdateListFetchFailed,
+    Code::VerifyMismatch,
+    Code::HttpRetry,
+    Code::MemoryLimitExceeded,
+    Code::DuplicateLaw,
+    Code::NotLawXml,
+  ];
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Code::FileOpen => "E001",
+      Code::XmlParse => "E002",
+      Code::EmptyFile => "E003",
+      Code::TruncatedXml => "E004",
+      Code::MalformedNesting => "E005",
+      Code::HookFailed => "E006",
+      Code::MissingNumAttribute => "W001",
+      Code::LawFileNotFound => "W002",
+      Code::ApiFetchFailed => "W003",
+      Code::UpdateListFetchFailed => "W004",
+      Code::VerifyMismatch => "W005",
+      Code::HttpRetry => "W006",
+      Code::MemoryLimitExceeded => "W007",
+      Code::DuplicateLaw => "W008",
+      Code::NotLawXml => "W009",
+    }
+  }
+
+  /// コードの説明。`--lang`で選んだ言語で返す
+  pub fn description(&self) -> &'static str {
+    match lang() {
+      Lang::Ja => self.description_ja(),
+      Lang::En => self.description_en(),
+    }
+  }
+
+  fn description_ja(&self) -> &'static str {
+    match self {
+      Code::FileOpen => "法令ファイルを開けなかった",
+      Code::XmlParse => "法令XMLの読み込み・解析に失敗した",
+      Code::EmptyFile => "法令ファイルが空（0バイト）だった",
+      Code::TruncatedXml => "法令XMLが途中で切れていた",
+      Code::MalformedNesting => {
+        "法令XMLの要素の入れ子が深すぎるか、開始タグと終了タグが対応していなかった"
+      }
+      Code::HookFailed => "検索した法令に対する処理が失敗した",
+      Code::MissingNumAttribute => "条・項・号などの要素にNum属性が無かった",
+      Code::LawFileNotFound => "法令番号に対応する法令ファイルが作業ディレクトリに無かった",
+      Code::ApiFetchFailed => "e-Gov法令APIから条文を取得できなかった",
+      Code::UpdateListFetchFailed => "e-Gov法令APIから更新法令一覧を取得できなかった",
+      Code::VerifyMismatch => "検索し直した結果が記録された検索結果と一致しなかった",
+      Code::HttpRetry => "HTTPリクエストが失敗したので再試行した",
+      Code::MemoryLimitExceeded => {
+        "見積もったメモリの量が上限を超えたので、テキストの取り出しをやめた"
+      }
+      Code::DuplicateLaw => "同じ法令ファイルか、同じ法令番号の法令を二度検索しようとした",
+      Code::NotLawXml => "XMLファイルの最上位の要素がLawではなく、法令XMLではなかった",
+    }
+  }
+
+  fn description_en(&self) -> &'static str {
+    match self {
+      Code::FileOpen => "could not open the statute file",
+      Code::XmlParse => "failed to read or parse the statute XML",
+      Code::EmptyFile => "the statute file was empty (0 bytes)",
+      Code::TruncatedXml => "the statute XML was truncated",
+      Code::MalformedNesting => {
+        "the statute XML was nested too deeply or had mismatched start and end tags"
+      }
+      Code::HookFailed => "the processing of a searched law failed",
+      Code::MissingNumAttribute => "an article, paragraph or item element had no Num attribute",
+      Code::LawFileNotFound => "no statute file for the law number in the working directory",
+      Code::ApiFetchFailed => "could not fetch the text from the e-Gov law API",
+      Code::UpdateListFetchFailed => {
+        "could not fetch the list of updated laws from the e-Gov law API"
+      }
+      Code::VerifyMismatch => "the search result did not match the recorded result when re-run",
+      Code::HttpRetry => "an HTTP request failed and was retried",
+      Code::MemoryLimitExceeded => {
+        "the estimated memory usage exceeded the limit, so text capture was stopped"
+      }
+      Code::DuplicateLaw => "the same statute file or law number was about to be searched twice",
+      Code::NotLawXml => "the root element of the XML file was not Law, so it was not statute XML",
+    }
+  }
+
+  /// エラー（そのファイルの処理を続けられないもの）かどうか
+  pub fn is_error(&self) -> bool {
+    self.as_str().starts_with('E')
+  }
+}
+
+impl fmt::Display for Code {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+/// コードの一覧に載せる情報
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CodeInfo {
+  pub code: Code,
+  pub description: String,
+}
+
+/// 全てのコードとその説明の一覧
+pub fn code_catalog() -> Vec<CodeInfo> {
+  Code::ALL
+    .iter()
+    .map(|code| CodeInfo {
+      code: *code,
+      description: code.description().to_string(),
+    })
+    .collect()
+}
+
+/// コードの付いた警告・エラー一件
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Diagnostic {
+  pub code: Code,
+  pub message: String,
+  /// 問題のあった法令ファイル
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub file: Option<PathBuf>,
+}
+
+impl Diagnostic {
+  pub fn new(code: Code, message: impl Into<String>) -> Self {
+    Diagnostic {
+      code,
+      message: message.into(),
+      file: None,
+    }
+  }
+
+  pub fn with_file(self, file: impl Into<PathBuf>) -> Self {
+    Diagnostic {
+      file: Some(file.into()),
+      ..self
+    }
+  }
+
+  /// `anyhow::Error`からコードの付いたエラーを作る。
+  /// 中身が`Diagnostic`ならそのコードを、そうでなければ`code`を使う
+  pub fn from_error(code: Code, err: &anyhow::Error) -> Self {
+    match err.downcast_ref::<Diagnostic>() {
+      Some(diagnostic) => diagnostic.clone(),
+      None => Diagnostic::new(code, err.to_string()),
+    }
+  }
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.file {
+      Some(file) => write!(f, "[{}] {}: {:?}", self.code, self.message, file),
+      None => write!(f, "[{}] {}", self.code, self.message),
+    }
+  }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// [`report`]で警告・エラーを書き出すファイルを決める
+pub fn set_report_file(file: File) {
+  if let Ok(mut report_file) = REPORT_FILE.lock() {
+    *report_file = Some(file);
+  }
+}
+
+/// コードの付いた警告・エラーをログに出し、報告ファイルがあればそこにも1行のJSONとして書き出す。
+/// ログには`--lang`で選んだ言語のコードの説明を付ける。詳しい内容（`message`）は翻訳しない
+pub fn report(diagnostic: &Diagnostic) {
+  let description = diagnostic.code.description();
+  if diagnostic.code.is_error() {
+    error!("{} ({})", diagnostic, description);
+  } else {
+    warn!("{} ({})", diagnostic, description);
+  }
+  let mut report_file = match REPORT_FILE.lock() {
+    Ok(report_file) => report_file,
+    Err(_) => return,
+  };
+  if let Some(file) = report_file.as_mut() {
+    let result = serde_json::to_string(diagnostic)
+      .map_err(std::io::Error::from)
+      .and_then(|diagnostic_json_str| writeln!(file, "{diagnostic_json_str}"))
+      .and_then(|()| file.flush());
+    if let Err(e) = result {
+      error!("failed to write the error report: {}", e);
+    }
+  }
+}
+
+/// コード`code`の警告をログと報告ファイルに出す
+pub fn report_warning(code: Code, message: impl Into<String>) {
+  report(&Diagnostic::new(code, message));
+}
@@ -0,0 +1,171 @@
+//! 二つの検索結果の比較
+//!
+//! 検索語が見つかった箇所をIDで突き合わせ、片方にしか無いものを追加・削除として報告する。
+//! 追加と削除の組のうち、条の見出しと検索語などが同じで条番号だけが違うものは、
+//! 改正で条番号が変わった（移動した）ものとして報告する。
+
+use crate::{align::ArticlePair, Chapter, LawParagraph, Match};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// 検索語が見つかった箇所の変化の種類
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+  /// 新しい検索結果にだけある
+  Added,
+  /// 古い検索結果にだけある
+  Removed,
+  /// 条番号が変わった
+  Moved,
+}
+
+/// 検索語が見つかった箇所一つの変化
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct DiffEntry {
+  pub kind: DiffKind,
+  /// 法令番号
+  pub num: String,
+  pub word: String,
+  /// 古い検索結果での箇所
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub old: Option<Match>,
+  /// 新しい検索結果での箇所
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub new: Option<Match>,
+}
+
+/// 古い箇所と新しい箇所が、条番号だけが変わった同じ箇所かどうか。
+/// 条の見出しがあり、それと検索語・条番号以外の位置情報が同じときにそうみなす
+fn is_moved(old_num: &str, old_match: &Match, new_num: &str, new_match: &Match) -> bool {
+  old_num == new_num
+    && old_match.word == new_match.word
+    && old_match.article_caption.is_some()
+    && old_match.article_caption == new_match.article_caption
+    && old_match.chapter.article != new_match.chapter.article
+    && is_same_position_in_article(&old_match.chapter, &new_match.chapter)
+}
+
+/// 附則の改正法令番号と、条の中での項・号の位置が同じかどうか
+fn is_same_position_in_article(a: &Chapter, b: &Chapter) -> bool {
+  a.suppl_provision_title == b.suppl_provision_title
+    && a.paragraph == b.paragraph
+    && a.item == b.item
+    && a.sub_item == b.sub_item
+}
+
+/// 古い検索結果と新しい検索結果を比べ、変化した箇所の一覧を返す
+pub fn diff_results(old: &[LawParagraph], new: &[LawParagraph]) -> Vec<DiffEntry> {
+  let flatten = |lst: &[LawParagraph]| {
+    lst
+      .iter()
+      .flat_map(|law| {
+        law
+          .matches
+          .iter()
+          .map(move |m| (law.num.clone(), m.clone()))
+      })
+      .collect::<Vec<_>>()
+  };
+  let old_matches = flatten(old);
+  let new_matches = flatten(new);
+  let old_ids = old_matches
+    .iter()
+    .map(|(_, m)| m.id.as_str())
+    .collect::<HashSet<_>>();
+  let new_ids = new_matches
+    .iter()
+    .map(|(_, m)| m.id.as_str())
+    .collect::<HashSet<_>>();
+  let removed = old_matches
+    .iter()
+    .filter(|(_, m)| !new_ids.contains(m.id.as_str()))
+    .collect::<Vec<_>>();
+  let mut added = new_matches
+    .iter()
+    .filter(|(_, m)| !old_ids.contains(m.id.as_str()))
+    .map(Some)
+    .collect::<Vec<_>>();
+
+  let mut entries = vec![];
+  for (num, old_match) in removed {
+    let moved_index = added.iter().position(|candidate| {
+      matches!(candidate, Some((new_num, new_match)) if is_moved(num, old_match, new_num, new_match))
+    });
+    match moved_index.and_then(|i| added[i].take()) {
+      Some((_, new_match)) => entries.push(DiffEntry {
+        kind: DiffKind::Moved,
+        num: num.clone(),
+        word: old_match.word.clone(),
+        old: Some(old_match.clone()),
+        new: Some(new_match.clone()),
+      }),
+      None => entries.push(DiffEntry {
+        kind: DiffKind::Removed,
+        num: num.clone(),
+        word: old_match.word.clone(),
+        old: Some(old_match.clone()),
+        new: None,
+      }),
+    }
+  }
+  for (num, new_match) in added.into_iter().flatten() {
+    entries.push(DiffEntry {
+      kind: DiffKind::Added,
+      num: num.clone(),
+      word: new_match.word.clone(),
+      old: None,
+      new: Some(new_match.clone()),
+    });
+  }
+  entries.sort();
+  entries
+}
+
+/// 法令`num`の条の対応付けをもとに、削除と追加の組のうち対応する条の同じ位置にあるものを移動にする
+pub fn apply_alignment(
+  entries: Vec<DiffEntry>,
+  num: &str,
+  pairs: &[ArticlePair],
+) -> Vec<DiffEntry> {
+  let mut entries = entries.into_iter().map(Some).collect::<Vec<_>>();
+  for i in 0..entries.len() {
+    let old_match = match &entries[i] {
+      Some(DiffEntry {
+        kind: DiffKind::Removed,
+        num: entry_num,
+        old: Some(old_match),
+        ..
+      }) if entry_num == num => old_match.clone(),
+      _ => continue,
+    };
+    let new_article = match pairs.iter().find(|pair| {
+      pair.old_article == old_match.chapter.article
+        && pair.suppl_provision_title == old_match.chapter.suppl_provision_title
+    }) {
+      Some(pair) => &pair.new_article,
+      None => continue,
+    };
+    let added_index = entries.iter().position(|entry| {
+      matches!(entry, Some(DiffEntry {
+        kind: DiffKind::Added,
+        num: entry_num,
+        new: Some(new_match),
+        ..
+      }) if entry_num == num
+        && &new_match.chapter.article == new_article
+        && new_match.word == old_match.word
+        && is_same_position_in_article(&old_match.chapter, &new_match.chapter))
+    });
+    if let Some(j) = added_index {
+      let new_match = entries[j].take().and_then(|entry| entry.new);
+      if let Some(entry) = &mut entries[i] {
+        entry.kind = DiffKind::Moved;
+        entry.new = new_match;
+      }
+    }
+  }
+  let mut entries = entries.into_iter().flatten().collect::<Vec<_>>();
+  entries.sort();
+  entries
+}
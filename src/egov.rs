@@ -0,0 +1,171 @@
+//! [e-Gov法令API](https://elaws.e-gov.go.jp/apitop/)との連携
+
+use crate::{
+  citation::article_title, date::Date, http::HttpClient, hydrate::hydrate_chapter, Chapter,
+};
+use anyhow::{anyhow, Result};
+use encoding_rs::Encoding;
+use quick_xml::{encoding, events::Event, Reader};
+use serde::{Deserialize, Serialize};
+
+/// e-Gov法令API（Version 1）のURL
+pub const API_BASE_URL: &str = "https://elaws.e-gov.go.jp/api/1";
+
+/// e-Gov法令検索で法令IDの法令を表示するページのURL
+pub fn law_page_url(law_id: &str) -> String {
+  format!("https://elaws.e-gov.go.jp/document?lawid={law_id}")
+}
+
+/// 条文内容取得APIで、法令番号と条を指定して条文を取得するURL
+pub fn article_url(law_num: &str, chapter: &Chapter) -> Result<String> {
+  let article = article_title(&chapter.article)
+    .ok_or_else(|| anyhow!("条番号を変換できません: {:?}", chapter.article))?;
+  Ok(format!(
+    "{API_BASE_URL}/articles;lawNum={law_num};article={article}"
+  ))
+}
+
+/// APIの結果のXMLの`Result`要素を見て、エラーであればその内容を返す
+pub fn check_api_result(xml: &[u8]) -> Result<()> {
+  let utf8 = Encoding::for_label(b"utf-8").unwrap();
+  let mut reader = Reader::from_reader(xml);
+  reader.trim_text(true);
+  let mut buf = Vec::new();
+  let mut tag_name = Vec::new();
+  let mut code = String::new();
+  let mut message = String::new();
+  loop {
+    match reader.read_event_into(&mut buf)? {
+      Event::Start(tag) => tag_name = tag.name().as_ref().to_vec(),
+      Event::Text(text) => match tag_name.as_slice() {
+        b"Code" => code = encoding::decode(&text.into_inner(), utf8)?.to_string(),
+        b"Message" => message = encoding::decode(&text.into_inner(), utf8)?.to_string(),
+        _ => (),
+      },
+      Event::End(tag) if tag.name().as_ref() == b"Result" => break,
+      Event::Eof => break,
+      _ => (),
+    }
+    buf.clear();
+  }
+  if code == "0" {
+    Ok(())
+  } else {
+    Err(anyhow!("e-Gov法令APIのエラー（{code}）: {message}"))
+  }
+}
+
+/// 条文内容取得APIから条を取得し、その中の条項のテキストを取り出す。
+/// ローカルに法令XMLが無い環境でも、保存しておいた検索結果から条文を取り出すのに使う。
+///
+/// 附則の条項は取得できない。
+pub async fn fetch_chapter_text(
+  client: &HttpClient,
+  law_num: &str,
+  chapter: &Chapter,
+) -> Result<String> {
+  if chapter.suppl_provision_title.is_some() {
+    return Err(anyhow!("附則の条文はe-Gov法令APIから取得できません"));
+  }
+  let url = article_url(law_num, chapter)?;
+  let body = client.get_bytes(&url).await?;
+  check_api_result(&body)?;
+  // APIの結果には編・章などの要素が含まれないので、条以下の位置情報だけで探す
+  let target = Chapter {
+    article: chapter.article.clone(),
+    paragraph: chapter.paragraph.clone(),
+    item: chapter.item.clone(),
+    sub_item: chapter.sub_item.clone(),
+    ..Default::default()
+  };
+  let mut reader = Reader::from_reader(body.as_slice());
+  hydrate_chapter(&target, &mut reader).await
+}
+
+/// 法令一覧情報取得API（更新法令）の結果の一件分
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Default, Deserialize, Serialize)]
+pub struct UpdatedLaw {
+  /// 法令ID
+  pub law_id: String,
+  /// 法令番号
+  pub law_num: String,
+  /// 法令名
+  pub law_name: String,
+  /// 施行日（YYYYMMDD）
+  pub enforcement_date: String,
+}
+
+/// 指定した日に更新された法令の一覧を取得するURL
+pub fn update_law_list_url(date: &Date) -> String {
+  format!("{API_BASE_URL}/updatelawlists/{date}")
+}
+
+/// 法令一覧情報取得API（更新法令）の結果のXMLを読む
+pub fn parse_update_law_list(xml: &[u8]) -> Result<Vec<UpdatedLaw>> {
+  let utf8 = Encoding::for_label(b"utf-8").unwrap();
+  let mut reader = Reader::from_reader(xml);
+  reader.trim_text(true);
+  let mut buf = Vec::new();
+  let mut tag_name = Vec::new();
+  let mut lst = vec![];
+  let mut updated_law = UpdatedLaw::default();
+  loop {
+    match reader.read_event_into(&mut buf)? {
+      Event::Start(tag) => {
+        tag_name = tag.name().as_ref().to_vec();
+        if tag_name == b"LawNameListInfo" {
+          updated_law = UpdatedLaw::default();
+        }
+      }
+      Event::End(tag) => {
+        if tag.name().as_ref() == b"LawNameListInfo" {
+          lst.push(updated_law.clone());
+        }
+        tag_name.clear();
+      }
+      Event::Text(text) => {
+        let text_str = encoding::decode(&text.into_inner(), utf8)?.to_string();
+        match tag_name.as_slice() {
+          b"LawId" => updated_law.law_id = text_str,
+          b"LawNo" => updated_law.law_num = text_str,
+          b"LawName" => updated_law.law_name = text_str,
+          b"EnforcementDate" => updated_law.enforcement_date = text_str,
+          _ => (),
+        }
+      }
+      Event::Eof => break,
+      _ => (),
+    }
+    buf.clear();
+  }
+  Ok(lst)
+}
+
+/// 指定した日に更新された法令の一覧を取得する
+pub async fn fetch_updated_laws(client: &HttpClient, date: &Date) -> Result<Vec<UpdatedLaw>> {
+  let body = client.get_bytes(&update_law_list_url(date)).await?;
+  check_api_result(&body)?;
+  parse_update_law_list(&body)
+}
+
+/// 法令取得APIから、法令IDで指定した法令の最新のXMLを取得する。
+/// 返すのは`Law`要素の部分にXML宣言を付けたもの。
+pub async fn fetch_law_xml(client: &HttpClient, law_id: &str) -> Result<String> {
+  let body = client
+    .get_bytes(&format!("{API_BASE_URL}/lawdata/{law_id}"))
+    .await?;
+  check_api_result(&body)?;
+  let xml = std::str::from_utf8(&body)?;
+  let start = xml
+    .find("<Law ")
+    .or_else(|| xml.find("<Law>"))
+    .ok_or_else(|| anyhow!("Law要素が見つかりません: {law_id}"))?;
+  let end = xml
+    .rfind("</Law>")
+    .ok_or_else(|| anyhow!("Law要素が見つかりません: {law_id}"))?
+    + "</Law>".len();
+  Ok(format!(
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}\n",
+    &xml[start..end]
+  ))
+}
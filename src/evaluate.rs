@@ -0,0 +1,116 @@
+//! 人手で正解を付けた箇所の一覧（gold set）に対する、検索結果の適合率と再現率
+//!
+//! 正解の一覧は一行に一つの`{"id": 見つかった箇所のID, "relevant": 正解かどうか}`を書いたJSONLで、
+//! `relevant`を省略したときは正解とする。IDは[`match_id`](crate::match_id)で作るもので、
+//! 表記のそろえ方や照らし合わせ方を変えて検索し直しても、同じ箇所なら同じになる。
+
+use crate::LawParagraph;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::{BTreeMap, HashSet},
+  path::Path,
+};
+
+/// 正解の一覧の一行
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct GoldLabel {
+  /// 見つかった箇所のID
+  pub id: String,
+  /// 見つかるべき箇所かどうか
+  #[serde(default = "is_relevant_default")]
+  pub relevant: bool,
+}
+
+fn is_relevant_default() -> bool {
+  true
+}
+
+/// 正解の一覧の読み込み
+pub async fn read_gold(path: &Path) -> Result<Vec<GoldLabel>> {
+  let s = tokio::fs::read_to_string(path).await?;
+  s.lines()
+    .enumerate()
+    .filter(|(_, line)| !line.trim().is_empty())
+    .map(|(i, line)| {
+      serde_json::from_str(line)
+        .map_err(|e| anyhow!("正解の一覧の{}行目を解析できません: {e}", i + 1))
+    })
+    .collect()
+}
+
+/// 検索結果を正解の一覧と比べた結果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EvaluationReport {
+  /// 検索結果にあり、正解とされた箇所の数
+  pub true_positive: usize,
+  /// 検索結果にあり、正解でないとされた箇所の数
+  pub false_positive: usize,
+  /// 正解とされたが、検索結果に無い箇所の数
+  pub false_negative: usize,
+  /// 検索結果にあるが、正解の一覧に無い（判定されていない）箇所の数。適合率の計算には含めない
+  pub unjudged: usize,
+  /// 適合率。判定された箇所が検索結果に一つも無ければ無い
+  pub precision: Option<f64>,
+  /// 再現率。正解とされた箇所が一つも無ければ無い
+  pub recall: Option<f64>,
+  /// 適合率と再現率の調和平均
+  pub f1: Option<f64>,
+  /// 検索結果にあり、正解でないとされた箇所のID
+  pub false_positive_ids: Vec<String>,
+  /// 正解とされたが、検索結果に無い箇所のID
+  pub false_negative_ids: Vec<String>,
+}
+
+fn ratio(numerator: usize, denominator: usize) -> Option<f64> {
+  if denominator == 0 {
+    None
+  } else {
+    Some(numerator as f64 / denominator as f64)
+  }
+}
+
+/// 検索結果の見つかった箇所のIDを正解の一覧と比べる。同じIDが正解の一覧に何度もあるときは、後のものを使う
+pub fn evaluate(results: &[LawParagraph], gold: &[GoldLabel]) -> EvaluationReport {
+  let retrieved = results
+    .iter()
+    .flat_map(|law_paragraph| law_paragraph.matches.iter().map(|m| m.id.as_str()))
+    .collect::<HashSet<_>>();
+  let mut labels = BTreeMap::new();
+  for label in gold.iter() {
+    labels.insert(label.id.as_str(), label.relevant);
+  }
+  let mut true_positive = 0;
+  let mut false_positive_ids = vec![];
+  let mut false_negative_ids = vec![];
+  for (id, relevant) in labels.iter() {
+    match (retrieved.contains(id), *relevant) {
+      (true, true) => true_positive += 1,
+      (true, false) => false_positive_ids.push(id.to_string()),
+      (false, true) => false_negative_ids.push(id.to_string()),
+      (false, false) => (),
+    }
+  }
+  let unjudged = retrieved
+    .iter()
+    .filter(|id| !labels.contains_key(*id))
+    .count();
+  let precision = ratio(true_positive, true_positive + false_positive_ids.len());
+  let recall = ratio(true_positive, true_positive + false_negative_ids.len());
+  let f1 = match (precision, recall) {
+    (Some(p), Some(r)) if p + r > 0.0 => Some(2.0 * p * r / (p + r)),
+    (Some(_), Some(_)) => Some(0.0),
+    _ => None,
+  };
+  EvaluationReport {
+    true_positive,
+    false_positive: false_positive_ids.len(),
+    false_negative: false_negative_ids.len(),
+    unjudged,
+    precision,
+    recall,
+    f1,
+    false_positive_ids,
+    false_negative_ids,
+  }
+}
@@ -0,0 +1,96 @@
+//! 見つかった箇所を[rhai](https://rhai.rs/)の式で絞り込む（`filter-expr` feature）
+//!
+//! 式は見つかった箇所一つごとに評価し、`true`になった箇所だけを残す。式の中では次の変数を使える。
+//!
+//! - `law_num`：法令番号
+//! - `title`：法令名（無ければ空文字列）
+//! - `word`：見つかった検索語
+//! - `synonym_of`：`--synonyms`の同義語で見つかったときの元の検索語（無ければ空文字列）
+//! - `scope`：本則なら`"main"`、附則なら`"suppl"`
+//! - `article`・`paragraph`・`item`：条・項・号の番号（無ければ空文字列）
+//! - `caption`：条の見出し（無ければ空文字列）
+//! - `count`：その条項で検索語が見つかった回数
+//! - `tags`：`--plugin`のプラグインが付けたタグの配列
+
+use crate::{LawParagraph, Match};
+use anyhow::{anyhow, Result};
+use rhai::{Array, Dynamic, Engine, Scope, AST};
+
+/// 式一つを一度評価するときの、演算の回数の上限。際限なく続く式で止まらないようにする
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// 式の入れ子の深さの上限
+const MAX_EXPR_DEPTH: usize = 64;
+
+/// 解析した絞り込みの式
+pub struct FilterExpr {
+  engine: Engine,
+  ast: AST,
+}
+
+impl FilterExpr {
+  /// 式を解析する。式の入れ子の深さと、評価するときの演算の回数には上限を設ける
+  pub fn compile(source: &str) -> Result<Self> {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_expr_depths(MAX_EXPR_DEPTH, MAX_EXPR_DEPTH);
+    let ast = engine
+      .compile_expression(source)
+      .map_err(|e| anyhow!("`--filter-expr`の式を解析できません: {e}"))?;
+    Ok(FilterExpr { engine, ast })
+  }
+
+  /// 見つかった箇所`m`を残すかどうか。`count`はその条項で検索語が見つかった回数
+  fn is_kept(&self, law_paragraph: &LawParagraph, m: &Match, count: usize) -> Result<bool> {
+    let text = |s: Option<&String>| s.cloned().unwrap_or_default();
+    let mut scope = Scope::new();
+    scope.push("law_num", law_paragraph.num.clone());
+    scope.push("title", text(law_paragraph.title.as_ref()));
+    scope.push("word", m.word.clone());
+    scope.push("synonym_of", text(m.synonym_of.as_ref()));
+    let provision = if m.chapter.suppl_provision_title.is_some() {
+      "suppl"
+    } else {
+      "main"
+    };
+    scope.push("scope", provision.to_string());
+    scope.push("article", m.chapter.article.clone());
+    scope.push("paragraph", text(m.chapter.paragraph.as_ref()));
+    scope.push("item", text(m.chapter.item.as_ref()));
+    scope.push("caption", text(m.article_caption.as_ref()));
+    scope.push("count", count as i64);
+    let tags = m.tags.iter().cloned().map(Dynamic::from).collect::<Array>();
+    scope.push("tags", tags);
+    self
+      .engine
+      .eval_ast_with_scope::<bool>(&mut scope, &self.ast)
+      .map_err(|e| {
+        anyhow!(
+          "`--filter-expr`の式を評価できません（{}）: {e}",
+          law_paragraph.num
+        )
+      })
+  }
+}
+
+/// 法令一つ分の見つかった箇所のうち、式が`true`になるものだけを残す
+pub fn apply_filter(filter: &FilterExpr, mut law_paragraph: LawParagraph) -> Result<LawParagraph> {
+  let is_kept = law_paragraph
+    .matches
+    .iter()
+    .map(|m| {
+      let count = law_paragraph
+        .chapter_data
+        .iter()
+        .position(|chapter| chapter == &m.chapter)
+        .and_then(|i| law_paragraph.hit_counts.get(i))
+        .copied()
+        .unwrap_or(0);
+      filter.is_kept(&law_paragraph, m, count)
+    })
+    .collect::<Result<Vec<_>>>()?;
+  law_paragraph.is_filtered |= is_kept.contains(&false);
+  let mut is_kept = is_kept.into_iter();
+  law_paragraph.retain_matches(|_| is_kept.next().unwrap_or(true));
+  Ok(law_paragraph)
+}
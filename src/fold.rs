@@ -0,0 +1,215 @@
+//! 検索語とテキストを照らし合わせる前に、表記の違いをそろえる
+
+use crate::{kansuji::parse_number, kyujitai::to_shinjitai};
+use serde::Serialize;
+
+/// どの表記の違いをそろえるか
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+pub struct Folding {
+  /// 全角の英数字・記号を半角にし、英字を小文字にする
+  pub is_width_case: bool,
+  /// 漢数字と全角・半角のアラビア数字で書かれた数を、その値の半角のアラビア数字にする（「第二種」と「第２種」を`第2種`にする）
+  pub is_numerals: bool,
+  /// 漢字に続く送り仮名を除く（「取り消し」「取消し」「取消」を`取消`にする）
+  pub is_okurigana: bool,
+  /// 片仮名を平仮名にする（「スル」「トキ」を`する`・`とき`にする）
+  pub is_kana: bool,
+  /// 旧字体の漢字を新字体にする（「國」「廳」を`国`・`庁`にする）
+  pub is_old_kanji: bool,
+}
+
+/// 数を表す文字かどうか。続けて現れたものをまとめて一つの数として読む
+fn is_numeral(c: char) -> bool {
+  matches!(
+    c,
+    '0'..='9' | '０'..='９' | '〇' | '一' | '二' | '三' | '四' | '五' | '六' | '七' | '八' | '九' | '十' | '百' | '千' | '万'
+  )
+}
+
+/// 漢字かどうか
+fn is_kanji(c: char) -> bool {
+  matches!(c, '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '々')
+}
+
+fn is_hiragana(c: char) -> bool {
+  matches!(c, '\u{3041}'..='\u{309F}')
+}
+
+/// 送り仮名として除く平仮名。助詞（「の」「に」「を」など）や、活用語尾として変わらない仮名は含めない
+const OKURIGANA: &str = "いえきぎけげしじせちつびべみめりれわ";
+
+/// 除く送り仮名の長さの上限
+const MAX_OKURIGANA_LEN: usize = 2;
+
+/// テキストの各文字を`fold`でそろえたときに、除く送り仮名になるかどうか。
+/// 漢字のすぐ後の平仮名の一続きで、長さが[`MAX_OKURIGANA_LEN`]以下、どれも[`OKURIGANA`]の仮名で、
+/// 平仮名でない文字かテキストの終わりが続くものを除く（「取り扱い、」の「り」と「い」）
+fn okurigana_flags(text: &str, fold: impl Fn(char) -> char) -> Vec<bool> {
+  let chars = text.chars().map(fold).collect::<Vec<_>>();
+  let mut flags = vec![false; chars.len()];
+  let mut i = 0;
+  while i < chars.len() {
+    if !is_kanji(chars[i]) {
+      i += 1;
+      continue;
+    }
+    let start = i + 1;
+    let mut end = start;
+    while end < chars.len() && is_hiragana(chars[end]) {
+      end += 1;
+    }
+    let len = end - start;
+    if (1..=MAX_OKURIGANA_LEN).contains(&len)
+      && chars[start..end].iter().all(|c| OKURIGANA.contains(*c))
+    {
+      for flag in flags[start..end].iter_mut() {
+        *flag = true;
+      }
+    }
+    i = end.max(i + 1);
+  }
+  flags
+}
+
+impl Folding {
+  /// そろえるものが何も無いかどうか
+  pub fn is_empty(&self) -> bool {
+    !self.is_width_case
+      && !self.is_numerals
+      && !self.is_okurigana
+      && !self.is_kana
+      && !self.is_old_kanji
+  }
+
+  /// 一文字をそろえる。数はまとめて読むので、ここではそろえない
+  pub fn fold_char(&self, c: char) -> char {
+    let mut c = c;
+    if self.is_width_case {
+      c = match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        _ => c,
+      }
+      .to_ascii_lowercase();
+    }
+    if self.is_kana {
+      c = match c {
+        // 「ァ」から「ヶ」までと、繰り返し記号の「ヽ」「ヾ」
+        '\u{30A1}'..='\u{30F6}' | '\u{30FD}' | '\u{30FE}' => {
+          char::from_u32(c as u32 - 0x60).unwrap_or(c)
+        }
+        _ => c,
+      };
+    }
+    if self.is_old_kanji {
+      c = to_shinjitai(c).unwrap_or(c);
+    }
+    c
+  }
+
+  /// テキストを、そろえた文字列とそれが元のテキストの何バイト目から来たかの組に分ける。
+  /// 数は一続きで一つの組にし、`keep`に当てはまる文字はそろえずに残す。除いた送り仮名は組にしない
+  fn fold_segments(&self, text: &str, keep: impl Fn(char) -> bool) -> Vec<(usize, String)> {
+    let mut segments = vec![];
+    let mut numeral_start = None;
+    let okurigana = if self.is_okurigana {
+      // 片仮名の送り仮名（「取リ消シ」）も、平仮名にしてから見つける
+      okurigana_flags(text, |c| self.fold_char(c))
+    } else {
+      vec![]
+    };
+    let push_numeral = |segments: &mut Vec<(usize, String)>, start: usize, end: usize| {
+      match parse_number(&text[start..end]) {
+        Some(n) => segments.push((start, n.to_string())),
+        // 「十二三」のように一つの数として読めないものは、一文字ずつそろえる
+        None => segments.extend(
+          text[start..end]
+            .char_indices()
+            .map(|(i, c)| (start + i, self.fold_char(c).to_string())),
+        ),
+      }
+    };
+    for (n, (i, c)) in text.char_indices().enumerate() {
+      if self.is_numerals && is_numeral(c) && !keep(c) {
+        numeral_start.get_or_insert(i);
+        continue;
+      }
+      if let Some(start) = numeral_start.take() {
+        push_numeral(&mut segments, start, i);
+      }
+      if okurigana.get(n).copied().unwrap_or(false) && !keep(c) {
+        continue;
+      }
+      let c = if keep(c) { c } else { self.fold_char(c) };
+      segments.push((i, c.to_string()));
+    }
+    if let Some(start) = numeral_start {
+      push_numeral(&mut segments, start, text.len());
+    }
+    segments
+  }
+
+  /// テキストをそろえる
+  pub fn fold(&self, text: &str) -> String {
+    if !self.is_numerals && !self.is_okurigana {
+      return text.chars().map(|c| self.fold_char(c)).collect();
+    }
+    self
+      .fold_segments(text, |_| false)
+      .into_iter()
+      .map(|(_, s)| s)
+      .collect()
+  }
+
+  /// 検索語をそろえる。`keep`に当てはまる文字（ワイルドカードの`＊`・`？`など）はそろえずに残す
+  pub fn fold_word(&self, word: &str, keep: impl Fn(char) -> bool) -> String {
+    self
+      .fold_segments(word, keep)
+      .into_iter()
+      .map(|(_, s)| s)
+      .collect()
+  }
+
+  /// そろえたテキスト`folded`の中の範囲が、数の一部分から始まったり、数の一部分で終わったりしていないかどうか。
+  /// 数をそろえないときは常に`true`を返す（「2」で「21」の「2」を見つけないようにする）
+  pub fn is_whole_numbers(&self, folded: &str, (start, end): (usize, usize)) -> bool {
+    !self.is_numerals || (!is_inside_number(folded, start) && !is_inside_number(folded, end))
+  }
+
+  /// テキストをそろえ、そろえたテキストの各バイトが元のテキストの何バイト目の文字（数ならその先頭）から来たかも返す。
+  /// 位置の一覧の末尾には、終端として元のテキストの長さを入れる
+  pub fn fold_with_offsets(&self, text: &str) -> (String, Vec<usize>) {
+    let mut folded = String::with_capacity(text.len());
+    let mut offsets = Vec::with_capacity(text.len() + 1);
+    for (i, s) in self.fold_segments(text, |_| false) {
+      folded.push_str(&s);
+      offsets.extend(std::iter::repeat(i).take(s.len()));
+    }
+    offsets.push(text.len());
+    (folded, offsets)
+  }
+}
+
+/// そろえたテキスト`folded`の`position`バイト目が、半角のアラビア数字の一続きの途中かどうか
+fn is_inside_number(folded: &str, position: usize) -> bool {
+  let bytes = folded.as_bytes();
+  position > 0
+    && position < bytes.len()
+    && bytes[position - 1].is_ascii_digit()
+    && bytes[position].is_ascii_digit()
+}
+
+/// そろえたテキストの中の範囲を、`fold_with_offsets`の位置の一覧を使って元のテキストの範囲にする。
+/// 数の途中で始まったり終わったりする範囲は、その数全体を含むように広げる
+pub fn original_range(offsets: &[usize], (start, end): (usize, usize)) -> (usize, usize) {
+  if start >= end {
+    return (offsets[start], offsets[start]);
+  }
+  let last = offsets[end - 1];
+  let original_end = offsets[end..]
+    .iter()
+    .find(|offset| **offset != last)
+    .copied()
+    .unwrap_or(last);
+  (offsets[start], original_end)
+}
@@ -0,0 +1,199 @@
+//! 検索結果の出力形式
+
+use crate::{
+  abbreviation::Abbreviations,
+  citation::{chapter_title, chapter_title_with, NumberStyle},
+  Chapter, LawParagraph, Match,
+};
+use std::collections::HashSet;
+
+/// `--output`に書き出す形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum OutputFormat {
+  /// 法令ごとの条項の位置情報のJSON配列
+  Json,
+  /// 法令ごとに条項を一覧にした、人が読むための形式
+  Pretty,
+  /// 「法令名:条項:検索語:ID」の形で1行に1箇所を書く、grepの出力に似た形式
+  Grep,
+  /// [Lawtext](https://github.com/yamachig/Lawtext)の記法で、法令名・法令番号と条項を書く形式
+  Lawtext,
+  /// `--output`のディレクトリに、法令の一覧と法令ごとのページを持つ静的なWebサイトを作る
+  Site,
+  /// `--dsn`で指定したPostgreSQLのデータベースに書き込む（`postgres` feature）
+  #[cfg(feature = "postgres")]
+  Postgres,
+  /// 見つかった箇所のシートと法令ごとの件数のシートを持つExcelのファイル（`xlsx` feature）
+  #[cfg(feature = "xlsx")]
+  Xlsx,
+}
+
+/// 法令一つ分の検索結果を`Pretty`形式の文字列にする
+pub fn to_pretty(
+  law_paragraph: &LawParagraph,
+  abbreviations: &Abbreviations,
+  number_style: NumberStyle,
+) -> String {
+  let mut s = match abbreviations.get(&law_paragraph.num) {
+    Some(name) => format!("{name}（{}）", law_paragraph.num),
+    None => law_paragraph.num.clone(),
+  };
+  if let Some(version) = &law_paragraph.version {
+    s.push_str(&format!(" [{version}]"));
+  }
+  s.push('\n');
+  if !law_paragraph.matched_words.is_empty() {
+    s.push_str(&format!(
+      "  含まれていた単語：{}\n",
+      law_paragraph.matched_words.join("、")
+    ));
+  }
+  for chapter in law_paragraph.chapter_data.iter() {
+    s.push_str(&format!(
+      "  {}\n",
+      chapter_title_with(chapter, number_style)
+    ));
+    for m in law_paragraph
+      .matches
+      .iter()
+      .filter(|m| &m.chapter == chapter)
+    {
+      s.push_str(&format!("    {} [{}]\n", m.word, m.id));
+    }
+  }
+  s
+}
+
+/// 法令一つ分の検索結果を`Lawtext`形式の文字列にする。
+/// 法令名と括弧書きの法令番号の後に、条項を1行に一つずつ条の見出しと共に書く。
+/// 附則の条項は、附則ごとに「附　則」の行の後にまとめる
+pub fn to_lawtext(law_paragraph: &LawParagraph, abbreviations: &Abbreviations) -> String {
+  let title = match &law_paragraph.title {
+    Some(title) => title.as_str(),
+    None => abbreviations.display_name(&law_paragraph.num),
+  };
+  let mut s = format!("{title}\n（{}）\n", law_paragraph.num);
+  // 本則の条項を先に書く
+  let mut chapters = law_paragraph.chapter_data.iter().collect::<Vec<_>>();
+  chapters.sort_by_key(|chapter| chapter.suppl_provision_title.is_some());
+  let mut suppl_provision_title = None;
+  let mut article = None;
+  for chapter in chapters {
+    if chapter.suppl_provision_title.is_some()
+      && suppl_provision_title != Some(&chapter.suppl_provision_title)
+    {
+      suppl_provision_title = Some(&chapter.suppl_provision_title);
+      article = None;
+      s.push_str("\n      附　則");
+      if let Some(title) = &chapter.suppl_provision_title {
+        s.push_str(&format!("　（{title}）"));
+      }
+      s.push('\n');
+    }
+    if article != Some(&chapter.article) {
+      article = Some(&chapter.article);
+      s.push('\n');
+      let caption = law_paragraph
+        .matches
+        .iter()
+        .find(|m| &m.chapter == chapter && m.article_caption.is_some())
+        .and_then(|m| m.article_caption.as_deref());
+      if let Some(caption) = caption {
+        s.push_str(&format!("  {caption}\n"));
+      }
+    }
+    // 附則であることは「附　則」の行で表すので、条項には「附則」を付けない
+    let chapter = Chapter {
+      suppl_provision_title: None,
+      ..chapter.clone()
+    };
+    s.push_str(&format!("{}\n", chapter_title(&chapter)));
+  }
+  s
+}
+
+/// 法令一つ分の検索結果を`Grep`形式の文字列にする。
+/// 検索語が見つかった箇所ごとに「法令名:条項:検索語:ID」の1行を書く。版があるときは法令名の後に「@版」を付ける
+pub fn to_grep(
+  law_paragraph: &LawParagraph,
+  abbreviations: &Abbreviations,
+  number_style: NumberStyle,
+) -> String {
+  let name = grep_name(
+    &law_paragraph.num,
+    law_paragraph.version.as_deref(),
+    abbreviations,
+  );
+  law_paragraph
+    .matches
+    .iter()
+    .map(|m| grep_line(&name, m, number_style))
+    .collect()
+}
+
+/// `Grep`形式の行の先頭に書く法令名
+fn grep_name(num: &str, version: Option<&str>, abbreviations: &Abbreviations) -> String {
+  match version {
+    Some(version) => format!("{}@{version}", abbreviations.display_name(num)),
+    None => abbreviations.display_name(num).to_string(),
+  }
+}
+
+fn grep_line(name: &str, m: &Match, number_style: NumberStyle) -> String {
+  format!(
+    "{name}:{}:{}:{}\n",
+    chapter_title_with(&m.chapter, number_style),
+    m.word,
+    m.id
+  )
+}
+
+/// 見つかった順に少しずつ受け取った箇所を、`Grep`形式の行にする。
+/// `is_dedup`なら、同じ条の中で同じ箇所を何度も受け取ったときは一度だけ書く
+#[derive(Debug, Clone)]
+pub struct GrepStreamWriter {
+  name: String,
+  is_dedup: bool,
+  number_style: NumberStyle,
+  /// 今の条の条番号と附則の改正法令番号
+  article: Option<(String, Option<String>)>,
+  /// 今の条の中で書いた箇所のID
+  written_ids: HashSet<String>,
+}
+
+impl GrepStreamWriter {
+  pub fn new(
+    num: &str,
+    version: Option<&str>,
+    abbreviations: &Abbreviations,
+    is_dedup: bool,
+    number_style: NumberStyle,
+  ) -> Self {
+    GrepStreamWriter {
+      name: grep_name(num, version, abbreviations),
+      is_dedup,
+      number_style,
+      article: None,
+      written_ids: HashSet::new(),
+    }
+  }
+
+  /// 受け取った箇所のうち、まだ書いていないものを行にする
+  pub fn lines(&mut self, matches: &[Match]) -> String {
+    let mut s = String::new();
+    for m in matches {
+      let article = (
+        m.chapter.article.clone(),
+        m.chapter.suppl_provision_title.clone(),
+      );
+      if self.article.as_ref() != Some(&article) {
+        self.article = Some(article);
+        self.written_ids.clear();
+      }
+      if !self.is_dedup || self.written_ids.insert(m.id.clone()) {
+        s.push_str(&grep_line(&self.name, m, self.number_style));
+      }
+    }
+    s
+  }
+}
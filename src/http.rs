@@ -0,0 +1,139 @@
+//! 外部のAPIを使う機能で共通して使うHTTPクライアント
+//!
+//! 失敗したときの再試行（指数バックオフ）、レスポンスのディスクへのキャッシュ、
+//! 連続してリクエストを送らないための間隔の確保を行う。
+
+use crate::diagnostic::{report_warning, Code};
+use anyhow::{anyhow, Result};
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use std::{path::PathBuf, time::Duration};
+use tokio::{
+  fs::{create_dir_all, read, write},
+  sync::Mutex,
+  time::{sleep, Instant},
+};
+use tracing::*;
+
+/// HTTPクライアントの設定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpConfig {
+  /// 失敗したときに再試行する回数
+  pub max_retries: usize,
+  /// 最初の再試行までの待ち時間。再試行のたびに2倍にする
+  pub initial_backoff: Duration,
+  /// リクエストとリクエストの間に最低限空ける時間
+  pub min_interval: Duration,
+  /// GETリクエストのレスポンスを保存するディレクトリ
+  pub cache_dir: Option<PathBuf>,
+}
+
+impl Default for HttpConfig {
+  fn default() -> Self {
+    HttpConfig {
+      max_retries: 3,
+      initial_backoff: Duration::from_secs(1),
+      min_interval: Duration::from_secs(1),
+      cache_dir: None,
+    }
+  }
+}
+
+/// 再試行・キャッシュ・間隔の確保を行うHTTPクライアント
+#[derive(Debug)]
+pub struct HttpClient {
+  client: reqwest::Client,
+  config: HttpConfig,
+  last_request: Mutex<Option<Instant>>,
+}
+
+impl HttpClient {
+  pub fn new(config: HttpConfig) -> Result<Self> {
+    let client = reqwest::Client::builder()
+      .user_agent(concat!(
+        env!("CARGO_PKG_NAME"),
+        "/",
+        env!("CARGO_PKG_VERSION")
+      ))
+      .build()?;
+    Ok(HttpClient {
+      client,
+      config,
+      last_request: Mutex::new(None),
+    })
+  }
+
+  /// 前のリクエストから`min_interval`が経つまで待つ
+  async fn wait_interval(&self) {
+    let mut last_request = self.last_request.lock().await;
+    if let Some(last) = *last_request {
+      let elapsed = last.elapsed();
+      if elapsed < self.config.min_interval {
+        sleep(self.config.min_interval - elapsed).await;
+      }
+    }
+    *last_request = Some(Instant::now());
+  }
+
+  fn cache_path(&self, url: &str) -> Option<PathBuf> {
+    let cache_dir = self.config.cache_dir.as_ref()?;
+    let digest = Sha256::digest(url.as_bytes());
+    Some(cache_dir.join(format!("{digest:x}")))
+  }
+
+  /// 再試行すべき失敗かどうか
+  fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+  }
+
+  /// `request`を送り、成功するか再試行の回数を使い切るまで繰り返す
+  async fn send_with_retry(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+    let mut backoff = self.config.initial_backoff;
+    let mut attempt = 0;
+    loop {
+      self.wait_interval().await;
+      let request = request
+        .try_clone()
+        .ok_or_else(|| anyhow!("リクエストを複製できません"))?;
+      let err = match request.send().await {
+        Ok(response) if Self::is_retryable(response.status()) => {
+          anyhow!("HTTP status {}", response.status())
+        }
+        Ok(response) => return Ok(response.error_for_status()?),
+        Err(e) => e.into(),
+      };
+      if attempt >= self.config.max_retries {
+        return Err(err);
+      }
+      attempt += 1;
+      report_warning(
+        Code::HttpRetry,
+        format!(
+          "http request failed (retry {}/{}): {}",
+          attempt, self.config.max_retries, err
+        ),
+      );
+      sleep(backoff).await;
+      backoff *= 2;
+    }
+  }
+
+  /// GETリクエストを送り、レスポンスの本体を返す。キャッシュがあればそれを返す
+  pub async fn get_bytes(&self, url: &str) -> Result<Vec<u8>> {
+    let cache_path = self.cache_path(url);
+    if let Some(cache_path) = &cache_path {
+      if let Ok(body) = read(cache_path).await {
+        info!("http cache hit: {}", url);
+        return Ok(body);
+      }
+    }
+    info!("http get: {}", url);
+    let response = self.send_with_retry(self.client.get(url)).await?;
+    let body = response.bytes().await?.to_vec();
+    if let (Some(cache_dir), Some(cache_path)) = (&self.config.cache_dir, &cache_path) {
+      create_dir_all(cache_dir).await?;
+      write(cache_path, &body).await?;
+    }
+    Ok(body)
+  }
+}
@@ -0,0 +1,240 @@
+//! 条項の位置情報から、実際の条文のテキストを取り出す
+
+use crate::{law_tree::LawTree, next_chapter, Chapter};
+use anyhow::Result;
+use encoding_rs::Encoding;
+use quick_xml::{encoding, events::Event, Reader};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncBufRead;
+
+/// 条項の位置情報とそのテキスト
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct ChapterText {
+  /// 法令番号
+  pub num: String,
+  /// 条項の位置情報
+  pub chapter: Chapter,
+  /// 条項のテキスト
+  pub text: String,
+  /// この条項の中で検索語が見つかった箇所のID
+  #[serde(skip_serializing_if = "Vec::is_empty", default)]
+  pub match_ids: Vec<String>,
+}
+
+/// ルビ（`Ruby`要素）の読み仮名（`Rt`要素）の扱い
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RubyStyle {
+  /// 読み仮名を取り除く
+  #[default]
+  Strip,
+  /// 読み仮名を`漢字(かんじ)`のように括弧に入れて残す
+  Parenthesized,
+}
+
+/// 与えられた条項それぞれの中にあるテキストを、法令XMLのイベントから集める
+struct Hydrator<'a> {
+  chapters: &'a [Chapter],
+  texts: Vec<String>,
+  last_chapters: Vec<Option<Chapter>>,
+  chapter_num: Chapter,
+  tag_stack: Vec<Vec<u8>>,
+  ruby: RubyStyle,
+  utf8: &'static Encoding,
+}
+
+impl<'a> Hydrator<'a> {
+  fn new(chapters: &'a [Chapter], ruby: RubyStyle) -> Self {
+    Hydrator {
+      chapters,
+      texts: vec![String::new(); chapters.len()],
+      last_chapters: vec![None; chapters.len()],
+      chapter_num: Chapter::default(),
+      tag_stack: Vec::new(),
+      ruby,
+      utf8: Encoding::for_label(b"utf-8").unwrap(),
+    }
+  }
+
+  fn handle_event(&mut self, event: Event) -> Result<()> {
+    match event {
+      Event::Start(tag) => {
+        self.tag_stack.push(tag.name().as_ref().to_vec());
+        self.chapter_num = next_chapter(self.chapter_num.clone(), &tag);
+      }
+      Event::End(_) => {
+        self.tag_stack.pop();
+      }
+      Event::Text(text) => {
+        let is_ruby_text = self.tag_stack.iter().any(|name| name == b"Rt");
+        if !is_ruby_text || self.ruby == RubyStyle::Parenthesized {
+          let text_str = encoding::decode(&text.into_inner(), self.utf8)?.to_string();
+          let text_str = if is_ruby_text {
+            format!("({text_str})")
+          } else {
+            text_str
+          };
+          let is_title = self
+            .tag_stack
+            .last()
+            .map(|name| name.ends_with(b"Title") || name.ends_with(b"Num"))
+            .unwrap_or(false);
+          for ((target, target_text), last_chapter) in self
+            .chapters
+            .iter()
+            .zip(self.texts.iter_mut())
+            .zip(self.last_chapters.iter_mut())
+          {
+            if self.chapter_num.is_within(target) {
+              if !target_text.is_empty() && last_chapter.as_ref() != Some(&self.chapter_num) {
+                target_text.push('\n');
+              }
+              target_text.push_str(&text_str);
+              if is_title {
+                target_text.push('　');
+              }
+              *last_chapter = Some(self.chapter_num.clone());
+            }
+          }
+        }
+      }
+      _ => (),
+    }
+    Ok(())
+  }
+}
+
+/// 法令XMLを先頭から読み、与えられた条項それぞれの中にあるテキストを取り出す。
+/// 返り値の順番は`chapters`の順番と同じになる。
+///
+/// 項や号などの単位ごとに改行を入れ、見出しや番号の後には全角空白を入れる。
+/// ルビの読み仮名は取り除く。
+pub async fn hydrate_chapters<R: AsyncBufRead + Unpin>(
+  chapters: &[Chapter],
+  reader: &mut Reader<R>,
+) -> Result<Vec<String>> {
+  hydrate_chapters_with(chapters, reader, RubyStyle::Strip).await
+}
+
+/// [`hydrate_chapters`]と同じようにテキストを取り出し、ルビの読み仮名は`ruby`に従って扱う
+pub async fn hydrate_chapters_with<R: AsyncBufRead + Unpin>(
+  chapters: &[Chapter],
+  reader: &mut Reader<R>,
+  ruby: RubyStyle,
+) -> Result<Vec<String>> {
+  let mut hydrator = Hydrator::new(chapters, ruby);
+  let mut buf = Vec::new();
+  reader.trim_text(true);
+  loop {
+    match reader.read_event_into_async(&mut buf).await? {
+      Event::Eof => break,
+      event => hydrator.handle_event(event)?,
+    }
+    buf.clear();
+  }
+  Ok(hydrator.texts)
+}
+
+/// [`hydrate_chapters`]と同じように、すでに[`LawTree`]に読み込んだ法令から条項のテキストを取り出す
+pub fn hydrate_parsed(chapters: &[Chapter], tree: &LawTree) -> Result<Vec<String>> {
+  let mut hydrator = Hydrator::new(chapters, RubyStyle::Strip);
+  tree
+    .root
+    .walk_events(&mut |event| hydrator.handle_event(event))?;
+  Ok(hydrator.texts)
+}
+
+/// 法令XMLを先頭から読み、与えられた条項の中にあるテキストを取り出す。
+/// 保存しておいた[`LawParagraph`](crate::LawParagraph)の条項について、条文を取り出すのに使う。
+///
+/// 複数の条項のテキストを取り出すときは、XMLを一度だけ読む[`hydrate_chapters`]を使う方が速い。
+pub async fn hydrate_chapter<R: AsyncBufRead + Unpin>(
+  chapter: &Chapter,
+  reader: &mut Reader<R>,
+) -> Result<String> {
+  let texts = hydrate_chapters(std::slice::from_ref(chapter), reader).await?;
+  Ok(texts.into_iter().next().unwrap_or_default())
+}
+
+/// 単語が含まれていた条項の前後のテキストとして、どの範囲を取り出すか
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ContextScope {
+  /// 条全体
+  Article,
+  /// 項全体
+  Paragraph,
+  /// 項の中で、単語が含まれていた文とその前後それぞれ指定した数の文
+  Sentences(usize),
+}
+
+impl std::str::FromStr for ContextScope {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "article" => Ok(ContextScope::Article),
+      "paragraph" => Ok(ContextScope::Paragraph),
+      _ => match s.strip_prefix("sentences:") {
+        Some(n) => n
+          .parse()
+          .map(ContextScope::Sentences)
+          .map_err(|e| format!("invalid number of sentences: {e}")),
+        None => Err(format!(
+          "unknown context scope: {s} (expected article, paragraph or sentences:N)"
+        )),
+      },
+    }
+  }
+}
+
+impl ContextScope {
+  /// テキストを取り出す範囲の条項
+  pub fn target(&self, chapter: &Chapter) -> Chapter {
+    match self {
+      ContextScope::Article => Chapter {
+        paragraph: None,
+        ..chapter.paragraph_level()
+      },
+      ContextScope::Paragraph | ContextScope::Sentences(_) => chapter.paragraph_level(),
+    }
+  }
+
+  /// `target`の範囲から取り出したテキストを、指定された範囲に切り詰める
+  pub fn trim(&self, text: &str, words: &[String]) -> String {
+    match self {
+      ContextScope::Article | ContextScope::Paragraph => text.to_string(),
+      ContextScope::Sentences(n) => {
+        let sentences = split_sentences(text);
+        let mut is_used = vec![false; sentences.len()];
+        for (i, sentence) in sentences.iter().enumerate() {
+          if words.iter().any(|word| sentence.contains(word.as_str())) {
+            let start = i.saturating_sub(*n);
+            let end = (i + n + 1).min(sentences.len());
+            is_used[start..end].iter_mut().for_each(|b| *b = true);
+          }
+        }
+        sentences
+          .iter()
+          .zip(is_used)
+          .filter(|(_, is_used)| *is_used)
+          .map(|(sentence, _)| *sentence)
+          .collect()
+      }
+    }
+  }
+}
+
+/// テキストを「。」の後ろと改行で文に区切る。改行は取り除かない
+pub fn split_sentences(text: &str) -> Vec<&str> {
+  let mut sentences = vec![];
+  let mut start = 0;
+  for (i, c) in text.char_indices() {
+    if c == '。' || c == '\n' {
+      let end = i + c.len_utf8();
+      sentences.push(&text[start..end]);
+      start = end;
+    }
+  }
+  if start < text.len() {
+    sentences.push(&text[start..]);
+  }
+  sentences
+}
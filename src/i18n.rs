@@ -0,0 +1,224 @@
+//! CLIのヘルプ・警告のコードの説明・報告書などに出す文言の日本語と英語のカタログ
+//!
+//! 言語は`--lang`か、無ければ環境変数`LC_ALL`・`LC_MESSAGES`・`LANG`のロケールで決める。
+//! 選ばれた言語はプロセス全体で一つなので、起動時に[`set_lang`]で一度だけ決めて保持する。
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// 文言の言語
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Lang {
+  /// 日本語
+  #[default]
+  Ja,
+  /// 英語
+  En,
+}
+
+static LANG: AtomicU8 = AtomicU8::new(0);
+
+/// これ以降に出す文言の言語を決める
+pub fn set_lang(lang: Lang) {
+  LANG.store(lang as u8, Ordering::Relaxed);
+}
+
+/// 今の文言の言語
+pub fn lang() -> Lang {
+  match LANG.load(Ordering::Relaxed) {
+    1 => Lang::En,
+    _ => Lang::Ja,
+  }
+}
+
+impl Lang {
+  /// ロケールを表す環境変数から言語を決める。
+  /// 日本語のロケールか、ロケールが無い・`C`・`POSIX`のときはこれまで通り日本語にする
+  pub fn from_env() -> Lang {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+      .iter()
+      .filter_map(|key| std::env::var(key).ok())
+      .find(|value| !value.is_empty());
+    match locale.as_deref() {
+      None | Some("C") | Some("POSIX") => Lang::Ja,
+      Some(locale) if locale.starts_with("ja") || locale.starts_with("C.") => Lang::Ja,
+      Some(_) => Lang::En,
+    }
+  }
+
+  /// コマンドライン引数の`--lang`の値。clapで解析する前にヘルプの言語を決めるために使う
+  pub fn from_args(args: &[String]) -> Option<Lang> {
+    let value = args.iter().enumerate().find_map(|(i, arg)| {
+      if arg == "--lang" {
+        args.get(i + 1).map(|value| value.as_str())
+      } else {
+        arg.strip_prefix("--lang=")
+      }
+    })?;
+    match value {
+      "ja" => Some(Lang::Ja),
+      "en" => Some(Lang::En),
+      _ => None,
+    }
+  }
+
+  /// HTMLの`lang`属性の値
+  pub fn html_lang(&self) -> &'static str {
+    match self {
+      Lang::Ja => "ja",
+      Lang::En => "en",
+    }
+  }
+}
+
+/// 警告や報告書に出す文言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Message {
+  /// 形態素解析に失敗して部分文字列として探すときの警告
+  MorphemeFallback,
+  /// 報告書の索引の題名
+  ReportIndexTitle,
+  /// 報告書の索引の絞り込み欄の説明
+  ReportIndexFilter,
+  /// `site`形式の一覧のページの題名
+  SiteTitle,
+  /// `site`形式の一覧のページで、検索語の前に付ける見出し
+  SiteSearchWords,
+  /// `site`形式の一覧のページで、目録のSHA-256の前に付ける見出し
+  SiteSnapshot,
+  /// `site`形式の法令のページから一覧のページへのリンク
+  SiteBackToIndex,
+}
+
+impl Message {
+  pub fn text(&self) -> &'static str {
+    match (self, lang()) {
+      (Message::MorphemeFallback, Lang::Ja) => "形態素解析に失敗したため、部分文字列として探します",
+      (Message::MorphemeFallback, Lang::En) => {
+        "morphological analysis failed; falling back to substring search"
+      }
+      (Message::ReportIndexTitle, Lang::Ja) => "検索結果の索引",
+      (Message::ReportIndexTitle, Lang::En) => "Index of search results",
+      (Message::ReportIndexFilter, Lang::Ja) => "法令名・法令番号・単語で絞り込む",
+      (Message::ReportIndexFilter, Lang::En) => "Filter by law name, law number or word",
+      (Message::SiteTitle, Lang::Ja) => "検索結果",
+      (Message::SiteTitle, Lang::En) => "Search results",
+      (Message::SiteSearchWords, Lang::Ja) => "検索語：",
+      (Message::SiteSearchWords, Lang::En) => "Search words: ",
+      (Message::SiteSnapshot, Lang::Ja) => "目録のSHA-256：",
+      (Message::SiteSnapshot, Lang::En) => "Snapshot SHA-256: ",
+      (Message::SiteBackToIndex, Lang::Ja) => "一覧へ戻る",
+      (Message::SiteBackToIndex, Lang::En) => "Back to the list",
+    }
+  }
+}
+
+/// 語を並べるときの区切り
+pub fn list_separator() -> &'static str {
+  match lang() {
+    Lang::Ja => "、",
+    Lang::En => ", ",
+  }
+}
+
+/// 見つかった条項の数の表記
+pub fn match_count(count: usize) -> String {
+  match lang() {
+    Lang::Ja => format!("{count}件"),
+    Lang::En if count == 1 => "1 match".to_string(),
+    Lang::En => format!("{count} matches"),
+  }
+}
+
+/// CLIの説明の英語版
+pub const ABOUT_EN: &str =
+  "Find the articles containing the given words in a directory of Japanese statute XML files";
+
+/// CLIの引数のヘルプの英語版。キーはclapの引数のid
+pub fn help_en(id: &str) -> Option<&'static str> {
+  let help = match id {
+    "output" => "Path to the JSON file to write the results to",
+    "work" => "Path to the working directory containing the statute XML files",
+    "index_file" => "Path to the JSON index file of the statute files",
+    "search_words" => "Words to search for",
+    "exclude_words" => "Words to exclude. Articles (or the `--all-words` scope) containing any of them are not recorded",
+    "synonyms" => "Path to a TSV synonym dictionary with a search word in the first column and its synonyms separated by `,` in the second. Each search word is also searched for as its synonyms",
+    "stats" => "Path to the JSON file to write statistics to",
+    "export_text" => "Directory to write the text of each matched paragraph to, one file per paragraph",
+    "export_text_jsonl" => "Path to the JSONL file to write the text and position of each matched paragraph to",
+    "export_speech" => "Directory to write the text of each matched article to as plain text for speech synthesis (TTS), one file per article",
+    "export_annotation" => "Path to the JSONL file to write matched paragraphs and word spans to, for annotation tools",
+    "annotation_format" => "Format written by `--export-annotation`",
+    "citation_list" => "Path to the text file to write the citations of matched provisions to, one per line without duplicates",
+    "bibliography" => "Path to the file to write bibliographic entries of matched laws to",
+    "bibliography_format" => "Format written by `--bibliography`",
+    "context" => "Range of text to write: `article`, `paragraph` or `sentences:N` (the matched sentence and N sentences around it)",
+    "qa_sample" => "Randomly sample the given number of matches and write them with their surrounding text as JSONL",
+    "furigana" => "Exports that keep ruby readings as `漢字(かんじ)` instead of removing them (comma separated)",
+    "cooccurrence" => "Path to the JSON file to write character n-grams that often appear in the same sentence as the search words",
+    "cooccurrence_ngram" => "Maximum length of the character n-grams counted by `--cooccurrence`",
+    "cooccurrence_top" => "Number of n-grams written per search word by `--cooccurrence`",
+    "suggest_terms" => "Write to `--stats` N words (character n-grams) that often appear in the same sentence as the search words but are not among them, as candidates to add to the search",
+    "snapshot" => "Path to the JSON inventory of the working directory made by `snapshot`",
+    "error_report" => "Path to the JSONL file to write coded errors to",
+    "max_memory" => "Memory limit for captured text (e.g. `512M`, `2G`). Beyond it, only positions are written",
+    "flush_every" => "Flush the output files after every N searched laws",
+    "fsync" => "Wait for the data to reach the disk (fsync) when flushing",
+    "report_template" => "Path to a handlebars template that the results are rendered into as a report",
+    "report_output" => "Path to write the report made with `--report-template` to",
+    "report_index" => "Path to write an HTML index with links to the laws and articles of the report and a filter box. Put it in the same directory as the report",
+    "format" => "Format written to `--output`",
+    "schema_version" => "Version of the JSON written to `--output`. Version 2 also writes, for each law, the chapters where each search word was found (`hits`)",
+    "number_style" => "How to write article numbers in the `pretty` and `grep` formats and reports",
+    "abbreviations" => "Path to a JSON file mapping law numbers to short names, used by the `pretty` and `grep` formats and reports",
+    "versions" => "Which versions to search when the working directory has several versions of the same law",
+    "read_buffer_size" => "Buffer size for reading statute files, at least 1 byte (e.g. `512K`, `4M`)",
+    "in_memory_max_size" => "Statute files up to this size are read into memory at once before searching",
+    "mmap" => "Memory-map statute files and search them synchronously. Suited to files on a fast local disk",
+    "dedup" => "How to merge words found several times at the same place",
+    "all_words" => "Record only when all search words appear together within this scope (article, paragraph or sentence)",
+    "regex" => "Treat the search words as regular expressions",
+    "match_mode" => "How to match the search words against the text. `morpheme` (`morpheme` feature) matches only at morpheme boundaries",
+    "fuzzy" => "Also match substrings within Levenshtein distance N of a search word, ignoring whitespace",
+    "wildcard" => "Treat `＊` in the search words as any string and `？` as any single character",
+    "fold_width" => "Ignore the difference between full-width and half-width alphanumerics and between upper and lower case",
+    "fold_numerals" => "Treat numbers written in kanji numerals and in full-width or half-width Arabic numerals as the same (「第二種」 matches 「第２種」)",
+    "fold_okurigana" => "Ignore okurigana following kanji (「取り消し」, 「取消し」 and 「取消」 are treated as the same)",
+    "fold_kana" => "Ignore the difference between katakana and hiragana (「スル」 matches 「する」), for pre-war statutes written in katakana",
+    "fold_old_kanji" => "Ignore the difference between old and new forms of kanji (「國」 matches 「国」, 「廳」 matches 「庁」)",
+    "captions_only" => "Search only the article and paragraph captions (`ArticleCaption`, `ParagraphCaption`)",
+    "definitions_only" => "Record only definitions of the form 「…とは、…をいう。」 whose defined term contains a search word",
+    "quoted_only" => "Record only search words inside Japanese corner brackets 「…」, to find where a statute quotes or relabels a phrase",
+    "snippet" => "Attach the matched text with N characters on each side to each match as `snippet`",
+    "highlight" => "Markers placed around the matched part of each `--snippet` (e.g. `【 】`, `<em> </em>`)",
+    "offsets" => "Attach the sentence number (`sentence_num`) and the character offset in the sentence (`offset`) to each match",
+    "first_match_per_article" => "Record only the first match in each article and skip the rest of its text. Faster when you only need to know which articles contain the words",
+    "min_hits" => "Record only articles (or `--all-words` scopes) where the search words are found at least N times in total",
+    "article_metrics" => "Attach the character count, the sentence count and the average sentence length (`article_metrics`) of each article with matches",
+    "query" => "A query such as `許可 AND (取消 OR 停止) NOT 附則`, evaluated per article (or per `--all-words` scope)",
+    "near" => "Record only where the two words appear in the same sentence (see `--near-unit`) with at most N characters between them",
+    "text_unit" => "Join the text of this unit (sentence or paragraph) without ruby readings before searching, so words across ruby or sentence breaks are found",
+    "max_depth" => "Maximum element nesting depth. Deeper or mismatched statute XML is an error (`E005`)",
+    "near_unit" => "The unit in which the two words of `--near` must appear",
+    "include_empty" => "Also write laws without matches, with an empty `chapter_data`",
+    "manifest" => "Path to the JSONL file to write the status, duration and match count of each processed file to",
+    "on_duplicate" => "What to do when the index lists the same file (including through symbolic links) or the same law number more than once",
+    "concurrency" => "Number of statute files searched at the same time",
+    "session_log" => "Path to the JSON file to write the settings, input hashes, durations and output hashes of the run to",
+    "nats_url" => "URL of the NATS server to send the results of each searched law to",
+    "nats_subject" => "Subject used by `--nats-url`",
+    "dsn" => "Connection string of the PostgreSQL database written by `--format postgres`",
+    "postgres_batch_size" => "Number of laws written in one transaction by `--format postgres`",
+    "config" => "Path to a TOML config file of search options. Keys are the long option names, and options given on the command line take precedence",
+    "plugin" => "Path to a WASM plugin that receives each match and can reject it or add tags and attributes. Can be given several times; plugins run in order (`plugin` feature)",
+    "filter_expr" => "A rhai expression evaluated for each match, such as `scope == \"suppl\" && count > 2`. Only matches for which it is true are kept (`filter-expr` feature)",
+    "relevance_model" => "Directory containing `model.onnx` and `tokenizer.json` of a cross-encoder used to score each match (`relevance` feature)",
+    "relevance_query" => "Natural-language description of the research question that `--relevance-model` compares each match with",
+    "relevance_output" => "Path to the JSONL file to write the scored matches to, highest score first",
+    "lang" => "Language of help, warnings and report strings (`ja` or `en`). Defaults to the locale",
+    "seed" => "Random seed for sampling. With it, `--qa-sample`, `verify` and `sample-paragraphs` sample the same items on every run. `sample-paragraphs` uses 0 when it is omitted",
+    _ => return None,
+  };
+  Some(help)
+}
@@ -0,0 +1,112 @@
+//! 漢数字の解析
+
+/// 漢数字一文字を数値に変換する
+fn digit(c: char) -> Option<usize> {
+  match c {
+    '〇' | '零' => Some(0),
+    '一' | '元' => Some(1),
+    '二' => Some(2),
+    '三' => Some(3),
+    '四' => Some(4),
+    '五' => Some(5),
+    '六' => Some(6),
+    '七' => Some(7),
+    '八' => Some(8),
+    '九' => Some(9),
+    _ => None,
+  }
+}
+
+/// 漢数字の位取りの文字を数値に変換する
+fn unit(c: char) -> Option<usize> {
+  match c {
+    '十' => Some(10),
+    '百' => Some(100),
+    '千' => Some(1000),
+    _ => None,
+  }
+}
+
+/// 「二十二」や「三百三十一」、「元」のような漢数字の文字列を数値に変換する
+///
+/// 「一九四七」のような位取りの文字を使わない表記にも対応する。`usize`に収まらないほど大きい数は`None`にする。
+pub fn parse_kansuji(s: &str) -> Option<usize> {
+  if s.is_empty() {
+    return None;
+  }
+  let mut man = 0;
+  let mut total = 0;
+  let mut current: Option<usize> = None;
+  let mut has_unit = false;
+  for c in s.chars() {
+    if let Some(n) = digit(c) {
+      current = Some(current.unwrap_or(0).checked_mul(10)?.checked_add(n)?);
+    } else if let Some(u) = unit(c) {
+      total = current.unwrap_or(1).checked_mul(u)?.checked_add(total)?;
+      current = None;
+      has_unit = true;
+    } else if c == '万' {
+      let n = total.checked_add(current.unwrap_or(0))?.max(1);
+      man = n.checked_mul(10000)?.checked_add(man)?;
+      total = 0;
+      current = None;
+      has_unit = true;
+    } else {
+      return None;
+    }
+  }
+  if has_unit && matches!(current, Some(n) if n >= 10) {
+    // 「十二三」のような表記は受け付けない
+    return None;
+  }
+  man.checked_add(total)?.checked_add(current.unwrap_or(0))
+}
+
+/// 半角・全角のアラビア数字の文字列を数値に変換する。`usize`に収まらないほど大きい数は`None`にする
+pub fn parse_digits(s: &str) -> Option<usize> {
+  if s.is_empty() {
+    return None;
+  }
+  s.chars().try_fold(0usize, |acc, c| {
+    let n = match c {
+      '0'..='9' => c as usize - '0' as usize,
+      '０'..='９' => c as usize - '０' as usize,
+      _ => return None,
+    };
+    acc.checked_mul(10)?.checked_add(n)
+  })
+}
+
+/// 漢数字かアラビア数字で書かれた数を数値に変換する
+pub fn parse_number(s: &str) -> Option<usize> {
+  parse_kansuji(s).or_else(|| parse_digits(s))
+}
+
+/// 数値を「三百三十一」のような漢数字に変換する
+pub fn to_kansuji(n: usize) -> String {
+  const DIGITS: [char; 10] = ['〇', '一', '二', '三', '四', '五', '六', '七', '八', '九'];
+  if n == 0 {
+    return DIGITS[0].to_string();
+  }
+  let mut s = String::new();
+  let man = n / 10000;
+  let rest = n % 10000;
+  if man > 0 {
+    s.push_str(&to_kansuji(man));
+    s.push('万');
+  }
+  for (u, c) in [(1000, '千'), (100, '百'), (10, '十')] {
+    let d = rest / u % 10;
+    if d > 1 {
+      s.push(DIGITS[d]);
+    }
+    if d > 0 {
+      s.push(c);
+    }
+  }
+  let d = rest % 10;
+  if d > 0 {
+    s.push(DIGITS[d]);
+  }
+  s
+}
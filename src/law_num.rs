@@ -0,0 +1,86 @@
+//! 法令番号から元号と年を読み取る
+
+use crate::kansuji::parse_number;
+
+/// 元号
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Era {
+  Meiji,
+  Taisho,
+  Showa,
+  Heisei,
+  Reiwa,
+}
+
+impl Era {
+  const ALL: [Era; 5] = [Era::Meiji, Era::Taisho, Era::Showa, Era::Heisei, Era::Reiwa];
+
+  /// 元号名
+  pub fn name(&self) -> &'static str {
+    match self {
+      Era::Meiji => "明治",
+      Era::Taisho => "大正",
+      Era::Showa => "昭和",
+      Era::Heisei => "平成",
+      Era::Reiwa => "令和",
+    }
+  }
+
+  /// 法令XMLの`Era`属性の値（`Showa`など）から元号を求める
+  pub fn from_attribute(s: &str) -> Option<Era> {
+    match s {
+      "Meiji" => Some(Era::Meiji),
+      "Taisho" => Some(Era::Taisho),
+      "Showa" => Some(Era::Showa),
+      "Heisei" => Some(Era::Heisei),
+      "Reiwa" => Some(Era::Reiwa),
+      _ => None,
+    }
+  }
+
+  /// 元年にあたる西暦年
+  pub fn first_year(&self) -> usize {
+    match self {
+      Era::Meiji => 1868,
+      Era::Taisho => 1912,
+      Era::Showa => 1926,
+      Era::Heisei => 1989,
+      Era::Reiwa => 2019,
+    }
+  }
+}
+
+/// 法令番号に書かれている年
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LawNumYear {
+  pub era: Era,
+  /// 元号での年（元年は1）
+  pub year: usize,
+}
+
+impl LawNumYear {
+  /// 西暦年
+  pub fn ad_year(&self) -> usize {
+    self.era.first_year() + self.year - 1
+  }
+
+  /// 西暦での年代（1947年なら1940）
+  pub fn decade(&self) -> usize {
+    self.ad_year() / 10 * 10
+  }
+}
+
+/// 「昭和二十二年法律第六十七号」のような法令番号から元号と年を取り出す
+pub fn parse_law_num_year(law_num: &str) -> Option<LawNumYear> {
+  let law_num = law_num.trim();
+  let era = Era::ALL
+    .iter()
+    .find(|era| law_num.starts_with(era.name()))?;
+  let rest = &law_num[era.name().len()..];
+  let year_str = &rest[..rest.find('年')?];
+  let year = parse_number(year_str)?;
+  if year == 0 {
+    return None;
+  }
+  Some(LawNumYear { era: *era, year })
+}
@@ -0,0 +1,337 @@
+//! メモリ上に読み込んだ法令XMLの木
+//!
+//! 法令をすでにメモリ上に持っているアプリケーション（エディタやビューアなど）が、
+//! XMLに書き出して読み直さずに検索できるようにする。
+//! 要素は[`ElementKind`]で編・章・条・項・号・表・附則などに分類でき、[`LawTree::articles`]や[`LawTree::paragraphs`]などで種類ごとに取り出せる。
+//! 検索（[`search_parsed`](crate::search_parsed)）と条文の取り出し（[`hydrate_parsed`](crate::hydrate::hydrate_parsed)）は同じ木をたどる。
+
+use crate::num::Num;
+use crate::{
+  diagnostic::{Code, Diagnostic},
+  search_config::DEFAULT_MAX_DEPTH,
+  xml_parse_error,
+};
+use anyhow::Result;
+use quick_xml::{
+  events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event},
+  Reader, Writer,
+};
+
+/// 要素の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElementKind {
+  /// 法令全体（`Law`）
+  Law,
+  /// 本則（`MainProvision`）
+  MainProvision,
+  /// 編
+  Part,
+  /// 章
+  Chapter,
+  /// 節
+  Section,
+  /// 款
+  Subsection,
+  /// 目
+  Division,
+  /// 条
+  Article,
+  /// 項
+  Paragraph,
+  /// 号
+  Item,
+  /// 号の細分。`Subitem1`（イ・ロ・ハ…）なら1になる
+  Subitem(usize),
+  /// 表（`TableStruct`）
+  Table,
+  /// 附則
+  SupplProvision,
+  /// 文
+  Sentence,
+  /// それ以外の要素
+  Other,
+}
+
+impl ElementKind {
+  /// 要素の名前から種類を求める
+  pub fn from_name(name: &str) -> Self {
+    match name {
+      "Law" => ElementKind::Law,
+      "MainProvision" => ElementKind::MainProvision,
+      "Part" => ElementKind::Part,
+      "Chapter" => ElementKind::Chapter,
+      "Section" => ElementKind::Section,
+      "Subsection" => ElementKind::Subsection,
+      "Division" => ElementKind::Division,
+      "Article" => ElementKind::Article,
+      "Paragraph" => ElementKind::Paragraph,
+      "Item" => ElementKind::Item,
+      "TableStruct" => ElementKind::Table,
+      "SupplProvision" => ElementKind::SupplProvision,
+      "Sentence" => ElementKind::Sentence,
+      _ => match name.strip_prefix("Subitem").map(|level| level.parse()) {
+        Some(Ok(level)) => ElementKind::Subitem(level),
+        _ => ElementKind::Other,
+      },
+    }
+  }
+}
+
+/// 法令XMLの要素
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct LawElement {
+  /// 要素の名前
+  pub name: String,
+  /// 属性の名前と値。XMLに書かれていた順に並べる
+  pub attributes: Vec<(String, String)>,
+  pub children: Vec<LawNode>,
+  /// 空要素のタグ（`<X/>`）で書かれていたかどうか。`<X></X>`と書かれていたときは`false`になる
+  pub is_empty_tag: bool,
+}
+
+/// 要素の子
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LawNode {
+  Element(LawElement),
+  /// 実体参照を戻したテキスト
+  Text(String),
+}
+
+/// 法令XML一つ分の木
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct LawTree {
+  /// 最上位の要素（`Law`）
+  pub root: LawElement,
+}
+
+impl LawElement {
+  /// 属性`name`の値
+  pub fn attribute(&self, name: &str) -> Option<&str> {
+    self
+      .attributes
+      .iter()
+      .find(|(key, _)| key == name)
+      .map(|(_, value)| value.as_str())
+  }
+
+  /// 要素の種類
+  pub fn kind(&self) -> ElementKind {
+    ElementKind::from_name(&self.name)
+  }
+
+  /// `Num`属性の値
+  pub fn num(&self) -> Option<Num> {
+    Num::parse(self.attribute("Num")?)
+  }
+
+  /// 子の要素
+  pub fn child_elements(&self) -> impl Iterator<Item = &LawElement> {
+    self.children.iter().filter_map(|node| match node {
+      LawNode::Element(element) => Some(element),
+      LawNode::Text(_) => None,
+    })
+  }
+
+  /// この要素の中（この要素を含まない）にある`kind`の要素を、文書の順に返す
+  pub fn descendants(&self, kind: ElementKind) -> Vec<&LawElement> {
+    let mut lst = vec![];
+    for element in self.child_elements() {
+      if element.kind() == kind {
+        lst.push(element);
+      }
+      lst.extend(element.descendants(kind));
+    }
+    lst
+  }
+
+  /// この要素の中のテキストを、ルビの読み仮名を除いてつなげたもの
+  pub fn text(&self) -> String {
+    let mut s = String::new();
+    for child in self.children.iter() {
+      match child {
+        LawNode::Element(element) if element.name == "Rt" => (),
+        LawNode::Element(element) => s.push_str(&element.text()),
+        LawNode::Text(text) => s.push_str(text),
+      }
+    }
+    s
+  }
+
+  /// この要素の開始タグ
+  pub(crate) fn start_tag(&self) -> BytesStart {
+    BytesStart::new(self.name.as_str()).with_attributes(
+      self
+        .attributes
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str())),
+    )
+  }
+
+  /// この要素の中を文書の順にたどり、読み込んだときと同じイベントを`f`に渡す。
+  /// 空要素のタグで書かれていた子の無い要素だけを空要素のタグとして渡し、それ以外は開始タグと終了タグを渡す
+  pub(crate) fn walk_events(&self, f: &mut impl FnMut(Event) -> Result<()>) -> Result<()> {
+    if self.is_empty_tag && self.children.is_empty() {
+      return f(Event::Empty(self.start_tag()));
+    }
+    f(Event::Start(self.start_tag()))?;
+    for child in self.children.iter() {
+      match child {
+        LawNode::Element(element) => element.walk_events(f)?,
+        LawNode::Text(text) => f(Event::Text(BytesText::new(text)))?,
+      }
+    }
+    f(Event::End(BytesEnd::new(self.name.as_str())))
+  }
+}
+
+impl LawTree {
+  /// メモリ上の法令XMLを読み込む。
+  /// 要素の入れ子が[`DEFAULT_MAX_DEPTH`]より深いとき（`E005`）や、要素が閉じられていないとき（`E004`）はエラーを返す
+  pub fn parse_xml(xml: &[u8]) -> Result<LawTree> {
+    let mut reader = Reader::from_reader(xml);
+    reader.trim_text(true);
+    // 開かれている要素。先頭は最上位の要素の親の代わりにする
+    let mut stack = vec![LawElement::default()];
+    loop {
+      let event = reader
+        .read_event()
+        .map_err(|e| xml_parse_error(e, reader.buffer_position()))?;
+      match event {
+        Event::Start(tag) => {
+          if stack.len() > DEFAULT_MAX_DEPTH {
+            return Err(
+              Diagnostic::new(
+                Code::MalformedNesting,
+                format!("要素の入れ子が{DEFAULT_MAX_DEPTH}より深くなっています"),
+              )
+              .into(),
+            );
+          }
+          stack.push(to_element(&tag)?);
+        }
+        Event::Empty(tag) => {
+          let element = LawElement {
+            is_empty_tag: true,
+            ..to_element(&tag)?
+          };
+          push_child(&mut stack, LawNode::Element(element));
+        }
+        Event::End(_) => {
+          // 終了タグの名前が開始タグと対応していることは`Reader`が確かめる
+          if let Some(element) = stack.pop() {
+            push_child(&mut stack, LawNode::Element(element));
+          }
+        }
+        Event::Text(text) => {
+          let text = text.unescape()?.to_string();
+          push_child(&mut stack, LawNode::Text(text));
+        }
+        Event::CData(text) => {
+          let text = String::from_utf8_lossy(&text.into_inner()).to_string();
+          push_child(&mut stack, LawNode::Text(text));
+        }
+        Event::Eof => break,
+        _ => (),
+      }
+    }
+    if stack.len() > 1 {
+      return Err(
+        Diagnostic::new(
+          Code::TruncatedXml,
+          format!(
+            "法令XMLが途中で終わっています（閉じられていない要素が{}個）",
+            stack.len() - 1
+          ),
+        )
+        .into(),
+      );
+    }
+    let root = stack
+      .pop()
+      .and_then(|document| {
+        document.children.into_iter().find_map(|node| match node {
+          LawNode::Element(element) => Some(element),
+          LawNode::Text(_) => None,
+        })
+      })
+      .ok_or_else(|| Diagnostic::new(Code::TruncatedXml, "法令XMLに要素がありません"))?;
+    Ok(LawTree { root })
+  }
+
+  /// 法令XMLとして書き出す。属性の値とテキストは実体参照に直す
+  pub fn to_xml(&self) -> Result<String> {
+    let mut writer = Writer::new(Vec::new());
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    self
+      .root
+      .walk_events(&mut |event| Ok(writer.write_event(event)?))?;
+    Ok(String::from_utf8(writer.into_inner())?)
+  }
+
+  /// 本則
+  pub fn main_provision(&self) -> Option<&LawElement> {
+    self
+      .root
+      .descendants(ElementKind::MainProvision)
+      .into_iter()
+      .next()
+  }
+
+  /// 編を文書の順に返す
+  pub fn parts(&self) -> Vec<&LawElement> {
+    self.root.descendants(ElementKind::Part)
+  }
+
+  /// 章を文書の順に返す
+  pub fn chapters(&self) -> Vec<&LawElement> {
+    self.root.descendants(ElementKind::Chapter)
+  }
+
+  /// 条を文書の順に返す。附則の条も含む
+  pub fn articles(&self) -> Vec<&LawElement> {
+    self.root.descendants(ElementKind::Article)
+  }
+
+  /// 項を文書の順に返す。附則の項も含む
+  pub fn paragraphs(&self) -> Vec<&LawElement> {
+    self.root.descendants(ElementKind::Paragraph)
+  }
+
+  /// 号を文書の順に返す。号の細分は含まない
+  pub fn items(&self) -> Vec<&LawElement> {
+    self.root.descendants(ElementKind::Item)
+  }
+
+  /// 表を文書の順に返す
+  pub fn tables(&self) -> Vec<&LawElement> {
+    self.root.descendants(ElementKind::Table)
+  }
+
+  /// 附則を文書の順に返す
+  pub fn suppl_provisions(&self) -> Vec<&LawElement> {
+    self.root.descendants(ElementKind::SupplProvision)
+  }
+}
+
+fn to_element(tag: &BytesStart) -> Result<LawElement> {
+  let name = String::from_utf8_lossy(tag.name().as_ref()).to_string();
+  let mut attributes = vec![];
+  for attribute in tag.attributes() {
+    let attribute = attribute?;
+    let key = String::from_utf8_lossy(attribute.key.as_ref()).to_string();
+    let value = attribute.unescape_value()?.to_string();
+    attributes.push((key, value));
+  }
+  Ok(LawElement {
+    name,
+    attributes,
+    children: vec![],
+    is_empty_tag: false,
+  })
+}
+
+fn push_child(stack: &mut [LawElement], node: LawNode) {
+  if let Some(parent) = stack.last_mut() {
+    parent.children.push(node);
+  }
+}
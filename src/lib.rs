@@ -7,6 +7,12 @@
 //! cargo install --git "https://github.com/japanese-law-analysis/search_article_with_word.git"
 //! ```
 //!
+//! NATSに検索結果を送る機能（`--nats-url`）を使うときは、`nats` featureを有効にしてインストールします。
+//!
+//! ```sh
+//! cargo install --git "https://github.com/japanese-law-analysis/search_article_with_word.git" --features nats
+//! ```
+//!
 //! # Use
 //!
 //! ```sh
@@ -15,28 +21,599 @@
 //!
 //! で起動します。それぞれのオプションの意味は以下の通りです。
 //!
-//! - `--output`：指定した単語が含まれる条項の情報のリストを出力するJSONファイル名
+//! - `--output`：指定した単語が含まれる条項の情報のリストを出力するJSONファイル名。法令ごとの`chapter_words`には、`chapter_data`のそれぞれの条項で見つかった検索語を、`hit_counts`にはそれぞれの条項で検索語が見つかった回数を、同じ順に書き出す
 //! - `--work`：[e-gov法令検索](https://elaws.e-gov.go.jp/)からダウンロードした全ファイルが入っているフォルダへのpath
 //! - `--index-file`：[japanese-law-analysis/listup_law](https://github.com/japanese-law-analysis/listup_law)で生成した法令のリストが書かれているJSONファイルへのpath
-//! - `--search-word`：検索する単語を指定する。複数指定可
+//! - `--search-word`：検索する単語を指定する。複数指定可。正規表現・ワイルドカード・`--fuzzy`・`--match-mode morpheme`を使わなければ、複数の単語をAho-Corasick法でまとめ、テキストを一度走査するだけで全ての単語を探す
+//! - `--exclude-words`：除外する単語を指定する。複数指定可。除外する単語を一つでも含む条（`--all-words`を指定すればその範囲）は、検索する単語を含んでいても記録しない。「許可」を検索するときに「無許可」を含む条を除くのに使う（省略可）
+//! - `--synonyms`：一行に一列目の検索語と、二列目のその同義語を`,`か`、`で区切って並べたTSVの同義語の辞書（`自動車\t車両,自動車等`）。検索語それぞれをその同義語にも広げて探し、同義語で見つかった箇所は`matches`の`word`を実際に見つかった同義語に、`synonym_of`を元の検索語にする。空の行と`#`で始まる行は読み飛ばす。`--all-words`・`--query`・`--near`とは同時に使えない（省略可）
+//! - `--stats`：単語ごと・年代ごとの、単語を含む法令の数などの統計情報を出力するJSONファイル名。`--versions all`で同じ法令の版を複数検索しても、法令の数は法令番号ごとに一つと数える。`--synonyms`の同義語で見つかったものは、元の検索語に数える（省略可）
+//! - `--export-text`：単語が含まれていた項のテキストを、項ごとに1ファイルずつ書き出すディレクトリ（省略可）
+//! - `--export-text-jsonl`：単語が含まれていた項のテキストと位置情報を、1行1項のJSONLで書き出すファイル名（省略可）
+//! - `--export-speech`：単語が含まれていた条のテキストを、読み上げ（TTS）用のテキストにして、条ごとに1ファイルずつ書き出すディレクトリ。ルビの読み仮名を除き、「（目的）」のような見出しは括弧を外して文にし、番号の後の空白は読点にして、読む順に1行に一つの単位を書く（省略可）
+//! - `--export-annotation`：単語が含まれていた項のテキストと単語の出現範囲を、アノテーションツールで読み込めるJSONLで書き出すファイル名（省略可）
+//! - `--annotation-format`：`--export-annotation`の形式。`doccano`（デフォルト）か`label-studio`
+//! - `--citation-list`：単語が含まれていた条項を「会社法（平成十七年法律第八十六号）第三百三十一条第一項第三号」のような引用の表記にし、重複を除いて一行に一つずつ書き出すテキストファイル名。ワープロなどにそのまま貼り付けられる（省略可）
+//! - `--bibliography`：単語が含まれていた法令の文献情報（法令名・法令番号・公布日・e-Gov法令検索のURL）を書き出すファイル名（省略可）
+//! - `--bibliography-format`：`--bibliography`の形式。`csl-json`（デフォルト）か`bibtex`
+//! - `--qa-sample`：`--qa-sample 100 qa.jsonl`のように件数とファイル名を指定すると、単語が含まれていた条項を無作為に抽出し、その前後のテキストと共にJSONLで書き出す（省略可）
+//! - `--seed`：無作為抽出に使う乱数の種。指定すると、`--qa-sample`・`verify`・`sample-paragraphs`で毎回同じものを抽出するので、研究の成果物として抽出した結果を再現できる。種が同じでも、作業ディレクトリの法令XMLファイルや検索のオプションが変われば抽出されるものも変わる。`sample-paragraphs`以外で省略したときは、実行のたびに違うものを抽出する（省略可）
+//! - `--furigana`：取り出したテキストで、ルビの読み仮名を取り除かずに`漢字(かんじ)`のように残す書き出し先。`export-text`・`export-text-jsonl`・`export-annotation`・`qa-sample`・`site`・`xlsx`をカンマ区切りで指定する。指定しなかった書き出し先では、これまで通り読み仮名を取り除く（省略可）
+//! - `--cooccurrence`：検索語を含む文によく現れる語（文字n-gram）を、検索語ごとに回数の多い順に書き出すJSONファイル名（省略可）。`--cooccurrence-ngram`でn-gramの最大の長さ（デフォルトは3）を、`--cooccurrence-top`で書き出す数（デフォルトは50）を指定する
+//! - `--suggest-terms`：検索語を含む文に特によく現れ、検索語を含まず検索語の一部分でもない語（文字n-gram）を指定した個数だけ、検索語に加える候補として`--stats`の`suggested_terms`に書き出す。「する」「場合」のようにどの文にも多く現れる語が上に来ないように、見つかった条項のテキストのうち検索語を含まない文での回数と比べた対数尤度比の大きい順に選び、検索語を含む文のほうに多く現れるものだけを残す。検索語を含む文かどうかは、`--regex`・`--fuzzy`・`--fold-*`なども含めて検索と同じ判定の仕方で調べる。同じ回数で現れるより長いn-gramの一部分であるものは除く。n-gramの長さは`--cooccurrence-ngram`で決める。見落としを減らすために検索語を少しずつ足していくのに使う（`--stats`と共に指定する、省略可）
+//! - `--context`：`--export-text`などで書き出すテキストの範囲。`article`（条全体）・`paragraph`（項全体、デフォルト）・`sentences:N`（単語を含む文とその前後N文）のいずれか
+//! - `--snapshot`：`snapshot`サブコマンドで作った目録のJSONファイル。そのSHA-256を、検索結果の法令ごとと`--stats`の出力に`snapshot_sha256`として書き込み、`site`形式では一覧のページに載せる（省略可）
+//! - `--error-report`：コードの付いた警告（`W`で始まるもの）とエラーを1行1件のJSONLで書き出すファイル名。ログに出すものと同じものを書き出す（省略可）
+//! - `--max-memory`：`--export-text`や`--cooccurrence`などで取り出したテキストや、`--snippet`で見つかった箇所に付けるテキスト、報告書などのために最後まで持っておく見つかった箇所に使うメモリの上限（`512M`・`2G`など）。見積もった量が上限を超えたら、警告（`W007`）を出してテキストの取り出しをやめ、以降は見つかった箇所に`snippet`を付けずに、条項・文の番号・位置だけを書き出す（省略可）
+//! - `--flush-every`：指定した数の法令を検索するごとに、`--output`や`--export-text-jsonl`などに書いた内容をflushする。途中で止まったときに失われるのは最大でこの数の法令の結果になる（省略可。省略したときは最後にだけflushする）
+//! - `--fsync`：flushするときに、ディスクへの書き込みが終わるまで待つ
+//! - `--report-template`：検索結果を埋め込んで報告書を作る[handlebars](https://handlebarsjs.com/)形式のテンプレートファイル。`--report-output`で報告書を書き出すファイル名を指定する（省略可）
+//! - `--report-index`：`--report-output`の報告書の法令と条へのリンクの一覧と絞り込み欄を持つHTMLの索引を書き出すファイル。報告書と同じディレクトリに置く。報告書のテンプレートでは`anchor`・`article_anchor`をidに使う（省略可）
+//! - `--format`：`--output`に書き出す形式。`json`（デフォルト）・`postgres`（`--dsn`のデータベースに書き込み、`--output`には何も書き出さない。`postgres` featureが必要）・`xlsx`（見つかった箇所をテキストとURLと共に1行に1箇所ずつ並べたシートと、法令ごとの件数のシートを持つExcelのファイル。テキストの範囲は`--context`に従う。Excelの上限に合わせて、32,767文字を超えるセルは切り詰め、1,048,575行を超える箇所は「箇所2」のような続きのシートに書く。`xlsx` featureが必要）・`pretty`（法令ごとに条項を一覧にした、人が読むための形式）・`grep`（`法令名:第十一条第一項`のように1行に1条項）・`lawtext`（[Lawtext](https://github.com/yamachig/Lawtext)の記法で、法令名と括弧書きの法令番号の後に条の見出しと条項を書き、附則の条項は「附　則」の行の後にまとめる）・`site`（`--output`をディレクトリとし、法令の一覧の`index.html`と、見つかった条項の`--context`の範囲のテキストを検索語に`<mark>`で印を付けて並べた法令ごと（`--versions all`では版ごと）のページを書き出す。印は検索と同じ判定の仕方で探すので、`--regex`・`--fuzzy`・`--fold-*`でも見つかった箇所に付く。GitHub Pagesなどにそのまま置ける）のいずれか。`grep`形式では、`--qa-sample`・`--report-template`・`--export-text`などの見つかった箇所を全て使う出力が無く、2以上の`--concurrency`・`--mmap`・`--in-memory-max-size`も指定しなければ、全ての法令の検索が終わるのを待たずに、法令ファイルを一つ読み終えるごとにその見つかった箇所を書き出す（途中でエラーになったファイルの箇所は書き出さない）。このときは、法令ファイルを一つずつバッファを通して読み、ファイルの大きさに応じた読み方の選択と、大きいファイルを本則と附則の境目で分けた並列の検索はしない
+//! - `--schema-version`：`--output`などに書き出すJSONの形式の版（デフォルトは1）。`2`を指定すると、法令ごとに`schema_version`（版）と、検索語をキー、その語が見つかった条項の一覧を値とする`hits`も書き出す。多くの検索語で一度に検索したときに、語ごとの検索結果として扱える
+//! - `--number-style`：`pretty`形式・`grep`形式と報告書の`title`で、条項の番号をどう書くか。`kanji`（「第十一条の二」、デフォルト）か`arabic`（「第11条の2」）
+//! - `--abbreviations`：`{"昭和二十二年法律第五十四号": "独禁法"}`のように、法令番号をキー、法令の略称を値とするJSONファイル。`pretty`・`grep`形式と報告書で法令番号の代わりに略称を使う（省略可）
+//! - `--versions`：同じ法令の版（`{法令ID}_{施行日}_{改正法令ID}.xml`）が作業ディレクトリに複数あるときに検索する版。`latest`（インデックスファイルに書かれた版だけ、デフォルト）か`all`（全ての版を検索し、結果に`version`として版を付ける）
+//! - `--read-buffer-size`：法令ファイルを読むときのバッファの大きさ（`512K`や`4M`のように1バイト以上を指定する、デフォルトは`256K`）
+//! - `--in-memory-max-size`：この大きさ以下の法令ファイルは一度に全てメモリに読み込んでから検索する（省略可）
+//! - `--mmap`：法令ファイルをメモリマップし、同期的に検索する。手元の速いディスクにある法令ファイルを検索するときに向く（`--in-memory-max-size`とは同時に使えない）。どちらも指定しないときは、ファイルの大きさと空いているメモリの量から、バッファを通して読むか、全てメモリに読み込むか、メモリマップするかを法令ファイルごとに選ぶ。全てメモリに読み込むかメモリマップした1MiB以上の法令ファイルは、本則と附則の境目で分けて並列に検索する
+//! - `--dedup`：同じ箇所で何度も見つかった検索語をどうまとめるか。`chapter`（項・号などの位置ごとに一つにまとめる、デフォルト）・`article`（条ごとに一つにまとめ、位置情報を条までにする）・`none`（まとめず、`matches`に見つかった回数だけ同じ箇所を書き出す。`chapter_data`・`hit_counts`と版2の`hits`の条項は一つずつにし、見つかった回数は`hit_counts`で数える）のいずれか
+//! - `--all-words`：`article`・`paragraph`・`sentence`のいずれかを指定すると、全ての検索語がその範囲（条・項・文（`Sentence`要素））に揃って現れたときだけ、見つかった箇所として記録する。`sentence`では、見出しなどの文の外のテキストは文とは別の範囲になる（省略可）
+//! - `--regex`：検索語を正規表現（[regex](https://docs.rs/regex/)の構文）として扱う。「することができ(る|ない)」のように、言い回しの違う語をまとめて検索できる。`--export-annotation`の出現範囲では、検索語をそのままの文字列として扱う
+//! - `--match-mode`：検索語とテキストの照らし合わせ方。`substring`（デフォルト）は部分文字列として探す。`morpheme`は文のテキストを[lindera](https://github.com/lindera-morphology/lindera)（IPADIC）で形態素に分け、形態素の境界で始まって終わるところだけを探すので、「権利」で「債権利息」が見つかることがない。`morpheme` featureを有効にしてビルドしたときだけ使え、`--regex`とは同時に使えない（省略可）
+//! - `--fuzzy`：空白を除いたテキストで、検索語との編集距離（Levenshtein距離）が指定した値以下の部分文字列も見つかったものとする。「取消し」と「取り消し」のような、古い法令での表記の揺れを拾える。値は検索語の文字数より小さくなければならず、`--regex`・`--match-mode`とは同時に使えない（省略可）
+//! - `--wildcard`：検索語の`＊`を任意の長さの文字列、`？`を任意の一文字として扱う。「第？条」や「届出＊義務」のように、正規表現を知らなくても言い回しの違う語をまとめて検索できる。`＊`はできるだけ短く当てはめる。`--regex`・`--match-mode`・`--fuzzy`とは同時に使えない（省略可）
+//! - `--fold-width`：全角と半角の英数字・記号、英字の大文字と小文字を区別せずに探す。「PCB」で「ＰＣＢ」も見つかる。`--regex`とは同時に使えない（省略可）
+//! - `--fold-numerals`：漢数字と全角・半角のアラビア数字で書かれた数を、その値で照らし合わせる。「第二種」で「第２種」や「第2種」も、「第三百三十一条」で「第331条」も見つかる。続けて書かれた数字は一つの数として読むので、数の一部分だけの検索語（「二」で「二十一」や「第十二条」の「二」）は見つからない。`--regex`とは同時に使えない（省略可）
+//! - `--fold-okurigana`：漢字のすぐ後に続く送り仮名を除いて照らし合わせる。「取り消し」「取消し」「取消」のように、時代によって送り仮名の付け方が違う語をまとめて探せる。除くのは、漢字の後の二文字までの平仮名のうち、「し」「り」「え」など送り仮名に使われるものだけからなり、平仮名でない文字が続くもので、「の」「に」などの助詞は除かない。「及び」の「び」のように送り仮名でないものも除くので、見つかる箇所は広くなる。`--regex`とは同時に使えない（省略可）
+//! - `--fold-kana`：片仮名と平仮名を区別せずに照らし合わせる。戦前の法令は「スル」「トキ」のように片仮名で書かれているので、「するとき」で「スルトキ」も見つかる。`--fold-okurigana`と一緒に指定すると、片仮名の送り仮名（「取リ消シ」）も除く。半角の片仮名はそろえない。`--regex`とは同時に使えない（省略可）
+//! - `--fold-old-kanji`：旧字体の漢字を新字体にしてから照らし合わせる。「国」で「國」も、「官庁」で「官廳」も見つかるので、旧字体のまま残る古い法令も新しい法令と同じ検索語で探せる。対応表は常用漢字表で字体が改められた漢字のうち法令でよく使われたもので、「辨」「瓣」「辯」はどれも「弁」にする。`--regex`とは同時に使えない（省略可）
+//! - `--captions-only`：条と項の見出し（`ArticleCaption`・`ParagraphCaption`）のテキストだけを検索する。テーマに関係する条項を探すのに使う。`--text-unit`・`--near`とは同時に使えない（省略可）
+//! - `--definitions-only`：「この法律において「個人情報」とは、…をいう。」の形の定義規定で、定義される語の部分（「とは、」より前。括弧があればその中）に検索語があるところだけを記録する。一つの文で複数の語を定義していればそれぞれを、「一　個人情報　…をいう。」のような号の形の定義規定では号の欄の最初の語を、定義される語とする。語が使われているところではなく、定義されているところを探せる。文ごとにテキストをつなげてから調べる。`--near`・`--captions-only`とは同時に使えない（省略可）
+//! - `--quoted-only`：「」で囲まれた部分の中にある検索語だけを記録する。「第一条中「許可」を「届出」に改める」のように、法令が語句を引用したり言い換えたりしているところを探すのに使う。入れ子になった括弧は外側の括弧の中に含める。括弧がテキストの区切りをまたいでもよいように、文ごと（`--text-unit`を指定すればその単位ごと）にテキストをつなげてから、括弧の中の部分を一つずつ調べるので、二つの括弧にまたがって見つかることは無い。`--snippet`の前後のテキストと`--offsets`の位置は、括弧の外も含めた元のテキストから求める（省略可）
+//! - `--snippet`：見つかった箇所それぞれに、見つかったテキストとその前後それぞれ指定した文字数のテキストを`snippet`として付ける。何が見つかったかを見るために法令XMLを開き直さなくてよくなる（省略可）
+//! - `--highlight`：`--snippet`で付けるテキストの中で、見つかった部分を二つの引数の文字列で囲む。`--highlight 【 】`や`--highlight "<em>" "</em>"`のように指定する（省略可）
+//! - `--offsets`：見つかった箇所それぞれに、見つかった文（`Sentence`要素）の`Num`属性の値（`sentence_num`）と、文の中での見つかったテキストの始まりの位置（`offset`。ルビの読み仮名を除いて数えた文字数）を付ける。アノテーションツールで見つかった場所をそのまま強調できる。`--text-unit paragraph`のときや、見出しなどの文の外で見つかったときは付けない（省略可）
+//! - `--first-match-per-article`：条ごとに最初に見つかった箇所だけを記録し、その条の残りはXMLを解析せずに終了タグまで読み飛ばす（`--article-metrics`のときは、条全体の文を数えるため読み飛ばさずに調べないだけにする）。検索語が現れる条だけを知りたいときに、大きな法令でもずっと速く検索できる。記録する条項と`matched_words`は、条ごとに最初に見つかったテキストのものだけになる。条の無い附則は附則全体を一つの条として扱う（省略可）
+//! - `--min-hits`：検索語が見つかった回数の合計が指定した値以上の条（`--all-words`を指定すればその範囲）だけを記録する。一度だけ触れている条を除き、その語を主に扱う条を探すのに使う。回数は`--dedup`によらず、テキストの中で重ならずに見つかった回数を数える。`--first-match-per-article`とは同時に使えない（省略可）
+//! - `--article-metrics`：見つかった箇所のある条それぞれについて、文（`Sentence`要素）のテキストの文字数（`char_count`。ルビの読み仮名を除く）、文の数（`sentence_count`）、文の平均の文字数（`average_sentence_length`）を`article_metrics`として付ける。検索語の有無と合わせて、条文の複雑さを大まかに比べるのに使う。条の無い本則や附則は、その全体を一つの条として数える。別表や様式などの中の文は、どの条にも数えない（省略可）
+//! - `--query`：`許可 AND (取消 OR 停止) NOT 附則`のような検索式（`--search-words`とは同時に使えない）。語は空白か括弧で区切り、演算子は強い順に`NOT`・`AND`・`OR`で、語を並べただけのときは`AND`でつなげたものとみなす。条ごと（`--all-words`を指定すればその範囲ごと）に評価し、成り立つ範囲で見つかった`NOT`の付いていない語だけを記録する
+//! - `--near`：`--near 許可 取消 10`のように二つの単語と文字数Nを指定すると、二つの単語が間にN文字以下しか挟まずに同じ文に現れたところだけを記録する（`--search-words`・`--query`とは同時に使えない）。文の中のテキストはまとめてから調べる（省略可）
+//! - `--near-unit`：`--near`の二つの単語が現れなければならない範囲。`sentence`（文、デフォルト）か`paragraph`（項）
+//! - `--text-unit`：`sentence`（文）か`paragraph`（項）を指定すると、その単位の中のテキストをルビの読みを除いてつなげてから検索する。ルビの付いた語や、文の途中の要素で区切られた語も見つけられる。`paragraph`のときは、見つかった箇所の位置情報を項までにする（省略可）
+//! - `--max-depth`：要素の入れ子の深さの上限（デフォルトは256）。これより深い法令XMLや、開始タグと終了タグが対応していない法令XMLは`E005`のエラーにする。壊れたファイルや細工されたファイルで状態が際限なく大きくなったり、見つかった箇所の位置を取り違えたりするのを防ぐ（省略可）
+//! - `--include-empty`：単語が含まれていなかった法令も、空の`chapter_data`と共に書き出す。検索しなかった法令と、検索したが見つからなかった法令を区別するのに使う
+//! - `--on-duplicate`：インデックスファイルに同じ法令ファイル（シンボリックリンクを辿って同じになるものも含む）や、同じ法令番号（`--versions all`では同じ版）の法令が二度以上あったときにどうするか。`warn`（警告（`W008`）を出し、どちらも書き出す、デフォルト）・`skip`（警告を出し、始めのものだけを書き出す）・`error`（エラーにして止める）のいずれか。`grep`形式で法令ファイルを一つ読み終えるごとに書き出すときも、重複かどうかを確かめてから書き出す
+//! - `--manifest`：処理した法令ファイルごとに、状態（`ok`・`error`・`skipped`・`filtered`。`filtered`は、見つかった箇所はあったが`--min-hits`や`--filter-expr`の条件に合わずに全て捨てたもの）・かかった時間・単語が含まれていた条項の数を、検索結果とは別にJSONLで書き出すファイル（省略可）
+//! - `--session-log`：コマンドライン引数（`--dsn`と`--nats-url`の値は伏せ、`--config`の値は絶対pathにする）・解決した検索と読み込みの設定・インデックスファイルと`--snapshot`の目録のSHA-256・開始時刻とかかった時間・統計情報・書き出したファイルそれぞれ（`site`形式の`--output`や`--export-text`などのディレクトリは、その中のファイルそれぞれ）のSHA-256を、一つのJSONにまとめて書き出すファイル名。論文の補足資料などで検索を再現できるようにするのに使う（省略可）
+//! - `--concurrency`：同時に検索する法令ファイルの数（デフォルトは1）。結果はインデックスファイルの順に書き出す
+//! - `--config`：検索のオプションを書いたTOMLの設定ファイル。書き方は「設定ファイル」を参照（省略可）
+//! - `--lang`：ヘルプ・コードの説明・報告書の索引と`site`形式のページの文言の言語。`ja`か`en`を指定する。省略したときは環境変数`LC_ALL`・`LC_MESSAGES`・`LANG`のロケールに従い、日本語のロケールかロケールが無いときは日本語にする。サブコマンドでは、サブコマンド名の後に書く。英語のヘルプは検索のオプションのものだけで、サブコマンドのヘルプは日本語のまま。ログに出す警告・エラーには選んだ言語のコードの説明を付けるが、詳しい内容の文言は翻訳しない（省略可）
+//! - `--nats-url`・`--nats-subject`：検索できた法令ごとに、`--output`と同じ検索結果のJSONをNATSのsubjectに送る。`nats` featureを有効にしてビルドしたときだけ使える（省略可）
+//! - `--plugin`：見つかった箇所を一つずつ渡し、捨てたり`tags`・`attributes`を付けたりさせるWASMのプラグインのファイル。複数指定でき、指定した順に呼ぶ。プラグインの作り方は「プラグイン」を参照。`plugin` featureを有効にしてビルドしたときだけ使える（省略可）
+//! - `--filter-expr`：見つかった箇所一つごとに評価し、`true`になった箇所だけを残す[rhai](https://rhai.rs/)の式。`scope == "suppl" && count > 2`のように書く。使える変数は「絞り込みの式」を参照。`filter-expr` featureを有効にしてビルドしたときだけ使える（省略可）
+//! - `--relevance-model`・`--relevance-query`・`--relevance-output`：`--relevance-model`のディレクトリに置いたcross-encoderの`model.onnx`と`tokenizer.json`で、見つかった箇所それぞれのテキスト（`--snippet`があればその範囲、無ければ項のテキスト）と`--relevance-query`の調べたいことを説明する文の関連の強さを採点し、点数の高い順に`id`・`num`・`chapter`・`word`・`score`・`text`を`--relevance-output`のJSONLに書き出す。数千件の見つかった箇所を、関連の強そうなものから目で確かめるのに使う。点数はモデルの出力（ロジット）で、モデルによって範囲が違う。三つとも共に指定する。`relevance` featureを有効にしてビルドしたときだけ使える（省略可）
+//! - `--dsn`：`--format postgres`で検索結果を書き込むPostgreSQLのデータベースの接続文字列。法令を`laws`テーブルに、見つかった箇所を`matches`テーブルに書き込み、同じ法令を書き込み直したときは置き換える。`--postgres-batch-size`（デフォルトは100）件の法令ごとに一つのトランザクションで書き込む。`postgres` featureを有効にしてビルドしたときだけ使える（省略可）
+//!
+//! # 条文の取り出し
+//!
+//! ```sh
+//! search_article_with_word hydrate output.json --output texts.jsonl --work "path/to/law_xml_directory"
+//! ```
+//!
+//! で、検索結果のJSONファイルに書かれた条項それぞれの条文のテキストを、1行1条項のJSONLで書き出します。
+//! `--work`の代わりに`--api`を指定すると、[e-Gov法令API](https://elaws.e-gov.go.jp/apitop/)から条文を取得します（附則の条項は取得できません）。
+//! APIへのリクエストは`--http-interval-ms`（デフォルトは1000）ミリ秒以上の間隔を空けて送り、失敗したときは`--http-retries`（デフォルトは3）回まで間隔を倍にしながら再試行します。`--http-cache-dir`を指定すると、レスポンスをそのディレクトリに保存して再利用します。
+//!
+//! # 作業ディレクトリの更新確認
+//!
+//! ```sh
+//! search_article_with_word check-updates --since 20230401 --work "path/to/law_xml_directory" --output updates.json
+//! ```
+//!
+//! で、指定した日から今日までにe-Gov法令APIで更新された法令を調べ、作業ディレクトリのファイルについて
+//! `stale`（古い）・`up_to_date`（ファイル名の施行日から最新とわかる）・`not_in_corpus`（ファイルが無い）のいずれかを書き出します。
+//! `--download dir`を指定すると、`up_to_date`以外の法令の最新のXMLを`dir/{法令ID}_{施行日}.xml`にダウンロードします。ファイル名の施行日から、次の確認では`up_to_date`になります。ダウンロードできなかった法令は警告（`W003`）を出して飛ばします。
+//! `--http-retries`などのオプションは`hydrate`と同じです。
+//!
+//! # 作業ディレクトリの目録
+//!
+//! ```sh
+//! search_article_with_word snapshot --work "path/to/law_xml_directory" --output snapshot.json
+//! ```
+//!
+//! で、作業ディレクトリにある法令XMLファイルそれぞれのpath・SHA-256・法令番号と作成日を記録した目録を書き出します。
+//! 検索のときに`--snapshot snapshot.json`を指定すると、目録のファイルのSHA-256（`sha256sum snapshot.json`の値と同じ）が検索結果の法令ごとの`snapshot_sha256`と統計情報に書き込まれるので、検索結果がどの版の法令XMLファイル群から得られたのかを示せます。
+//!
+//! # 検索結果の再現性の確認
+//!
+//! ```sh
+//! search_article_with_word verify output.json --work "path/to/law_xml_directory" --output verify.json
+//! ```
+//!
+//! で、検索結果のJSONファイルから無作為に抽出した条項（`--sample`で件数を指定、デフォルトは100）について、今の作業ディレクトリで検索し直しても同じ条項が見つかるかどうかを確かめます。
+//! 検索し直すときには検索結果に記録されている単語を使います（`--search-word`で指定することもできます）。
+//! `--session-log`に検索したときの`--session-log`の記録を指定すると、記録されたコマンドライン引数から検索語と`--dedup`・`--regex`・`--fold-numerals`などの照らし合わせ方を作り直して検索し直します。記録が壊れていたり、今のバージョンで解釈できない引数を含んでいたりすれば、エラーにします。
+//! 指定しないときは既定の照らし合わせ方で検索し直すので、`--regex`・`--fuzzy`・`--wildcard`・`--fold-*`・`--dedup`などを指定して作った検索結果では、変わっていない条項も`mismatched`になります（そのときは警告を出します）。
+//! 再現できなかった条項があったときは、その一覧を`--output`に書き出し、エラーで終了します。
+//! `--seed`で乱数の種を指定すると、毎回同じ条項を抽出して確かめます。
+//!
+//! # 項の無作為抽出
+//!
+//! ```sh
+//! search_article_with_word sample-paragraphs --work "path/to/law_xml_directory" --output sample.jsonl --sample 500 --seed 42
+//! ```
+//!
+//! で、作業ディレクトリにある全ての法令の項から`--sample`個（デフォルトは100）の項を一様に無作為抽出し、一行に一つずつ`num`（法令番号）・`title`（法令名）・`chapter`（項の位置）・`text`（ルビの読みを除いた項のテキスト）を書き出します。
+//! 検索の精度を調べるための評価用のデータを作るのに使います。
+//! 項ごとに`--seed`（省略したときは0）と法令番号・項の位置から決まる乱数を割り当て、その小さいものを選ぶので、種と作業ディレクトリが同じなら`--concurrency`で同時に読むファイルの数を変えても同じ項を抽出します。
+//!
+//! # 検索結果の評価
+//!
+//! ```sh
+//! search_article_with_word evaluate --gold gold.jsonl --results output.json --output evaluation.json
+//! ```
+//!
+//! で、人手で正解を付けた箇所の一覧（`--gold`）と検索結果を比べ、適合率（`precision`）・再現率（`recall`）・F値（`f1`）を書き出します（`--output`を省略したときは標準出力）。
+//! 正解の一覧は一行に一つの`{"id": "見つかった箇所のID", "relevant": true}`を書いたJSONLで、`relevant`は省略すると`true`になります。
+//! IDは「検索結果のID」のもので、表記のそろえ方や照らし合わせ方を変えて検索し直しても同じ箇所なら同じになるので、検索の仕方を変えたときの精度の変化を数字で比べられます。
+//! 検索結果にあって正解の一覧に無い箇所は`unjudged`として数え、適合率の計算には含めません。誤って見つかった箇所と見落とした箇所のIDは、`false_positive_ids`・`false_negative_ids`に書き出します。
+//! # 警告・エラーのコード
+//!
+//! 警告・エラーには変わらないコードが付いており、ログには`[W002] law file not found: ...`のようにコードを先頭に付けて出力します。
+//! `--error-report`を指定したときは、ログに出した警告・エラーを全て、`{"code": "W002", "message": "..."}`のような1行1件のJSONでそのファイルにも書き出します。
+//! 検索中のログは法令ファイルごとの`law_file`スパンの中に出力され、スパンには`file`（ファイルへのpath）・`law_num`（法令番号）・`matches`（検索語が見つかった箇所の数）が付きます。
+//! コードとその説明の一覧は`search_article_with_word codes`でJSONとして出力できます。
+//! 検索中に`E`で始まるエラーが起きた法令ファイルは飛ばして検索を続け、飛ばしたファイルの数を統計情報の`skipped_file_count`に書き込みます。
+//!
+//! | コード | 内容 |
+//! | --- | --- |
+//! | `E001` | 法令ファイルを開けなかった |
+//! | `E002` | 法令XMLの読み込み・解析に失敗した |
+//! | `E003` | 法令ファイルが空（0バイト）だった |
+//! | `E004` | 法令XMLが途中で切れていた |
+//! | `E005` | 法令XMLの要素の入れ子が深すぎるか、開始タグと終了タグが対応していなかった |
+//! | `E006` | 検索した法令に対する処理が失敗した |
+//! | `W001` | 条・項・号などの要素にNum属性が無かった |
+//! | `W002` | 法令番号に対応する法令ファイルが作業ディレクトリに無かった |
+//! | `W003` | e-Gov法令APIから条文を取得できなかった |
+//! | `W004` | e-Gov法令APIから更新法令一覧を取得できなかった |
+//! | `W005` | 検索し直した結果が記録された検索結果と一致しなかった |
+//! | `W006` | HTTPリクエストが失敗したので再試行した |
+//! | `W007` | 見積もったメモリの量が上限を超えたので、テキストの取り出しをやめた |
+//! | `W008` | 同じ法令ファイルか、同じ法令番号の法令を二度検索しようとした |
+//! | `W009` | XMLファイルの最上位の要素がLawではなく、法令XMLではなかった |
+//!
+//! # 検索結果のID
+//!
+//! 検索語が見つかった箇所それぞれに、法令番号・条項の位置情報・検索語から決まる16桁のIDを付けます。
+//! `--dedup none`で一つの条項に同じ検索語の箇所が複数あるときは、二つ目からは法令XMLの先頭から数えて何番目の箇所かもIDに含めるので、箇所ごとに違うIDになります（一つ目の箇所のIDは、まとめたときのIDと同じです）。
+//! `--versions all`で同じ法令の複数の版を検索したときは、法令の版もIDに含めるので、同じ条項でも版ごとに違うIDになります。
+//! IDは実行し直しても変わらないので、出力の形式をまたいで同じ箇所を追いかけたり、重複を取り除いたりするのに使えます。
+//! `--output`のJSONでは法令ごとの`matches`に、`--export-text-jsonl`などでは`match_ids`に、`grep`形式では各行の最後に書き出します。
+//!
+//! # 検索結果の比較
+//!
+//! ```sh
+//! search_article_with_word diff old.json new.json --output diff.json
+//! ```
+//!
+//! で、二つの検索結果を検索語が見つかった箇所のIDで突き合わせ、箇所ごとに`added`（新しい検索結果にだけある）・`removed`（古い検索結果にだけある）・`moved`（条番号が変わった）のいずれかを書き出します。
+//! 条の見出し（「（定義）」など）と検索語、項・号の番号が同じで条番号だけが違う箇所は、改正で条番号が変わったものとして`moved`にします。
+//! `--old-work`と`--new-work`にそれぞれの検索結果を得たときの作業ディレクトリを指定すると、同じ法令の古い版と新しい版の条を、条の見出しと条文の文字bigramの類似度（`--align-threshold`で下限を指定、デフォルトは0.6）から対応付けます。
+//! 旧第十条と新第十二条のように対応付けられた条の同じ位置にある箇所も`moved`にします。
+//!
+//! # プラグイン
+//!
+//! `--plugin`で指定するプラグインは、次のものをexportするWASMのモジュールです。見つかった箇所一つごとに呼ばれます。
+//!
+//! - `memory`：線形メモリ
+//! - `alloc(len: i32) -> i32`：入力のJSONを書き込む`len`バイトの領域を確保し、その先頭の位置を返す
+//! - `process(ptr: i32, len: i32) -> i64`：入力のJSONを受け取り、出力のJSONの位置を上位32ビットに、長さを下位32ビットにして返す
+//! - `dealloc(ptr: i32, len: i32)`：`alloc`で確保した入力の領域と、`process`が返した出力の領域を解放する。出力を読み終えたら、入力、出力の順に呼ぶ
+//!
+//! 見つかった箇所一つについての呼び出し（`alloc`・`process`・`dealloc`）で使える計算の量には上限（燃料で10億）があり、超えたとき（止まらないプラグインなど）はエラーにして検索を止めます。
+//!
+//! 入力は`{"num": 法令番号, "match": 見つかった箇所, "text": その箇所を含む項のテキスト}`のJSONです。
+//! 出力は`{"reject": true}`（その箇所を捨てる）や`{"tags": ["要確認"], "attributes": {"分類": "許認可"}}`（その箇所にタグと属性を付ける）のようなJSONで、キーはどれも省略できます。
+//! 付けたタグと属性は、`--output`のJSONで見つかった箇所ごとの`tags`・`attributes`に書き出します。
+//! 見つかった箇所が全て捨てられた条項は、`chapter_data`からも除きます。
+//!
+//! # 絞り込みの式
+//!
+//! `--filter-expr`では、見つかった箇所一つごとに評価する[rhai](https://rhai.rs/)の式を指定します。
+//! 式が`true`になった箇所だけを残し、見つかった箇所が全て除かれた条項は`chapter_data`からも除きます。
+//! 式の中では次の変数を使えます。
+//!
+//! - `law_num`：法令番号
+//! - `title`：法令名（無ければ空文字列）
+//! - `word`：見つかった検索語
+//! - `synonym_of`：`--synonyms`の同義語で見つかったときの元の検索語（無ければ空文字列）
+//! - `scope`：本則なら`"main"`、附則なら`"suppl"`
+//! - `article`・`paragraph`・`item`：条・項・号の番号（無ければ空文字列）
+//! - `caption`：条の見出し（無ければ空文字列）
+//! - `count`：その条項で検索語が見つかった回数
+//! - `tags`：`--plugin`のプラグインが付けたタグの配列
+//!
+//! `--filter-expr 'scope == "suppl" && count > 2'`は附則の中で検索語が三回以上見つかった条項だけを、
+//! `--filter-expr 'caption.contains("罰則") || word != "許可"'`は「許可」を見出しに「罰則」を含む条の中でだけ残します。
+//! プラグインがあれば、プラグインの後に評価します。
+//! 式の入れ子の深さは64まで、一度の評価での演算の回数は100,000回までで、超えたときはエラーにします。
+//!
+//! # 設定ファイル
+//!
+//! 検索のオプションは、`--config search.toml`でTOMLの設定ファイルにまとめて書けます。
+//! キーはオプションの長い名前で、フラグは真偽値、複数指定できるオプションや複数の値を取るオプションは配列で書きます。
+//! 同じオプションをコマンドラインでも指定したときは、コマンドラインのものを使います（複数指定できるオプションは、設定ファイルの値に足します）。
+//!
+//! ```toml
+//! search-words = ["許可", "認可"]
+//! dedup = "article"
+//! fold-width = true
+//! near = ["許可", "取消", "10"]
+//! ```
+//!
+//! ```sh
+//! search_article_with_word config validate search.toml
+//! ```
+//!
+//! で、検索を始める前に設定ファイルに知らないキー・型の誤り・同時に使えないオプションの組み合わせが無いかを確かめられます。
+//! 問題があればキーと共に一行に一つずつ書き出して、エラーで終わります。
+//! `search_article_with_word config schema`で、設定ファイルに書けるキーと値の型をJSON Schemaとして出力できます。エディタの補完や検査に使えます。
+//! # 報告書のテンプレート
+//!
+//! `--report-template`で指定するテンプレートには、次の値が渡されます。
+//!
+//! - `search_words`：検索した単語のリスト
+//! - `laws`：単語が含まれていた法令のリスト。それぞれ`num`（法令番号）・`title`（法令名）・`short_name`（`--abbreviations`で指定した略称）・`version`（`--versions all`のときの法令の版）・`matched_words`（含まれていた単語）・`chapters`（条項のリスト）・`anchor`（法令番号と版から決まるHTMLのid）を持つ
+//! - `laws.[].chapters`：条項の位置情報（`article`・`paragraph`など）と、「第十一条の二第一項」のような表記の`title`・その条のHTMLのidの`article_anchor`・その条の「第十一条の二」のような表記の`article_title`・その条の最初の条項かどうかの`is_article_start`を持つ
+//! - `stats`：`--stats`で書き出すものと同じ統計情報
+//!
+//! ```handlebars
+//! {{#each laws}}
+//! ## {{num}}
+//! {{#each chapters}}
+//! - {{title}}
+//! {{/each}}
+//! {{/each}}
+//! ```
 //!
 
 use anyhow::Result;
+use date::Date;
+use diagnostic::{report_warning, Code, Diagnostic};
 use encoding_rs::Encoding;
-use quick_xml::{encoding, events::Event, Reader};
+use law_num::Era;
+use law_tree::LawTree;
+use matcher::{is_near, Matchers};
+use memchr::memmem;
+use memmap2::Mmap;
+use metrics::ArticleMetrics;
+use num::Num;
+use quick_xml::{
+  encoding,
+  events::{BytesEnd, BytesStart, Event},
+  name::QName,
+  Reader,
+};
+use search_config::{Dedup, SearchConfig, TextUnit};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+  collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+  sync::Arc,
+  time::Instant,
+};
 use tokio::{
   fs::File,
-  io::{AsyncReadExt, BufReader},
+  io::{AsyncBufRead, AsyncReadExt},
 };
 use tracing::*;
 
+pub mod abbreviation;
+pub mod align;
+pub mod annotation;
+pub mod batch;
+pub mod bibliography;
+pub mod citation;
+pub mod config_file;
+pub mod cooccurrence;
+pub mod corpus;
+pub mod date;
+pub mod definition;
+pub mod diagnostic;
+pub mod diff;
+pub mod egov;
+pub mod evaluate;
+#[cfg(feature = "filter-expr")]
+pub mod filter_expr;
+pub mod fold;
+pub mod format;
+pub mod http;
+pub mod hydrate;
+pub mod i18n;
+pub mod kansuji;
+pub mod kyujitai;
+pub mod law_num;
+pub mod law_tree;
+pub mod manifest;
+pub mod matcher;
+pub mod memory;
+pub mod metrics;
+#[cfg(feature = "morpheme")]
+pub mod morpheme;
+#[cfg(feature = "nats")]
+pub mod nats;
+pub mod num;
+#[cfg(feature = "plugin")]
+pub mod plugin;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod query;
+pub mod quote;
+pub mod read_strategy;
+#[cfg(feature = "relevance")]
+pub mod relevance;
+pub mod report;
+pub mod sample;
+pub mod search_config;
+pub mod session;
+pub mod site;
+pub mod snapshot;
+pub mod snippet;
+pub mod speech;
+pub mod stats;
+pub mod synonym;
+pub mod update;
+pub mod verify;
+#[cfg(feature = "xlsx")]
+pub mod xlsx;
+
+pub use batch::{search_many, search_many_with};
+pub use hydrate::{hydrate_chapter, hydrate_chapters, hydrate_parsed};
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 pub struct LawParagraph {
   /// 法令番号
   pub num: String,
+  /// 法令名（`LawTitle`要素のテキスト）
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub title: Option<String>,
+  /// 公布日（「19470414」の形式）
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub promulgation_date: Option<String>,
   /// 見出しと章番号
   pub chapter_data: Vec<Chapter>,
+  /// `chapter_data`のそれぞれの条項で見つかった検索語。`chapter_data`と同じ順に並べる
+  #[serde(skip_serializing_if = "Vec::is_empty", default)]
+  pub chapter_words: Vec<Vec<String>>,
+  /// `chapter_data`のそれぞれの条項で検索語が見つかった回数。`chapter_data`と同じ順に並べる
+  #[serde(skip_serializing_if = "Vec::is_empty", default)]
+  pub hit_counts: Vec<usize>,
+  /// 法令中に含まれていた検索語
+  #[serde(skip_serializing_if = "Vec::is_empty", default)]
+  pub matched_words: Vec<String>,
+  /// 検索語が見つかった箇所それぞれ
+  #[serde(skip_serializing_if = "Vec::is_empty", default)]
+  pub matches: Vec<Match>,
+  /// 法令の版（ファイルの名前の`{施行日}_{改正法令ID}`の部分）。`--versions all`のときに付ける
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub version: Option<String>,
+  /// 出力の形式の版。2以上の版で出力するときに付ける
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub schema_version: Option<u32>,
+  /// 検索した法令XMLの目録（`snapshot`サブコマンドで作ったもの）のSHA-256。`--snapshot`のときに付ける
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub snapshot_sha256: Option<String>,
+  /// 検索語ごとの、その語が見つかった条項の一覧。版2で出力するときに付ける
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub hits: Option<BTreeMap<String, Vec<Chapter>>>,
+  /// 見つかった箇所のある条それぞれの、文章の長さの指標。`--article-metrics`のときに付ける
+  #[serde(skip_serializing_if = "Vec::is_empty", default)]
+  pub article_metrics: Vec<ArticleMetrics>,
+  /// 見つかった箇所を`min_hits`や`--filter-expr`の条件に合わないので捨てたかどうか。
+  /// マニフェストの状態を決めるのに使い、出力には書き出さない
+  #[serde(skip)]
+  pub is_filtered: bool,
+}
+
+/// 出力の形式の最新の版
+pub const LATEST_SCHEMA_VERSION: u32 = 2;
+
+impl LawParagraph {
+  /// 検索語ごとの、その語が見つかった条項の一覧。条項は`chapter_data`の順に並べる
+  pub fn word_hits(&self) -> BTreeMap<String, Vec<Chapter>> {
+    let mut hits: BTreeMap<String, Vec<Chapter>> = BTreeMap::new();
+    for (chapter, words) in self.chapter_data.iter().zip(self.chapter_words.iter()) {
+      for word in words.iter() {
+        hits.entry(word.clone()).or_default().push(chapter.clone());
+      }
+    }
+    hits
+  }
+
+  /// 法令の版`version`を付け、見つかった箇所のIDを版も含めて決まるものにする
+  pub fn set_version(&mut self, version: Option<String>) {
+    for m in self.matches.iter_mut() {
+      m.id = versioned_match_id(&m.id, version.as_deref());
+    }
+    self.version = version;
+  }
+
+  /// 出力の形式の版`schema_version`に合わせて、その版で付けるものを付ける
+  pub fn set_schema_version(&mut self, schema_version: u32) {
+    if schema_version >= 2 {
+      self.schema_version = Some(schema_version);
+      self.hits = Some(self.word_hits());
+    }
+  }
+
+  /// 見つかった箇所とそのテキストが使うメモリのおおよその量（バイト数）
+  pub fn approx_bytes(&self) -> usize {
+    let match_bytes = self
+      .matches
+      .iter()
+      .map(|m| {
+        std::mem::size_of::<Match>()
+          + m.id.len()
+          + m.word.len()
+          + m.snippet.as_ref().map_or(0, |snippet| snippet.len())
+      })
+      .sum::<usize>();
+    self.num.len() + self.chapter_data.len() * std::mem::size_of::<Chapter>() + match_bytes
+  }
+
+  /// 見つかった箇所から見つかったテキストを除き、位置だけを残す
+  pub fn drop_snippets(&mut self) {
+    for m in self.matches.iter_mut() {
+      m.snippet = None;
+    }
+  }
+
+  /// 条項`chapter`で見つかった検索語の一覧
+  pub fn words_at(&self, chapter: &Chapter) -> &[String] {
+    self
+      .chapter_data
+      .iter()
+      .position(|c| c == chapter)
+      .and_then(|i| self.chapter_words.get(i))
+      .map(|words| words.as_slice())
+      .unwrap_or(&[])
+  }
+
+  /// 条項`chapter`そのもので検索語が見つかった箇所のIDの一覧
+  pub fn match_ids_at(&self, chapter: &Chapter) -> Vec<String> {
+    self
+      .matches
+      .iter()
+      .filter(|m| &m.chapter == chapter)
+      .map(|m| m.id.clone())
+      .collect()
+  }
+
+  /// 見つかった箇所のうち`f`が`true`を返すものだけを残す。
+  /// 見つかった箇所が全て無くなった条項と検索語は、`chapter_data`と`matched_words`からも除く
+  pub fn retain_matches(&mut self, f: impl FnMut(&Match) -> bool) {
+    let had_matches = self
+      .chapter_data
+      .iter()
+      .map(|chapter| self.matches.iter().any(|m| &m.chapter == chapter))
+      .collect::<Vec<_>>();
+    let had_words = self
+      .matches
+      .iter()
+      .map(|m| m.word.clone())
+      .collect::<HashSet<_>>();
+    self.matches.retain(f);
+    let is_kept = self
+      .chapter_data
+      .iter()
+      .zip(had_matches)
+      .map(|(chapter, had_matches)| {
+        !had_matches || self.matches.iter().any(|m| &m.chapter == chapter)
+      })
+      .collect::<Vec<_>>();
+    retain_parallel(&mut self.chapter_data, &is_kept);
+    retain_parallel(&mut self.chapter_words, &is_kept);
+    retain_parallel(&mut self.hit_counts, &is_kept);
+    let chapter_data = &self.chapter_data;
+    self.article_metrics.retain(|metrics| {
+      chapter_data
+        .iter()
+        .any(|chapter| chapter.article_level() == metrics.chapter)
+    });
+    let matches = &self.matches;
+    self
+      .matched_words
+      .retain(|word| !had_words.contains(word) || matches.iter().any(|m| &m.word == word));
+  }
+
+  /// `target`の中で検索語が見つかった箇所のIDの一覧
+  pub fn match_ids_within(&self, target: &Chapter) -> Vec<String> {
+    self
+      .matches
+      .iter()
+      .filter(|m| m.chapter.is_within(target))
+      .map(|m| m.id.clone())
+      .collect()
+  }
+}
+
+/// `chapter_data`と同じ順に並べた一覧から、`is_kept`が`false`の条項のものを除く
+fn retain_parallel<T>(lst: &mut Vec<T>, is_kept: &[bool]) {
+  if lst.len() == is_kept.len() {
+    let mut is_kept = is_kept.iter();
+    lst.retain(|_| *is_kept.next().unwrap_or(&true));
+  }
+}
+
+/// 検索語が見つかった箇所一つ
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct Match {
+  /// 法令番号・条項の位置情報・検索語から決まるID
+  pub id: String,
+  pub chapter: Chapter,
+  pub word: String,
+  /// 条の見出し（「（定義）」など）。条番号が変わったときに同じ条を探すのに使う
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub article_caption: Option<String>,
+  /// 見つかったテキストとその前後のテキスト。`--snippet`のときに付ける
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub snippet: Option<String>,
+  /// 見つかった文（`Sentence`要素）の`Num`属性の値。`--offsets`のときに付ける
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub sentence_num: Option<usize>,
+  /// 見つかった文の中での、見つかったテキストの始まりの位置（ルビの読み仮名を除いて数えた文字数）。`--offsets`のときに付ける
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub offset: Option<usize>,
+  /// `--synonyms`の同義語で見つかったときの、その同義語を加えた元の検索語。`word`は実際に見つかった同義語
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub synonym_of: Option<String>,
+  /// `--plugin`のプラグインが付けたタグ
+  #[serde(skip_serializing_if = "Vec::is_empty", default)]
+  pub tags: Vec<String>,
+  /// `--plugin`のプラグインが付けた属性
+  #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+  pub attributes: BTreeMap<String, String>,
+}
+
+/// 法令番号・条項の位置情報・検索語から、実行のたびに変わらないIDを作る
+pub fn match_id(law_num: &str, chapter: &Chapter, word: &str) -> String {
+  let chapter_json_str = serde_json::to_string(chapter).unwrap_or_default();
+  let key = format!("{law_num}\u{1f}{chapter_json_str}\u{1f}{word}");
+  let digest = Sha256::digest(key.as_bytes());
+  format!("{digest:x}")[..16].to_string()
+}
+
+/// 箇所のID`id`に、法令の版`version`も含めたID。`--versions all`で同じ条項が複数の版にあっても、版ごとに違うIDになる。
+/// 版が無ければ`id`のままにする
+pub fn versioned_match_id(id: &str, version: Option<&str>) -> String {
+  match version {
+    Some(version) => {
+      let key = format!("{id}\u{1f}{version}");
+      let digest = Sha256::digest(key.as_bytes());
+      format!("{digest:x}")[..16].to_string()
+    }
+    None => id.to_string(),
+  }
+}
+
+/// 同じ条項で同じ検索語が見つかった`occurrence`番目（0から数える）の箇所のID。
+/// `--dedup none`で一つの条項の箇所が複数あっても、それぞれに違うIDを付ける。
+/// 最初の箇所のIDは[`match_id`]と同じなので、まとめたときの箇所のIDとも突き合わせられる
+pub fn occurrence_match_id(
+  law_num: &str,
+  chapter: &Chapter,
+  word: &str,
+  occurrence: usize,
+) -> String {
+  if occurrence == 0 {
+    return match_id(law_num, chapter, word);
+  }
+  let chapter_json_str = serde_json::to_string(chapter).unwrap_or_default();
+  let key = format!("{law_num}\u{1f}{chapter_json_str}\u{1f}{word}\u{1f}{occurrence}");
+  let digest = Sha256::digest(key.as_bytes());
+  format!("{digest:x}")[..16].to_string()
 }
 
 /// 章・節などを表す
@@ -73,406 +650,1574 @@ pub struct Chapter {
   pub suppl_provision_title: Option<String>,
 }
 
-/// 指定された単語が含まれる条があったとき、その条番号等のデータのみを保存する。
-/// 後でこのデータをもとに実際の条文を再度取得するのに使いたい。
-pub async fn search_xml(
-  search_str_lst: &[String],
-  reader: &mut Reader<BufReader<File>>,
-) -> Result<LawParagraph> {
+impl Chapter {
+  /// `other`が表す条項の中（`other`そのものも含む）にあるかどうか
+  pub fn is_within(&self, other: &Chapter) -> bool {
+    self.part == other.part
+      && self.chapter == other.chapter
+      && self.section == other.section
+      && self.subsection == other.subsection
+      && self.division == other.division
+      && self.article == other.article
+      && self.suppl_provision_title == other.suppl_provision_title
+      && (other.paragraph.is_none() || self.paragraph == other.paragraph)
+      && (other.item.is_none() || self.item == other.item)
+      && (other.sub_item.is_none() || self.sub_item == other.sub_item)
+  }
+
+  /// 号以下の情報を取り除き、項までの位置情報にしたもの
+  pub fn paragraph_level(&self) -> Chapter {
+    Chapter {
+      item: None,
+      sub_item: None,
+      ..self.clone()
+    }
+  }
+
+  /// 項以下の情報を取り除き、条までの位置情報にしたもの
+  pub fn article_level(&self) -> Chapter {
+    Chapter {
+      paragraph: None,
+      ..self.paragraph_level()
+    }
+  }
+
+  /// 条番号を正規化したもの
+  pub fn article_num(&self) -> Option<Num> {
+    Num::parse(&self.article)
+  }
+
+  /// 項番号を正規化したもの
+  pub fn paragraph_num(&self) -> Option<Num> {
+    self.paragraph.as_deref().and_then(Num::parse)
+  }
+
+  /// 号番号を正規化したもの
+  pub fn item_num(&self) -> Option<Num> {
+    self.item.as_deref().and_then(Num::parse)
+  }
+
+  /// 条・項・号の番号の鍵を`/`でつないだもの（「第三条の二第一項第三号」なら`3-2/1/3`）。
+  /// 附則の条項には先頭に`suppl:{改正法令番号}/`を付ける
+  pub fn key(&self) -> String {
+    let mut lst = vec![];
+    if let Some(title) = &self.suppl_provision_title {
+      lst.push(format!("suppl:{title}"));
+    }
+    let nums = [
+      Some(self.article.as_str()),
+      self.paragraph.as_deref(),
+      self.item.as_deref(),
+    ];
+    for num in nums.iter().flatten() {
+      lst.push(match Num::parse(num) {
+        Some(num) => num.key(),
+        None => num.to_string(),
+      });
+    }
+    lst.join("/")
+  }
+
+  /// 「第十一条の二第一項第三号」のような表示用の表記
+  pub fn display_title(&self) -> String {
+    citation::chapter_title(self)
+  }
+}
+
+/// 開始タグから属性の値を取り出す
+pub(crate) fn get_attribute(tag: &BytesStart, name: &str) -> Option<String> {
   let utf8 = Encoding::for_label(b"utf-8").unwrap();
+  tag
+    .attributes()
+    .find(|res| encoding::decode(res.as_ref().unwrap().key.0, utf8).unwrap() == name)
+    .map(|res| {
+      encoding::decode(&res.unwrap().value, utf8)
+        .unwrap()
+        .to_string()
+    })
+}
 
-  let mut lst = vec![];
-  let mut buf = Vec::new();
-  let mut chapter_num = Chapter::default();
-  let mut law_num = String::new();
-  let mut is_law_num_mode = false;
+/// `Law`要素の`Era`・`Year`・`PromulgateMonth`・`PromulgateDay`属性から公布日を求める
+fn promulgation_date(tag: &BytesStart) -> Option<Date> {
+  let era = Era::from_attribute(&get_attribute(tag, "Era")?)?;
+  let year = get_attribute(tag, "Year")?.parse::<usize>().ok()?;
+  let month = get_attribute(tag, "PromulgateMonth")?.parse().ok()?;
+  let day = get_attribute(tag, "PromulgateDay")?.parse().ok()?;
+  if year == 0 {
+    return None;
+  }
+  Date::new(era.first_year() + year - 1, month, day)
+}
 
-  reader.trim_text(true);
-  loop {
-    match reader.read_event_into_async(&mut buf).await {
-      Ok(Event::Start(tag)) => match tag.name().as_ref() {
-        b"LawNum" => is_law_num_mode = true,
-        b"Part" => {
-          chapter_num = Chapter {
-            part: {
-              match chapter_num.part {
-                Some(n) => Some(n + 1),
-                None => Some(1),
-              }
-            },
-            chapter: None,
-            section: None,
-            subsection: None,
-            division: None,
-            article: chapter_num.article,
-            paragraph: None,
-            item: None,
-            sub_item: None,
-            suppl_provision_title: chapter_num.suppl_provision_title,
-          };
+/// 開始タグから`Num`属性の値を取り出す。無いときは警告を出して空文字列を返す
+fn get_num_attribute(tag: &BytesStart) -> String {
+  get_attribute(tag, "Num").unwrap_or_else(|| {
+    report_warning(
+      Code::MissingNumAttribute,
+      format!(
+        "missing Num attribute: {}",
+        String::from_utf8_lossy(tag.name().as_ref())
+      ),
+    );
+    String::new()
+  })
+}
+
+/// 開始タグに応じて、現在いる条項の位置情報を更新したものを返す
+pub(crate) fn next_chapter(chapter_num: Chapter, tag: &BytesStart) -> Chapter {
+  match tag.name().as_ref() {
+    b"Part" => Chapter {
+      part: {
+        match chapter_num.part {
+          Some(n) => Some(n + 1),
+          None => Some(1),
         }
-        b"Chapter" => {
-          chapter_num = Chapter {
-            part: chapter_num.part,
-            chapter: {
-              match chapter_num.chapter {
-                Some(n) => Some(n + 1),
-                None => Some(1),
-              }
-            },
-            section: None,
-            subsection: None,
-            division: None,
-            article: chapter_num.article,
-            paragraph: None,
-            item: None,
-            sub_item: None,
-            suppl_provision_title: chapter_num.suppl_provision_title,
-          }
+      },
+      chapter: None,
+      section: None,
+      subsection: None,
+      division: None,
+      article: chapter_num.article,
+      paragraph: None,
+      item: None,
+      sub_item: None,
+      suppl_provision_title: chapter_num.suppl_provision_title,
+    },
+    b"Chapter" => Chapter {
+      part: chapter_num.part,
+      chapter: {
+        match chapter_num.chapter {
+          Some(n) => Some(n + 1),
+          None => Some(1),
         }
-        b"Section" => {
-          chapter_num = Chapter {
-            part: chapter_num.part,
-            chapter: chapter_num.chapter,
-            section: {
-              match chapter_num.section {
-                Some(n) => Some(n + 1),
-                None => Some(1),
-              }
-            },
-            subsection: None,
-            division: None,
-            article: chapter_num.article,
-            paragraph: None,
-            item: None,
-            sub_item: None,
-            suppl_provision_title: chapter_num.suppl_provision_title,
-          }
+      },
+      section: None,
+      subsection: None,
+      division: None,
+      article: chapter_num.article,
+      paragraph: None,
+      item: None,
+      sub_item: None,
+      suppl_provision_title: chapter_num.suppl_provision_title,
+    },
+    b"Section" => Chapter {
+      part: chapter_num.part,
+      chapter: chapter_num.chapter,
+      section: {
+        match chapter_num.section {
+          Some(n) => Some(n + 1),
+          None => Some(1),
         }
-        b"Subsection" => {
-          chapter_num = Chapter {
-            part: chapter_num.part,
-            chapter: chapter_num.chapter,
-            section: chapter_num.section,
-            subsection: {
-              match chapter_num.subsection {
-                Some(n) => Some(n + 1),
-                None => Some(1),
-              }
-            },
-            division: None,
-            article: chapter_num.article,
-            paragraph: None,
-            item: None,
-            sub_item: None,
-            suppl_provision_title: chapter_num.suppl_provision_title,
-          }
+      },
+      subsection: None,
+      division: None,
+      article: chapter_num.article,
+      paragraph: None,
+      item: None,
+      sub_item: None,
+      suppl_provision_title: chapter_num.suppl_provision_title,
+    },
+    b"Subsection" => Chapter {
+      part: chapter_num.part,
+      chapter: chapter_num.chapter,
+      section: chapter_num.section,
+      subsection: {
+        match chapter_num.subsection {
+          Some(n) => Some(n + 1),
+          None => Some(1),
         }
-        b"Division" => {
-          chapter_num = Chapter {
-            part: chapter_num.part,
-            chapter: chapter_num.chapter,
-            section: chapter_num.section,
-            subsection: chapter_num.subsection,
-            division: {
-              match chapter_num.division {
-                Some(n) => Some(n + 1),
-                None => Some(1),
-              }
-            },
-            article: chapter_num.article,
-            paragraph: None,
-            item: None,
-            sub_item: None,
-            suppl_provision_title: chapter_num.suppl_provision_title,
-          }
+      },
+      division: None,
+      article: chapter_num.article,
+      paragraph: None,
+      item: None,
+      sub_item: None,
+      suppl_provision_title: chapter_num.suppl_provision_title,
+    },
+    b"Division" => Chapter {
+      part: chapter_num.part,
+      chapter: chapter_num.chapter,
+      section: chapter_num.section,
+      subsection: chapter_num.subsection,
+      division: {
+        match chapter_num.division {
+          Some(n) => Some(n + 1),
+          None => Some(1),
         }
-        b"Article" => {
-          let article_num_str = tag
-            .attributes()
-            .find(|res| encoding::decode(res.as_ref().unwrap().key.0, utf8).unwrap() == "Num")
-            .map(|res| {
-              encoding::decode(&res.unwrap().value, utf8)
-                .unwrap()
-                .to_string()
-            })
-            .unwrap();
-          chapter_num = Chapter {
-            part: chapter_num.part,
-            chapter: chapter_num.chapter,
-            section: chapter_num.section,
-            subsection: chapter_num.subsection,
-            division: chapter_num.division,
-            article: article_num_str,
-            paragraph: None,
-            item: None,
-            sub_item: None,
-            suppl_provision_title: chapter_num.suppl_provision_title,
-          };
-          info!("law_num: {}", &law_num);
-          info!("law_chapter: {:?}", &chapter_num);
+      },
+      article: chapter_num.article,
+      paragraph: None,
+      item: None,
+      sub_item: None,
+      suppl_provision_title: chapter_num.suppl_provision_title,
+    },
+    b"Article" => {
+      let article_num_str = get_num_attribute(tag);
+      Chapter {
+        part: chapter_num.part,
+        chapter: chapter_num.chapter,
+        section: chapter_num.section,
+        subsection: chapter_num.subsection,
+        division: chapter_num.division,
+        article: article_num_str,
+        paragraph: None,
+        item: None,
+        sub_item: None,
+        suppl_provision_title: chapter_num.suppl_provision_title,
+      }
+    }
+    b"Paragraph" => {
+      let paragraph_num_str = get_num_attribute(tag);
+      Chapter {
+        part: chapter_num.part,
+        chapter: chapter_num.chapter,
+        section: chapter_num.section,
+        subsection: chapter_num.subsection,
+        division: chapter_num.division,
+        article: chapter_num.article,
+        paragraph: Some(paragraph_num_str),
+        item: None,
+        sub_item: None,
+        suppl_provision_title: chapter_num.suppl_provision_title,
+      }
+    }
+    b"Item" => {
+      let item_num_str = get_num_attribute(tag);
+      Chapter {
+        part: chapter_num.part,
+        chapter: chapter_num.chapter,
+        section: chapter_num.section,
+        subsection: chapter_num.subsection,
+        division: chapter_num.division,
+        article: chapter_num.article,
+        paragraph: chapter_num.paragraph,
+        item: Some(item_num_str),
+        sub_item: None,
+        suppl_provision_title: chapter_num.suppl_provision_title,
+      }
+    }
+    b"SubItem1" => {
+      let sub_item_num_str = get_num_attribute(tag);
+      Chapter {
+        part: chapter_num.part,
+        chapter: chapter_num.chapter,
+        section: chapter_num.section,
+        subsection: chapter_num.subsection,
+        division: chapter_num.division,
+        article: chapter_num.article,
+        paragraph: chapter_num.paragraph,
+        item: chapter_num.item,
+        sub_item: Some((1, sub_item_num_str)),
+        suppl_provision_title: chapter_num.suppl_provision_title,
+      }
+    }
+    b"SubItem2" => {
+      let sub_item_num_str = get_num_attribute(tag);
+      Chapter {
+        part: chapter_num.part,
+        chapter: chapter_num.chapter,
+        section: chapter_num.section,
+        subsection: chapter_num.subsection,
+        division: chapter_num.division,
+        article: chapter_num.article,
+        paragraph: chapter_num.paragraph,
+        item: chapter_num.item,
+        sub_item: Some((2, sub_item_num_str)),
+        suppl_provision_title: chapter_num.suppl_provision_title,
+      }
+    }
+    b"SubItem3" => {
+      let sub_item_num_str = get_num_attribute(tag);
+      Chapter {
+        part: chapter_num.part,
+        chapter: chapter_num.chapter,
+        section: chapter_num.section,
+        subsection: chapter_num.subsection,
+        division: chapter_num.division,
+        article: chapter_num.article,
+        paragraph: chapter_num.paragraph,
+        item: chapter_num.item,
+        sub_item: Some((3, sub_item_num_str)),
+        suppl_provision_title: chapter_num.suppl_provision_title,
+      }
+    }
+    b"SubItem4" => {
+      let sub_item_num_str = get_num_attribute(tag);
+      Chapter {
+        part: chapter_num.part,
+        chapter: chapter_num.chapter,
+        section: chapter_num.section,
+        subsection: chapter_num.subsection,
+        division: chapter_num.division,
+        article: chapter_num.article,
+        paragraph: chapter_num.paragraph,
+        item: chapter_num.item,
+        sub_item: Some((4, sub_item_num_str)),
+        suppl_provision_title: chapter_num.suppl_provision_title,
+      }
+    }
+    b"SubItem5" => {
+      let sub_item_num_str = get_num_attribute(tag);
+      Chapter {
+        part: chapter_num.part,
+        chapter: chapter_num.chapter,
+        section: chapter_num.section,
+        subsection: chapter_num.subsection,
+        division: chapter_num.division,
+        article: chapter_num.article,
+        paragraph: chapter_num.paragraph,
+        item: chapter_num.item,
+        sub_item: Some((5, sub_item_num_str)),
+        suppl_provision_title: chapter_num.suppl_provision_title,
+      }
+    }
+    b"SubItem6" => {
+      let sub_item_num_str = get_num_attribute(tag);
+      Chapter {
+        part: chapter_num.part,
+        chapter: chapter_num.chapter,
+        section: chapter_num.section,
+        subsection: chapter_num.subsection,
+        division: chapter_num.division,
+        article: chapter_num.article,
+        paragraph: chapter_num.paragraph,
+        item: chapter_num.item,
+        sub_item: Some((6, sub_item_num_str)),
+        suppl_provision_title: chapter_num.suppl_provision_title,
+      }
+    }
+    b"SubItem7" => {
+      let sub_item_num_str = get_num_attribute(tag);
+      Chapter {
+        part: chapter_num.part,
+        chapter: chapter_num.chapter,
+        section: chapter_num.section,
+        subsection: chapter_num.subsection,
+        division: chapter_num.division,
+        article: chapter_num.article,
+        paragraph: chapter_num.paragraph,
+        item: chapter_num.item,
+        sub_item: Some((7, sub_item_num_str)),
+        suppl_provision_title: chapter_num.suppl_provision_title,
+      }
+    }
+    // 附則
+    b"SupplProvision" => Chapter {
+      part: None,
+      chapter: None,
+      section: None,
+      subsection: None,
+      division: None,
+      article: String::new(),
+      paragraph: None,
+      item: None,
+      sub_item: None,
+      suppl_provision_title: get_attribute(tag, "AmendLawNum"),
+    },
+    _ => chapter_num,
+  }
+}
+
+/// テキスト一つの中で見つかった検索語一つ分
+struct Hit {
+  /// 検索語の番号
+  index: usize,
+  /// 見つかった回数
+  count: usize,
+  /// 記録する箇所それぞれについての詳しい情報。`--snippet`か`--offsets`のときだけ作る
+  details: Vec<HitDetail>,
+}
+
+/// 見つかった箇所一つについての詳しい情報
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct HitDetail {
+  snippet: Option<String>,
+  sentence_num: Option<usize>,
+  offset: Option<usize>,
+  /// 同じ条項で同じ検索語が見つかった箇所のうち、法令XMLの先頭から数えて何番目か（0から数える）。
+  /// `--dedup none`のときだけ数え、それ以外は0にする
+  occurrence: usize,
+}
+
+/// 号の欄（`Column`）の文をつなげるときに、欄の間に入れる空白
+const COLUMN_SEPARATOR: char = '　';
+
+/// 検索の途中の状態。法令XMLのイベントを先頭から一つずつ受け取る
+struct SearchState<'a> {
+  search_str_lst: &'a [String],
+  config: &'a SearchConfig,
+  /// 検索語と除外する語の判定の仕方。同じ検索語と設定の検索で共有する
+  matchers: Arc<Matchers>,
+  utf8: &'static Encoding,
+  lst: Vec<Chapter>,
+  /// 箇所ごとに見つかった検索語
+  chapter_words: BTreeMap<Chapter, BTreeSet<String>>,
+  /// 箇所ごとの検索語が見つかった回数
+  hit_counts: BTreeMap<Chapter, usize>,
+  matched_words: Vec<String>,
+  matched_pairs: Vec<(Chapter, String, Option<String>, HitDetail)>,
+  /// `--dedup none`のときの、条項と検索語の組ごとのこれまでに見つかった箇所の数
+  occurrence_counts: HashMap<(Chapter, String), usize>,
+  chapter_num: Chapter,
+  law_num: String,
+  is_law_num_mode: bool,
+  law_title: Option<String>,
+  is_law_title_mode: bool,
+  promulgation_date: Option<Date>,
+  article_caption: Option<String>,
+  is_article_caption_mode: bool,
+  /// 法令XMLの断片で、開始タグが前の断片にある終了タグの名前。現れた順に並べ、断片をつなげるときに確かめる
+  unopened_ends: Vec<Vec<u8>>,
+  /// 法令XMLの断片を検索しているかどうか。断片の終わりでは途中で切れたかどうかを確かめない
+  is_fragment: bool,
+  /// 最初の要素が`Law`かどうかを確かめるかどうか。法令XMLの途中から始まる断片では確かめない
+  is_root_checked: bool,
+  /// 開かれている要素の名前。終了タグが開始タグと対応しているかどうかと、途中で切れたファイルを見つけるのに使う
+  element_stack: Vec<Vec<u8>>,
+  /// 要素が一つでもあったかどうか
+  is_element_found: bool,
+  article_count: usize,
+  started: Instant,
+  /// 検索語の種類の数
+  distinct_word_count: usize,
+  /// 検索語ごとの、見つかった箇所として記録するかどうか（検索式で`NOT`の付いた語は記録しない）
+  is_recorded: Vec<bool>,
+  /// `all_words`のときに、全ての検索語が揃うのを待っている箇所。
+  /// 箇所ごとに、見つかった検索語の番号と回数、条の見出しを持つ
+  pending: Vec<(Chapter, Vec<Hit>, Option<String>)>,
+  /// `pending`の箇所を含む範囲
+  pending_scope: Option<Chapter>,
+  /// `pending_scope`の範囲に除外する語があったかどうか
+  is_excluded: bool,
+  /// まとめて調べる単位の要素の中のテキスト
+  unit_text: String,
+  /// 開かれている単位の要素の数
+  unit_depth: usize,
+  /// ルビの読み（`Rt`要素）の中かどうか
+  is_ruby_text_mode: bool,
+  /// 今の（最後に始まった）文の`Num`属性の値
+  sentence_num: Option<usize>,
+  /// 今の文の中でここまでに読んだテキストの文字数（ルビの読み仮名を除く）
+  sentence_chars: usize,
+  /// 今の条で見つかった箇所をすでに記録したかどうか。`is_first_match_per_article`のときに、残りのテキストを飛ばすのに使う
+  is_article_matched: bool,
+  /// 見つかった箇所を、`min_hits`に足りなかったので捨てたことがあるかどうか
+  is_filtered: bool,
+  /// 今の条の残りを読み飛ばすときの、その条の要素の名前。読む側が[`SearchState::take_skip`]で受け取り、終了タグまで読み飛ばす
+  skip_to: Option<Vec<u8>>,
+  /// 条ごとの文章の長さの指標。`is_article_metrics`のときに、読んだ条の順に並べる。最後のものが今の条
+  article_metrics: Vec<ArticleMetrics>,
+}
+
+impl<'a> SearchState<'a> {
+  fn new(search_str_lst: &'a [String], config: &'a SearchConfig) -> Result<Self> {
+    Ok(SearchState {
+      search_str_lst,
+      config,
+      matchers: Matchers::shared(search_str_lst, config)?,
+      utf8: Encoding::for_label(b"utf-8").unwrap(),
+      lst: vec![],
+      chapter_words: BTreeMap::new(),
+      hit_counts: BTreeMap::new(),
+      matched_words: vec![],
+      matched_pairs: vec![],
+      occurrence_counts: HashMap::new(),
+      chapter_num: Chapter::default(),
+      law_num: String::new(),
+      is_law_num_mode: false,
+      law_title: None,
+      is_law_title_mode: false,
+      promulgation_date: None,
+      article_caption: None,
+      is_article_caption_mode: false,
+      unopened_ends: vec![],
+      is_fragment: false,
+      is_root_checked: true,
+      element_stack: vec![],
+      is_element_found: false,
+      article_count: 0,
+      started: Instant::now(),
+      distinct_word_count: search_str_lst.iter().collect::<HashSet<_>>().len(),
+      is_recorded: match &config.query {
+        Some(query) => {
+          let positive_terms = query.positive_terms();
+          search_str_lst
+            .iter()
+            .map(|s| positive_terms.contains(s))
+            .collect()
         }
-        b"Paragraph" => {
-          let paragraph_num_str = tag
-            .attributes()
-            .find(|res| encoding::decode(res.as_ref().unwrap().key.0, utf8).unwrap() == "Num")
-            .map(|res| {
-              encoding::decode(&res.unwrap().value, utf8)
-                .unwrap()
-                .to_string()
-            })
-            .unwrap();
-          chapter_num = Chapter {
-            part: chapter_num.part,
-            chapter: chapter_num.chapter,
-            section: chapter_num.section,
-            subsection: chapter_num.subsection,
-            division: chapter_num.division,
-            article: chapter_num.article,
-            paragraph: Some(paragraph_num_str),
-            item: None,
-            sub_item: None,
-            suppl_provision_title: chapter_num.suppl_provision_title,
-          }
+        None => vec![true; search_str_lst.len()],
+      },
+      pending: vec![],
+      pending_scope: None,
+      is_excluded: false,
+      unit_text: String::new(),
+      unit_depth: 0,
+      is_ruby_text_mode: false,
+      sentence_num: None,
+      sentence_chars: 0,
+      is_article_matched: false,
+      is_filtered: false,
+      skip_to: None,
+      article_metrics: vec![],
+    })
+  }
+
+  /// イベントを一つ処理する。ファイルの終わりまで来たら`true`を返す
+  fn handle_event(&mut self, event: Event) -> Result<bool> {
+    match event {
+      Event::Start(tag) => {
+        self.check_root(&tag)?;
+        if self.element_stack.len() >= self.config.max_depth {
+          return Err(
+            Diagnostic::new(
+              Code::MalformedNesting,
+              format!(
+                "要素の入れ子が{}より深くなっています",
+                self.config.max_depth
+              ),
+            )
+            .into(),
+          );
         }
-        b"Item" => {
-          let item_num_str = tag
-            .attributes()
-            .find(|res| encoding::decode(res.as_ref().unwrap().key.0, utf8).unwrap() == "Num")
-            .map(|res| {
-              encoding::decode(&res.unwrap().value, utf8)
-                .unwrap()
-                .to_string()
-            })
-            .unwrap();
-          chapter_num = Chapter {
-            part: chapter_num.part,
-            chapter: chapter_num.chapter,
-            section: chapter_num.section,
-            subsection: chapter_num.subsection,
-            division: chapter_num.division,
-            article: chapter_num.article,
-            paragraph: chapter_num.paragraph,
-            item: Some(item_num_str),
-            sub_item: None,
-            suppl_provision_title: chapter_num.suppl_provision_title,
-          }
+        if self.is_scope_element(tag.name().as_ref()) {
+          self.flush_pending();
         }
-        b"SubItem1" => {
-          let sub_item_num_str = tag
-            .attributes()
-            .find(|res| encoding::decode(res.as_ref().unwrap().key.0, utf8).unwrap() == "Num")
-            .map(|res| {
-              encoding::decode(&res.unwrap().value, utf8)
-                .unwrap()
-                .to_string()
-            })
-            .unwrap();
-          chapter_num = Chapter {
-            part: chapter_num.part,
-            chapter: chapter_num.chapter,
-            section: chapter_num.section,
-            subsection: chapter_num.subsection,
-            division: chapter_num.division,
-            article: chapter_num.article,
-            paragraph: chapter_num.paragraph,
-            item: chapter_num.item,
-            sub_item: Some((1, sub_item_num_str)),
-            suppl_provision_title: chapter_num.suppl_provision_title,
+        self.element_stack.push(tag.name().as_ref().to_vec());
+        self.is_element_found = true;
+        if let Some(unit) = self.text_unit() {
+          if tag.name().as_ref() == unit.element_name() {
+            if self.unit_depth == 0 && !self.is_joining_columns() {
+              self.unit_text.clear();
+            }
+            self.unit_depth += 1;
           }
         }
-        b"SubItem2" => {
-          let sub_item_num_str = tag
-            .attributes()
-            .find(|res| encoding::decode(res.as_ref().unwrap().key.0, utf8).unwrap() == "Num")
-            .map(|res| {
-              encoding::decode(&res.unwrap().value, utf8)
-                .unwrap()
-                .to_string()
-            })
-            .unwrap();
-          chapter_num = Chapter {
-            part: chapter_num.part,
-            chapter: chapter_num.chapter,
-            section: chapter_num.section,
-            subsection: chapter_num.subsection,
-            division: chapter_num.division,
-            article: chapter_num.article,
-            paragraph: chapter_num.paragraph,
-            item: chapter_num.item,
-            sub_item: Some((2, sub_item_num_str)),
-            suppl_provision_title: chapter_num.suppl_provision_title,
-          }
+        // 条の無い附則は、附則全体を一つの条として扱う
+        if matches!(tag.name().as_ref(), b"Article" | b"SupplProvision") {
+          self.is_article_matched = false;
         }
-        b"SubItem3" => {
-          let sub_item_num_str = tag
-            .attributes()
-            .find(|res| encoding::decode(res.as_ref().unwrap().key.0, utf8).unwrap() == "Num")
-            .map(|res| {
-              encoding::decode(&res.unwrap().value, utf8)
-                .unwrap()
-                .to_string()
-            })
-            .unwrap();
-          chapter_num = Chapter {
-            part: chapter_num.part,
-            chapter: chapter_num.chapter,
-            section: chapter_num.section,
-            subsection: chapter_num.subsection,
-            division: chapter_num.division,
-            article: chapter_num.article,
-            paragraph: chapter_num.paragraph,
-            item: chapter_num.item,
-            sub_item: Some((3, sub_item_num_str)),
-            suppl_provision_title: chapter_num.suppl_provision_title,
+        if tag.name().as_ref() == b"Sentence" {
+          self.sentence_num = get_attribute(&tag, "Num").and_then(|num| num.parse().ok());
+          self.sentence_chars = 0;
+          if self.is_in_metrics_article() {
+            if let Some(metrics) = self.article_metrics.last_mut() {
+              metrics.sentence_count += 1;
+            }
           }
         }
-        b"SubItem4" => {
-          let sub_item_num_str = tag
-            .attributes()
-            .find(|res| encoding::decode(res.as_ref().unwrap().key.0, utf8).unwrap() == "Num")
-            .map(|res| {
-              encoding::decode(&res.unwrap().value, utf8)
-                .unwrap()
-                .to_string()
-            })
-            .unwrap();
-          chapter_num = Chapter {
-            part: chapter_num.part,
-            chapter: chapter_num.chapter,
-            section: chapter_num.section,
-            subsection: chapter_num.subsection,
-            division: chapter_num.division,
-            article: chapter_num.article,
-            paragraph: chapter_num.paragraph,
-            item: chapter_num.item,
-            sub_item: Some((4, sub_item_num_str)),
-            suppl_provision_title: chapter_num.suppl_provision_title,
+        match tag.name().as_ref() {
+          b"LawNum" => self.is_law_num_mode = true,
+          b"LawTitle" => self.is_law_title_mode = true,
+          b"Law" => self.promulgation_date = promulgation_date(&tag),
+          b"Article" => {
+            self.chapter_num = next_chapter(self.chapter_num.clone(), &tag);
+            self.article_caption = None;
+            self.article_count += 1;
+            debug!("law_chapter: {:?}", &self.chapter_num);
           }
+          b"ArticleCaption" => self.is_article_caption_mode = true,
+          b"Rt" => self.is_ruby_text_mode = true,
+          _ => self.chapter_num = next_chapter(self.chapter_num.clone(), &tag),
         }
-        b"SubItem5" => {
-          let sub_item_num_str = tag
-            .attributes()
-            .find(|res| encoding::decode(res.as_ref().unwrap().key.0, utf8).unwrap() == "Num")
-            .map(|res| {
-              encoding::decode(&res.unwrap().value, utf8)
-                .unwrap()
-                .to_string()
-            })
-            .unwrap();
-          chapter_num = Chapter {
-            part: chapter_num.part,
-            chapter: chapter_num.chapter,
-            section: chapter_num.section,
-            subsection: chapter_num.subsection,
-            division: chapter_num.division,
-            article: chapter_num.article,
-            paragraph: chapter_num.paragraph,
-            item: chapter_num.item,
-            sub_item: Some((5, sub_item_num_str)),
-            suppl_provision_title: chapter_num.suppl_provision_title,
-          }
+        // 条の無い本則も、本則全体を一つの条として扱う
+        if self.config.is_article_metrics
+          && matches!(
+            tag.name().as_ref(),
+            b"MainProvision" | b"Article" | b"SupplProvision"
+          )
+        {
+          self
+            .article_metrics
+            .push(ArticleMetrics::new(self.chapter_num.article_level()));
         }
-        b"SubItem6" => {
-          let sub_item_num_str = tag
-            .attributes()
-            .find(|res| encoding::decode(res.as_ref().unwrap().key.0, utf8).unwrap() == "Num")
-            .map(|res| {
-              encoding::decode(&res.unwrap().value, utf8)
-                .unwrap()
-                .to_string()
-            })
-            .unwrap();
-          chapter_num = Chapter {
-            part: chapter_num.part,
-            chapter: chapter_num.chapter,
-            section: chapter_num.section,
-            subsection: chapter_num.subsection,
-            division: chapter_num.division,
-            article: chapter_num.article,
-            paragraph: chapter_num.paragraph,
-            item: chapter_num.item,
-            sub_item: Some((6, sub_item_num_str)),
-            suppl_provision_title: chapter_num.suppl_provision_title,
+      }
+      Event::Empty(tag) => {
+        self.check_root(&tag)?;
+        self.is_element_found = true;
+      }
+      Event::End(tag) => {
+        // 断片では、前の断片で開かれた要素の終了タグも現れる
+        match self.element_stack.pop() {
+          Some(name) if name != tag.name().as_ref() => {
+            return Err(end_tag_error(tag.name().as_ref(), Some(&name)));
           }
+          None if self.is_fragment => self.unopened_ends.push(tag.name().as_ref().to_vec()),
+          None => return Err(end_tag_error(tag.name().as_ref(), None)),
+          _ => (),
         }
-        b"SubItem7" => {
-          let sub_item_num_str = tag
-            .attributes()
-            .find(|res| encoding::decode(res.as_ref().unwrap().key.0, utf8).unwrap() == "Num")
-            .map(|res| {
-              encoding::decode(&res.unwrap().value, utf8)
-                .unwrap()
-                .to_string()
-            })
-            .unwrap();
-          chapter_num = Chapter {
-            part: chapter_num.part,
-            chapter: chapter_num.chapter,
-            section: chapter_num.section,
-            subsection: chapter_num.subsection,
-            division: chapter_num.division,
-            article: chapter_num.article,
-            paragraph: chapter_num.paragraph,
-            item: chapter_num.item,
-            sub_item: Some((7, sub_item_num_str)),
-            suppl_provision_title: chapter_num.suppl_provision_title,
-          }
+        match tag.name().as_ref() {
+          b"LawNum" => self.is_law_num_mode = false,
+          b"LawTitle" => self.is_law_title_mode = false,
+          b"ArticleCaption" => self.is_article_caption_mode = false,
+          b"Rt" => self.is_ruby_text_mode = false,
+          _ => (),
         }
-        // 附則
-        b"SupplProvision" => {
-          chapter_num = Chapter {
-            part: None,
-            chapter: None,
-            section: None,
-            subsection: None,
-            division: None,
-            article: String::new(),
-            paragraph: None,
-            item: None,
-            sub_item: None,
-            suppl_provision_title: tag
-              .attributes()
-              .find(|res| {
-                encoding::decode(res.as_ref().unwrap().key.0, utf8).unwrap() == "AmendLawNum"
-              })
-              .map(|res| {
-                encoding::decode(&res.unwrap().value, utf8)
-                  .unwrap()
-                  .to_string()
-              }),
+        if let Some(unit) = self.text_unit() {
+          if tag.name().as_ref() == unit.element_name() && self.unit_depth > 0 {
+            self.unit_depth -= 1;
+            if self.unit_depth == 0 && self.is_joining_columns() {
+              self.unit_text.push(COLUMN_SEPARATOR);
+            } else if self.unit_depth == 0 {
+              let text = std::mem::take(&mut self.unit_text);
+              let chapter = match unit {
+                TextUnit::Sentence => self.chapter_num.clone(),
+                TextUnit::Paragraph => self.chapter_num.paragraph_level(),
+              };
+              self.search_text(chapter, &text);
+            }
           }
         }
-        _ => (),
-      },
-      Ok(Event::End(tag)) => {
-        if let b"LawNum" = tag.name().as_ref() {
-          is_law_num_mode = false
+        if tag.name().as_ref() == b"ItemSentence"
+          && self.config.is_definitions_only
+          && self.unit_depth == 0
+          && !self.unit_text.is_empty()
+        {
+          let text = std::mem::take(&mut self.unit_text);
+          self.search_text(
+            self.chapter_num.clone(),
+            text.trim_end_matches(COLUMN_SEPARATOR),
+          );
+        }
+        if self.is_scope_element(tag.name().as_ref()) {
+          self.flush_pending();
         }
       }
-      Ok(Event::Text(text)) => {
-        if is_law_num_mode {
-          law_num = encoding::decode(&text.into_inner(), utf8)?.to_string();
+      Event::Text(text) => {
+        let text_str = encoding::decode(&text.into_inner(), self.utf8)?.to_string();
+        if self.is_law_num_mode {
+          self.law_num = text_str;
         } else {
-          let text_str = encoding::decode(&text.into_inner(), utf8)?.to_string();
-          let is_use_junyou = search_str_lst.iter().any(|s| text_str.contains(s));
-          info!("law_num: {}", &law_num);
-          if is_use_junyou {
-            lst.push(chapter_num.clone())
+          if self.is_law_title_mode {
+            self
+              .law_title
+              .get_or_insert_with(String::new)
+              .push_str(&text_str);
+          }
+          if self.is_article_caption_mode {
+            self
+              .article_caption
+              .get_or_insert_with(String::new)
+              .push_str(&text_str);
+          }
+          if self.unit_depth > 0 {
+            if !self.is_ruby_text_mode && !self.is_article_skipped() {
+              self.unit_text.push_str(&text_str);
+            }
+          } else if self.config.proximity.is_none() && self.is_searched_text() {
+            self.search_text(self.chapter_num.clone(), &text_str);
+          }
+          if self.config.is_offsets && !self.is_ruby_text_mode {
+            self.sentence_chars += text_str.chars().count();
+          }
+          if self.config.is_article_metrics
+            && !self.is_ruby_text_mode
+            && self.element_stack.iter().any(|name| name == b"Sentence")
+            && self.is_in_metrics_article()
+          {
+            if let Some(metrics) = self.article_metrics.last_mut() {
+              metrics.char_count += text_str.chars().count();
+            }
           }
         }
       }
-      Ok(Event::Eof) => break,
-      Err(e) => panic!("法令名APIの結果のXMLの解析中のエラー: {e}"),
+      Event::Eof if self.is_fragment => {
+        self.flush_pending();
+        return Ok(true);
+      }
+      Event::Eof => {
+        self.flush_pending();
+        self.check_complete()?;
+        return Ok(true);
+      }
       _ => (),
     }
+    Ok(false)
   }
-  lst.sort();
-  lst.dedup();
-  Ok(LawParagraph {
-    num: law_num,
-    chapter_data: lst,
-  })
+
+  /// 最初の要素が`Law`でなければ、法令XMLではないとしてエラーを返す
+  fn check_root(&self, tag: &BytesStart) -> Result<()> {
+    if self.is_root_checked && !self.is_element_found && tag.name().as_ref() != b"Law" {
+      let name = String::from_utf8_lossy(tag.name().as_ref()).to_string();
+      return Err(
+        Diagnostic::new(
+          Code::NotLawXml,
+          format!("最上位の要素が`Law`ではありません（`{name}`）"),
+        )
+        .into(),
+      );
+    }
+    Ok(())
+  }
+
+  /// 今読んでいるテキストが検索する対象かどうか。見出しだけを検索するときは、見出しの中のテキストだけが対象になる
+  fn is_searched_text(&self) -> bool {
+    !self.config.is_captions_only
+      || self
+        .element_stack
+        .iter()
+        .any(|name| name == b"ArticleCaption" || name == b"ParagraphCaption")
+  }
+
+  /// 号の欄（`Column`）の文を、欄の終わりで調べずに号の文（`ItemSentence`）の終わりまでつなげるかどうか。
+  /// 「一　個人情報　…をいう。」のような号の形の定義規定では、定義される語と定義が別々の欄の文になっている
+  fn is_joining_columns(&self) -> bool {
+    self.config.is_definitions_only
+      && self.text_unit() == Some(TextUnit::Sentence)
+      && self.element_stack.iter().any(|name| name == b"Column")
+      && self
+        .element_stack
+        .iter()
+        .any(|name| name == b"ItemSentence")
+  }
+
+  /// 文章の長さの指標を数える条（本則・条・附則）の中かどうか。別表などの中の文は、直前の条に数えない
+  fn is_in_metrics_article(&self) -> bool {
+    self.element_stack.iter().any(|name| {
+      matches!(
+        name.as_slice(),
+        b"MainProvision" | b"Article" | b"SupplProvision"
+      )
+    }) && !self
+      .element_stack
+      .iter()
+      .any(|name| name.starts_with(b"Appdx") || name.starts_with(b"SupplProvisionAppdx"))
+  }
+
+  /// 今開かれている、条（条の無い附則では附則）の要素の名前
+  fn article_element(&self) -> Option<Vec<u8>> {
+    let name: &[u8] = if self.chapter_num.article.is_some() {
+      b"Article"
+    } else {
+      b"SupplProvision"
+    };
+    if self.element_stack.iter().any(|open| open == name) {
+      Some(name.to_vec())
+    } else {
+      None
+    }
+  }
+
+  /// 読み飛ばす要素の名前を取り出す。読む側は、その要素の終了タグまで読み飛ばしてから[`SearchState::skipped_to_end`]を呼ぶ
+  fn take_skip(&mut self) -> Option<Vec<u8>> {
+    self.skip_to.take()
+  }
+
+  /// 要素`name`の終了タグまで読み飛ばした後に、読み飛ばした要素の中で開かれていた要素を閉じ、`name`の終了タグを受け取る
+  fn skipped_to_end(&mut self, name: &[u8]) -> Result<bool> {
+    while let Some(open) = self.element_stack.last() {
+      if open == name {
+        break;
+      }
+      self.element_stack.pop();
+    }
+    self.unit_depth = 0;
+    self.unit_text.clear();
+    self.is_ruby_text_mode = false;
+    self.is_article_caption_mode = false;
+    let name = String::from_utf8_lossy(name).into_owned();
+    self.handle_event(Event::End(BytesEnd::new(name)))
+  }
+
+  /// 今の条の残りのテキストを調べずに飛ばすかどうか
+  fn is_article_skipped(&self) -> bool {
+    self.config.is_first_match_per_article && self.is_article_matched
+  }
+
+  /// 見つかった箇所の記録を範囲ごとに判断するときの、範囲の要素かどうか
+  fn is_scope_element(&self, name: &[u8]) -> bool {
+    matches!(self.config.scope(), Some(scope) if scope.element_name() == name)
+  }
+
+  /// テキストをまとめて調べるときの単位
+  fn text_unit(&self) -> Option<TextUnit> {
+    self
+      .config
+      .proximity
+      .map(|proximity| proximity.unit)
+      .or(self.config.text_unit)
+      // 定義規定かどうかや括弧の中かどうかは、文全体を見ないと分からない
+      .or(
+        if self.config.is_definitions_only || self.config.is_quoted_only {
+          Some(TextUnit::Sentence)
+        } else {
+          None
+        },
+      )
+  }
+
+  /// 調べているテキスト`text`の`segment_start`バイト目から始まる部分`segment`で見つかった`index`番目の検索語の、
+  /// 記録する箇所それぞれについての詳しい情報。前後のテキストと位置は`text`の中で求める
+  fn hit_details(
+    &self,
+    index: usize,
+    count: usize,
+    text: &str,
+    (segment_start, segment): (usize, &str),
+  ) -> Vec<HitDetail> {
+    if self.config.snippet_context.is_none() && !self.config.is_offsets {
+      return vec![];
+    }
+    let pair_count = match self.config.dedup {
+      Dedup::None => count,
+      Dedup::Chapter | Dedup::Article => 1,
+    };
+    let sentence_position = if self.config.is_offsets {
+      self.sentence_position()
+    } else {
+      None
+    };
+    // `--near`のときは、二つの語それぞれが最初に見つかった位置を使う
+    self.matchers.word_matchers[index]
+      .find_ranges(segment)
+      .into_iter()
+      .take(pair_count)
+      .map(|(start, end)| (segment_start + start, segment_start + end))
+      .map(|range| HitDetail {
+        snippet: self
+          .config
+          .snippet_context
+          .map(|context| snippet::snippet(text, range, context, self.config.highlight.as_ref())),
+        sentence_num: sentence_position.map(|(num, _)| num),
+        offset: sentence_position.map(|(_, base)| base + text[..range.0].chars().count()),
+        occurrence: 0,
+      })
+      .collect()
+  }
+
+  /// 今調べているテキストのある文の`Num`属性の値と、テキストが文の何文字目から始まるか。
+  /// 項ごとにテキストをつなげているときや、文の外のテキストでは分からない
+  fn sentence_position(&self) -> Option<(usize, usize)> {
+    match self.text_unit() {
+      Some(TextUnit::Sentence) => self.sentence_num.map(|num| (num, 0)),
+      Some(TextUnit::Paragraph) => None,
+      None if self.element_stack.iter().any(|name| name == b"Sentence") => {
+        self.sentence_num.map(|num| (num, self.sentence_chars))
+      }
+      None => None,
+    }
+  }
+
+  /// `text`で見つかった検索語の番号と回数
+  fn count_hits(&self, text: &str) -> Vec<(usize, usize)> {
+    match (
+      &self.config.proximity,
+      self.matchers.word_matchers.as_slice(),
+    ) {
+      (Some(proximity), [a, b, ..]) if is_near(text, a, b, proximity.distance) => {
+        vec![(0, 1), (1, 1)]
+      }
+      (Some(_), _) => vec![],
+      (None, _) => {
+        let counts = match &self.matchers.multi_matcher {
+          Some(multi_matcher) => multi_matcher.counts(text),
+          None => self
+            .matchers
+            .word_matchers
+            .iter()
+            .map(|matcher| matcher.count(text))
+            .collect(),
+        };
+        counts
+          .into_iter()
+          .enumerate()
+          .filter(|(_, count)| *count > 0)
+          .collect()
+      }
+    }
+  }
+
+  /// 条項`chapter`にあるテキストから検索語を探す
+  fn search_text(&mut self, chapter: Chapter, text: &str) {
+    if self.is_article_skipped() {
+      return;
+    }
+    if let Some(scope) = self.config.scope() {
+      let scope_chapter = scope.target(&chapter);
+      if self.pending_scope.as_ref() != Some(&scope_chapter) {
+        self.flush_pending();
+        self.pending_scope = Some(scope_chapter);
+      }
+    }
+    if self
+      .matchers
+      .exclude_matchers
+      .iter()
+      .any(|m| m.is_match(text))
+    {
+      self.is_excluded = true;
+    }
+    // 定義規定だけを探すときは定義される語の部分だけを、括弧の中だけを探すときは括弧の中の部分それぞれを別々に調べる。
+    // 部分はテキストの何バイト目から始まるかと共に持ち、前後のテキストと位置は元のテキストから求める
+    let mut segments = vec![(0, text)];
+    if self.config.is_definitions_only {
+      segments = definition::defined_terms(text);
+      if segments.is_empty() {
+        return;
+      }
+    }
+    if self.config.is_quoted_only {
+      segments = segments
+        .into_iter()
+        .flat_map(|(start, segment)| {
+          quote::quoted_segments(segment)
+            .into_iter()
+            .map(move |(quoted_start, quoted)| (start + quoted_start, quoted))
+        })
+        .collect();
+    }
+    let mut hits: Vec<Hit> = vec![];
+    for segment in segments {
+      for (index, count) in self.count_hits(segment.1) {
+        let details = self.hit_details(index, count, text, segment);
+        match hits.iter_mut().find(|hit| hit.index == index) {
+          Some(hit) => {
+            hit.count += count;
+            hit.details.extend(details);
+          }
+          None => hits.push(Hit {
+            index,
+            count,
+            details,
+          }),
+        }
+      }
+    }
+    hits.sort_by_key(|hit| hit.index);
+    if !hits.is_empty() {
+      let position = match self.config.dedup {
+        Dedup::Article => Chapter {
+          paragraph: None,
+          item: None,
+          sub_item: None,
+          ..chapter
+        },
+        Dedup::Chapter | Dedup::None => chapter,
+      };
+      self.record_hits(position, hits);
+    }
+  }
+
+  /// 箇所`position`で見つかった検索語の番号と回数を記録する。
+  /// `all_words`・検索式・除外する語・`min_hits`があるときは、範囲の中で全ての検索語が揃うか、検索式が成り立つか、
+  /// 除外する語が無いか、見つかった回数が足りるかどうかが分かるまで、範囲の終わりまで記録を待つ
+  fn record_hits(&mut self, position: Chapter, hits: Vec<Hit>) {
+    let article_caption = self.article_caption.clone();
+    match self.config.scope() {
+      None => self.commit_hits(position, &hits, article_caption),
+      Some(_) => self.pending.push((position, hits, article_caption)),
+    }
+  }
+
+  /// 記録を待っている箇所を、範囲の中で全ての検索語が揃っていれば（検索式があれば、それが成り立てば）記録し、
+  /// そうでないか、範囲の中に除外する語があるか、見つかった回数の合計が`min_hits`に足りなければ捨てる
+  fn flush_pending(&mut self) {
+    let pending = std::mem::take(&mut self.pending);
+    self.pending_scope = None;
+    let is_excluded = std::mem::replace(&mut self.is_excluded, false);
+    let mut is_too_few_hits = false;
+    let is_matched = !is_excluded && {
+      let found_words = pending
+        .iter()
+        .flat_map(|(_, hits, _)| {
+          hits
+            .iter()
+            .map(|hit| self.search_str_lst[hit.index].as_str())
+        })
+        .collect::<HashSet<_>>();
+      let hit_count = pending
+        .iter()
+        .flat_map(|(_, hits, _)| hits.iter())
+        .filter(|hit| self.is_recorded[hit.index])
+        .map(|hit| hit.count)
+        .sum::<usize>();
+      is_too_few_hits =
+        matches!(self.config.min_hits, Some(min_hits) if 0 < hit_count && hit_count < min_hits);
+      !is_too_few_hits
+        && match (&self.config.query, self.config.all_words) {
+          (Some(query), _) => query.evaluate(&found_words),
+          (None, Some(_)) => found_words.len() == self.distinct_word_count,
+          (None, None) => !found_words.is_empty(),
+        }
+    };
+    self.is_filtered |= is_too_few_hits;
+    if is_matched {
+      for (position, hits, article_caption) in pending {
+        let hits = hits
+          .into_iter()
+          .filter(|hit| self.is_recorded[hit.index])
+          .collect::<Vec<_>>();
+        if !hits.is_empty() {
+          self.commit_hits(position, &hits, article_caption);
+        }
+      }
+    }
+  }
+
+  fn commit_hits(&mut self, position: Chapter, hits: &[Hit], article_caption: Option<String>) {
+    // 範囲の終わりを次の条のテキストで知ったときは、前の条の箇所を記録している
+    if position.article == self.chapter_num.article
+      && position.suppl_provision_title == self.chapter_num.suppl_provision_title
+    {
+      self.is_article_matched = true;
+      // 文章の長さの指標は条全体のテキストで数えるので、そのときは読み飛ばさない
+      if self.config.is_first_match_per_article && !self.config.is_article_metrics {
+        self.skip_to = self.skip_to.take().or_else(|| self.article_element());
+      }
+    }
+    let mut hit_count = 0;
+    for hit in hits.iter() {
+      let word = &self.search_str_lst[hit.index];
+      self.matched_words.push(word.clone());
+      self
+        .chapter_words
+        .entry(position.clone())
+        .or_default()
+        .insert(word.clone());
+      // まとめるときは、一つのテキストの中で何度見つかっても箇所は一つにする
+      let pair_count = match self.config.dedup {
+        Dedup::None => hit.count,
+        Dedup::Chapter | Dedup::Article => 1,
+      };
+      for k in 0..pair_count {
+        let mut detail = hit.details.get(k).cloned().unwrap_or_default();
+        if self.config.dedup == Dedup::None {
+          let count = self
+            .occurrence_counts
+            .entry((position.clone(), word.clone()))
+            .or_default();
+          detail.occurrence = *count;
+          *count += 1;
+        }
+        self.matched_pairs.push((
+          position.clone(),
+          word.clone(),
+          article_caption.clone(),
+          detail,
+        ));
+      }
+      hit_count += hit.count;
+    }
+    *self.hit_counts.entry(position.clone()).or_default() += hit_count;
+    // `Dedup::None`でも条項は一つずつにし、見つかった回数は`hit_counts`で数える
+    if self.lst.last() != Some(&position) {
+      self.lst.push(position);
+    }
+  }
+
+  /// 法令XMLが途中で切れていないかどうかを確かめる
+  fn check_complete(&self) -> Result<()> {
+    if !self.is_element_found {
+      return Err(Diagnostic::new(Code::TruncatedXml, "法令XMLに要素がありません").into());
+    }
+    if !self.element_stack.is_empty() {
+      return Err(
+        Diagnostic::new(
+          Code::TruncatedXml,
+          format!(
+            "法令XMLが途中で終わっています（閉じられていない要素が{}個）",
+            self.element_stack.len()
+          ),
+        )
+        .into(),
+      );
+    }
+    Ok(())
+  }
+
+  /// これまでに見つかった箇所を取り出す。取り出した箇所は[`SearchState::finish`]の結果に含めない
+  fn take_matches(&mut self) -> Vec<Match> {
+    let law_num = &self.law_num;
+    let matches = self
+      .matched_pairs
+      .drain(..)
+      .map(|pair| to_match(law_num, pair))
+      .collect();
+    self.matched_words.sort();
+    self.matched_words.dedup();
+    matches
+  }
+
+  /// すぐ後ろに続く断片を検索した状態をつなげる
+  fn append(&mut self, mut other: SearchState) -> Result<()> {
+    // 後ろの断片で閉じられた要素は、この状態の最後に開かれているものから順に閉じる
+    for name in other.unopened_ends {
+      match self.element_stack.pop() {
+        Some(open) if open == name => (),
+        Some(open) => return Err(end_tag_error(&name, Some(&open))),
+        None if self.is_fragment => self.unopened_ends.push(name),
+        None => return Err(end_tag_error(&name, None)),
+      }
+    }
+    self.element_stack.extend(other.element_stack);
+    self.lst.extend(other.lst);
+    for (chapter, words) in other.chapter_words {
+      self.chapter_words.entry(chapter).or_default().extend(words);
+    }
+    for (chapter, count) in other.hit_counts {
+      *self.hit_counts.entry(chapter).or_default() += count;
+    }
+    self.matched_words.extend(other.matched_words);
+    // 後ろの断片の箇所は、前の断片で同じ条項と検索語の組が見つかった数の続きから数える
+    for (chapter, word, _, detail) in other.matched_pairs.iter_mut() {
+      if let Some(count) = self.occurrence_counts.get(&(chapter.clone(), word.clone())) {
+        detail.occurrence += count;
+      }
+    }
+    self.matched_pairs.extend(other.matched_pairs);
+    for (key, count) in other.occurrence_counts {
+      *self.occurrence_counts.entry(key).or_default() += count;
+    }
+    self.article_metrics.extend(other.article_metrics);
+    if self.law_num.is_empty() {
+      self.law_num = other.law_num;
+    }
+    if self.law_title.is_none() {
+      self.law_title = other.law_title;
+    }
+    if self.promulgation_date.is_none() {
+      self.promulgation_date = other.promulgation_date;
+    }
+    self.is_element_found |= other.is_element_found;
+    self.article_count += other.article_count;
+    self.is_filtered |= other.is_filtered;
+    Ok(())
+  }
+
+  fn finish(self) -> LawParagraph {
+    let is_dedup = self.config.dedup != Dedup::None;
+    let mut lst = self.lst;
+    lst.sort();
+    lst.dedup();
+    let chapter_words = self.chapter_words;
+    let chapter_words = lst
+      .iter()
+      .map(|chapter| {
+        chapter_words
+          .get(chapter)
+          .map(|words| words.iter().cloned().collect())
+          .unwrap_or_default()
+      })
+      .collect();
+    let hit_counts = lst
+      .iter()
+      .map(|chapter| self.hit_counts.get(chapter).copied().unwrap_or_default())
+      .collect();
+    let mut matched_words = self.matched_words;
+    matched_words.sort();
+    matched_words.dedup();
+    let mut matched_pairs = self.matched_pairs;
+    matched_pairs.sort();
+    if is_dedup {
+      // 同じ箇所は、前後のテキストが違っても一つにする
+      matched_pairs.dedup_by(|a, b| (&a.0, &a.1) == (&b.0, &b.1));
+    }
+    let matched_articles = lst
+      .iter()
+      .map(|chapter| chapter.article_level())
+      .collect::<HashSet<_>>();
+    let mut article_metrics = self.article_metrics;
+    article_metrics.retain(|metrics| matched_articles.contains(&metrics.chapter));
+    let law_num = self.law_num;
+    let matches = matched_pairs
+      .into_iter()
+      .map(|pair| to_match(&law_num, pair))
+      .collect::<Vec<_>>();
+    info!(
+      law_num = law_num.as_str(),
+      articles = self.article_count,
+      chapters = lst.len(),
+      matches = matches.len(),
+      duration_ms = self.started.elapsed().as_millis() as u64,
+      "searched law file"
+    );
+    LawParagraph {
+      num: law_num,
+      title: self.law_title,
+      promulgation_date: self.promulgation_date.map(|date| date.to_string()),
+      chapter_data: lst,
+      chapter_words,
+      hit_counts,
+      matched_words,
+      matches,
+      version: None,
+      schema_version: None,
+      snapshot_sha256: None,
+      hits: None,
+      article_metrics,
+      is_filtered: self.is_filtered,
+    }
+  }
+}
+
+fn to_match(
+  law_num: &str,
+  (chapter, word, article_caption, detail): (Chapter, String, Option<String>, HitDetail),
+) -> Match {
+  Match {
+    id: occurrence_match_id(law_num, &chapter, &word, detail.occurrence),
+    chapter,
+    word,
+    article_caption,
+    snippet: detail.snippet,
+    sentence_num: detail.sentence_num,
+    offset: detail.offset,
+    synonym_of: None,
+    tags: vec![],
+    attributes: BTreeMap::new(),
+  }
+}
+
+/// 法令XMLの解析中のエラーに、読んでいた位置を付けたもの
+pub(crate) fn xml_parse_error(err: quick_xml::Error, position: usize) -> anyhow::Error {
+  Diagnostic::new(
+    Code::XmlParse,
+    format!("法令XMLの解析中のエラー（{position}バイト目付近）: {err}"),
+  )
+  .into()
+}
+
+/// 指定された単語が含まれる条があったとき、その条番号等のデータのみを保存する。
+/// 後でこのデータをもとに実際の条文を再度取得するのに使いたい。
+/// 検索の仕方は[`SearchConfig`]のデフォルトのものにする
+#[deprecated(note = "検索の仕方を指定できる`search_xml_with`を使う")]
+pub async fn search_xml(
+  search_str_lst: &[String],
+  reader: &mut Reader<tokio::io::BufReader<File>>,
+) -> Result<LawParagraph> {
+  search_xml_with(search_str_lst, &SearchConfig::default(), reader).await
+}
+
+/// 指定された単語が含まれる条があったとき、その条番号等のデータのみを保存する。
+/// 検索の仕方は`config`で決める
+pub async fn search_xml_with<R: AsyncBufRead + Unpin>(
+  search_str_lst: &[String],
+  config: &SearchConfig,
+  reader: &mut Reader<R>,
+) -> Result<LawParagraph> {
+  let mut state = SearchState::new(search_str_lst, config)?;
+  let mut buf = Vec::new();
+  reader.trim_text(true);
+  // 終了タグの対応は、どの読み方でも`SearchState`で確かめて`E005`にする
+  reader.check_end_names(false);
+  loop {
+    // 条ごとに最初に見つかった箇所だけを記録するときは、その条の残りを解析せずに読み飛ばす
+    if let Some(name) = state.take_skip() {
+      reader
+        .read_to_end_into_async(QName(&name), &mut buf)
+        .await
+        .map_err(|e| xml_parse_error(e, reader.buffer_position()))?;
+      buf.clear();
+      if state.skipped_to_end(&name)? {
+        break;
+      }
+      continue;
+    }
+    match reader.read_event_into_async(&mut buf).await {
+      Ok(event) => {
+        if state.handle_event(event)? {
+          break;
+        }
+      }
+      Err(e) => return Err(xml_parse_error(e, reader.buffer_position())),
+    }
+    buf.clear();
+  }
+  Ok(state.finish())
+}
+
+/// 法令XMLを先頭から読み、検索語が見つかった箇所を見つかった順に少しずつ返す。
+/// 見つかった箇所を全て貯めておかないので、見つかった箇所が多くても使うメモリが増えない
+pub struct MatchStream<'a, R> {
+  reader: Reader<R>,
+  state: SearchState<'a>,
+  buf: Vec<u8>,
+  is_done: bool,
+}
+
+impl<'a, R: AsyncBufRead + Unpin> MatchStream<'a, R> {
+  pub fn new(
+    search_str_lst: &'a [String],
+    config: &'a SearchConfig,
+    mut reader: Reader<R>,
+  ) -> Result<Self> {
+    reader.trim_text(true);
+    reader.check_end_names(false);
+    Ok(MatchStream {
+      reader,
+      state: SearchState::new(search_str_lst, config)?,
+      buf: Vec::new(),
+      is_done: false,
+    })
+  }
+
+  /// ここまでに読んだ法令番号
+  pub fn law_num(&self) -> &str {
+    &self.state.law_num
+  }
+
+  /// 次に検索語が見つかるところまで読み、前に呼んだときより後に見つかった箇所を返す。
+  /// 同じ箇所を何度も返すことがある。ファイルの終わりまで読んだら`None`を返す
+  pub async fn next_matches(&mut self) -> Result<Option<Vec<Match>>> {
+    while !self.is_done {
+      if let Some(name) = self.state.take_skip() {
+        self
+          .reader
+          .read_to_end_into_async(QName(&name), &mut self.buf)
+          .await
+          .map_err(|e| xml_parse_error(e, self.reader.buffer_position()))?;
+        self.buf.clear();
+        self.is_done = self.state.skipped_to_end(&name)?;
+        continue;
+      }
+      let event = self
+        .reader
+        .read_event_into_async(&mut self.buf)
+        .await
+        .map_err(|e| xml_parse_error(e, self.reader.buffer_position()))?;
+      self.is_done = self.state.handle_event(event)?;
+      self.buf.clear();
+      if !self.state.matched_pairs.is_empty() {
+        return Ok(Some(self.state.take_matches()));
+      }
+    }
+    Ok(None)
+  }
+
+  /// 読み終えた法令の検索結果を返す。[`MatchStream::next_matches`]で返した箇所は`matches`に含めない
+  pub fn finish(self) -> LawParagraph {
+    self.state.finish()
+  }
+}
+
+/// 法令XMLを必要な分だけ読み進める検索。[`SearchSession::poll_next_match`]を呼ぶたびに、
+/// 次に見つかった箇所が一つ返るところまでしか読まない。
+/// 呼び出しの合間に持つのは検索の状態と、一度に見つかったうちのまだ返していない箇所だけなので、
+/// 画面に表示する分だけ検索を進めるような使い方で、好きなだけ中断できる
+pub struct SearchSession<'a, R> {
+  stream: MatchStream<'a, R>,
+  /// 読み進めたときに見つかったが、まだ返していない箇所
+  queue: VecDeque<Match>,
+}
+
+impl<'a, R: AsyncBufRead + Unpin> SearchSession<'a, R> {
+  pub fn new(
+    search_str_lst: &'a [String],
+    config: &'a SearchConfig,
+    reader: Reader<R>,
+  ) -> Result<Self> {
+    Ok(SearchSession {
+      stream: MatchStream::new(search_str_lst, config, reader)?,
+      queue: VecDeque::new(),
+    })
+  }
+
+  /// ここまでに読んだ法令番号
+  pub fn law_num(&self) -> &str {
+    self.stream.law_num()
+  }
+
+  /// ここまでに読んだ位置の条項
+  pub fn position(&self) -> &Chapter {
+    &self.stream.state.chapter_num
+  }
+
+  /// 次に見つかった箇所を一つ返す。まだ返していない箇所が無ければ、次に検索語が見つかるところまで読む。
+  /// ファイルの終わりまで読み、返す箇所も無くなったら`None`を返す
+  pub async fn poll_next_match(&mut self) -> Result<Option<Match>> {
+    if self.queue.is_empty() {
+      if let Some(matches) = self.stream.next_matches().await? {
+        self.queue.extend(matches);
+      }
+    }
+    Ok(self.queue.pop_front())
+  }
+
+  /// 読み終えた法令の検索結果を返す。[`SearchSession::poll_next_match`]で返した箇所は`matches`に含めず、
+  /// まだ返していない箇所は含める
+  pub fn finish(self) -> LawParagraph {
+    let mut law_paragraph = self.stream.finish();
+    let mut matches = Vec::from(self.queue);
+    matches.append(&mut law_paragraph.matches);
+    law_paragraph.matches = matches;
+    law_paragraph
+  }
+}
+
+/// [`search_xml_with`]と同じ検索を、すでに[`LawTree`]に読み込んだ法令に対して行う。
+/// XMLに書き出して読み直すことはしない
+pub fn search_parsed(
+  search_str_lst: &[String],
+  config: &SearchConfig,
+  tree: &LawTree,
+) -> Result<LawParagraph> {
+  let mut state = SearchState::new(search_str_lst, config)?;
+  let mut is_done = false;
+  tree.root.walk_events(&mut |event| {
+    if !is_done {
+      is_done = state.handle_event(event)?;
+    }
+    Ok(())
+  })?;
+  if !is_done {
+    state.handle_event(Event::Eof)?;
+  }
+  Ok(state.finish())
+}
+
+/// [`search_xml_with`]と同じ検索を、メモリ上の法令XMLに対して同期的に行う
+pub fn search_xml_bytes(
+  search_str_lst: &[String],
+  config: &SearchConfig,
+  xml: &[u8],
+) -> Result<LawParagraph> {
+  let mut state = SearchState::new(search_str_lst, config)?;
+  let mut reader = Reader::from_reader(xml);
+  reader.trim_text(true);
+  reader.check_end_names(false);
+  loop {
+    if let Some(name) = state.take_skip() {
+      reader
+        .read_to_end(QName(&name))
+        .map_err(|e| xml_parse_error(e, reader.buffer_position()))?;
+      if state.skipped_to_end(&name)? {
+        break;
+      }
+      continue;
+    }
+    match reader.read_event() {
+      Ok(event) => {
+        if state.handle_event(event)? {
+          break;
+        }
+      }
+      Err(e) => return Err(xml_parse_error(e, reader.buffer_position())),
+    }
+  }
+  Ok(state.finish())
+}
+
+/// メモリ上の法令XMLの、`offset`バイト目から始まる断片を検索する
+fn search_fragment<'a>(
+  search_str_lst: &'a [String],
+  config: &'a SearchConfig,
+  fragment: &[u8],
+  offset: usize,
+) -> Result<SearchState<'a>> {
+  let mut state = SearchState::new(search_str_lst, config)?;
+  state.is_fragment = true;
+  state.is_root_checked = offset == 0;
+  let mut reader = Reader::from_reader(fragment);
+  reader.trim_text(true);
+  reader.check_end_names(false);
+  loop {
+    if let Some(name) = state.take_skip() {
+      reader
+        .read_to_end(QName(&name))
+        .map_err(|e| xml_parse_error(e, offset + reader.buffer_position()))?;
+      if state.skipped_to_end(&name)? {
+        break;
+      }
+      continue;
+    }
+    match reader.read_event() {
+      Ok(event) => {
+        if state.handle_event(event)? {
+          break;
+        }
+      }
+      Err(e) => return Err(xml_parse_error(e, offset + reader.buffer_position())),
+    }
+  }
+  Ok(state)
+}
+
+/// 終了タグ`name`が、開かれている要素`open`と対応していないときのエラー
+fn end_tag_error(name: &[u8], open: Option<&[u8]>) -> anyhow::Error {
+  let message = match open {
+    Some(open) => format!(
+      "`{}`の終了タグがありますが、開かれているのは`{}`です",
+      String::from_utf8_lossy(name),
+      String::from_utf8_lossy(open)
+    ),
+    None => format!(
+      "`{}`の終了タグに対応する開始タグがありません",
+      String::from_utf8_lossy(name)
+    ),
+  };
+  Diagnostic::new(Code::MalformedNesting, message).into()
+}
+
+/// 本則（`MainProvision`）と附則（`SupplProvision`）の開始タグの位置を、文書の順に返す
+fn provision_offsets(xml: &[u8]) -> Vec<usize> {
+  let mut offsets = vec![];
+  for name in [&b"<MainProvision"[..], &b"<SupplProvision"[..]] {
+    for i in memmem::find_iter(xml, name) {
+      // `<SupplProvisionLabel`などを除く
+      if let Some(b'>' | b'/' | b' ' | b'\t' | b'\r' | b'\n') = xml.get(i + name.len()) {
+        offsets.push(i);
+      }
+    }
+  }
+  offsets.sort_unstable();
+  offsets
+}
+
+/// [`search_xml_bytes`]と同じ検索を、法令XMLを本則と附則の境目で最大`parts`個に分け、並列に行う。
+/// 結果は文書の順につなげる
+pub fn search_xml_bytes_parallel(
+  search_str_lst: &[String],
+  config: &SearchConfig,
+  xml: &[u8],
+  parts: usize,
+) -> Result<LawParagraph> {
+  let offsets = provision_offsets(xml);
+  let body_start = match offsets.first() {
+    Some(&body_start) if parts > 1 && offsets.len() > 1 => body_start,
+    _ => return search_xml_bytes(search_str_lst, config, xml),
+  };
+  // 本則と附則をまとめて、大きさがおおよそ同じになるように分ける
+  let target_size = (xml.len() - body_start) / parts;
+  let mut ranges = vec![];
+  let mut start = body_start;
+  for &offset in offsets.iter().skip(1) {
+    if offset - start >= target_size && ranges.len() + 1 < parts {
+      ranges.push((start, offset));
+      start = offset;
+    }
+  }
+  ranges.push((start, xml.len()));
+
+  let mut state = search_fragment(search_str_lst, config, &xml[..body_start], 0)?;
+  let results = std::thread::scope(|scope| {
+    let handles = ranges
+      .iter()
+      .map(|&(start, end)| {
+        scope.spawn(move || search_fragment(search_str_lst, config, &xml[start..end], start))
+      })
+      .collect::<Vec<_>>();
+    handles
+      .into_iter()
+      .map(|handle| handle.join().unwrap_or_else(std::panic::resume_unwind))
+      .collect::<Vec<_>>()
+  });
+  for result in results {
+    state.append(result?)?;
+  }
+  state.check_complete()?;
+  Ok(state.finish())
+}
+
+/// 開いた法令ファイルをメモリマップし、[`search_xml_bytes_parallel`]で検索する
+pub fn search_xml_mmap(
+  search_str_lst: &[String],
+  config: &SearchConfig,
+  file: &std::fs::File,
+  parts: usize,
+) -> Result<LawParagraph> {
+  // 検索している間にファイルが書き換えられないことを前提にしている
+  let mmap = unsafe { Mmap::map(file)? };
+  search_xml_bytes_parallel(search_str_lst, config, &mmap, parts)
 }
 
 pub async fn get_law_from_artcile_info(info_file_path: &str) -> Result<Vec<LawParagraph>> {
@@ -1,27 +1,1420 @@
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{anyhow, Result};
+use clap::{error::ErrorKind, CommandFactory, FromArgMatches, Parser, Subcommand};
 use quick_xml::Reader;
-use std::path::Path;
+#[cfg(feature = "filter-expr")]
+use search_article_with_word::filter_expr::{apply_filter, FilterExpr};
+#[cfg(feature = "nats")]
+use search_article_with_word::nats::NatsSink;
+#[cfg(feature = "plugin")]
+use search_article_with_word::plugin::{apply_plugins, Plugin};
+#[cfg(feature = "postgres")]
+use search_article_with_word::postgres::PostgresSink;
+#[cfg(feature = "relevance")]
+use search_article_with_word::relevance::{sort_by_score, RelevanceModel, RelevanceScore};
+use search_article_with_word::{
+  abbreviation::Abbreviations,
+  align::{align_articles, read_article_texts, DEFAULT_THRESHOLD},
+  annotation::{find_spans, to_annotation_json, AnnotationFormat},
+  batch::{open_law_file, SearchManyConfig},
+  bibliography::{render_bibliography, BibEntry, BibliographyFormat},
+  citation::{chapter_title_with, law_citation, NumberStyle},
+  config_file::{config_schema, config_to_args, load_config, ConfigIssue},
+  cooccurrence::CooccurrenceCounter,
+  corpus::{find_law_files, find_law_versions, law_id_of, version_of},
+  date::Date,
+  diagnostic::{self, code_catalog, report_warning, Code, Diagnostic},
+  diff::{apply_alignment, diff_results, DiffKind},
+  egov::{fetch_chapter_text, fetch_law_xml, fetch_updated_laws},
+  evaluate::{evaluate, read_gold},
+  fold::Folding,
+  format::{to_grep, to_lawtext, to_pretty, GrepStreamWriter, OutputFormat},
+  http::{HttpClient, HttpConfig},
+  hydrate::{hydrate_chapters, hydrate_chapters_with, ChapterText, ContextScope, RubyStyle},
+  i18n::{help_en, lang, set_lang, Lang, ABOUT_EN},
+  manifest::{ManifestEntry, ManifestStatus},
+  matcher::Matchers,
+  memory::{ByteSize, MemoryGuard},
+  query::Query,
+  read_strategy::{available_memory, ReadOptions},
+  report::{render_report, render_report_index, ReportData, ReportLaw},
+  sample::{
+    paragraph_search, seeded_priority, seeded_rng, BottomK, Reservoir, SampledMatch,
+    SampledParagraph,
+  },
+  search_config::{
+    Dedup, MatchMode, Proximity, SearchConfig, TextUnit, WordScope, DEFAULT_MAX_DEPTH,
+  },
+  search_many,
+  session::{
+    absolute_path_arguments, file_sha256, hash_outputs, read_session_arguments, redact_arguments,
+    unix_time_now, SessionLog,
+  },
+  site::{render_law_page, render_site_index, SiteChapter, SiteLaw},
+  snapshot::{create_snapshot, read_snapshot_hash},
+  snippet::Highlight,
+  speech::to_speech_text,
+  synonym::{mark_synonyms, Synonyms},
+  update::{classify, download_file_name, UpdateReport, UpdateStatus},
+  verify::{verify_chapters, VerifyReport, VerifyStatus},
+  versioned_match_id, Chapter, LawParagraph, MatchStream, LATEST_SCHEMA_VERSION,
+};
+#[cfg(feature = "xlsx")]
+use search_article_with_word::{
+  citation::chapter_title,
+  egov::article_url,
+  xlsx::{write_xlsx, XlsxMatchRow, XlsxSummaryRow},
+};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tokio::fs::*;
 use tokio::io::{AsyncWriteExt, BufReader};
 use tokio_stream::StreamExt;
 use tracing::*;
 
 #[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
+#[clap(
+  author,
+  version,
+  about,
+  long_about = None,
+  args_conflicts_with_subcommands = true,
+  subcommand_negates_reqs = true
+)]
 struct Args {
+  #[clap(subcommand)]
+  command: Option<Command>,
   /// 解析結果を出力するJSONファイルへのpath
-  #[clap(short, long)]
-  output: String,
+  #[clap(short, long, required = true)]
+  output: Option<String>,
   /// 法令XMLファイル群が置かれている作業ディレクトリへのpath
-  #[clap(short, long)]
-  work: String,
+  #[clap(short, long, required = true)]
+  work: Option<String>,
   /// 法令ファイルのインデックス情報が書かれたJSONファイルへのpath
-  #[clap(short, long)]
-  index_file: String,
+  #[clap(short, long, required = true)]
+  index_file: Option<String>,
   /// 検索する単語
   #[clap(short, long)]
   search_words: Vec<String>,
+  /// 除外する単語。この単語を一つでも含む条（`--all-words`があればその範囲）は、検索する単語を含んでいても記録しない
+  #[clap(long)]
+  exclude_words: Vec<String>,
+  /// 一列目に検索語、二列目にその同義語を`,`で区切って並べたTSVの同義語の辞書へのpath。検索語をその同義語にも広げて探す
+  #[clap(long, conflicts_with_all = ["all_words", "query", "near"])]
+  synonyms: Option<String>,
+  /// 統計情報を出力するJSONファイルへのpath
+  #[clap(long)]
+  stats: Option<String>,
+  /// 単語が含まれていた項のテキストを、項ごとに1ファイルずつ書き出すディレクトリへのpath
+  #[clap(long)]
+  export_text: Option<String>,
+  /// 単語が含まれていた項のテキストと位置情報を、JSONL形式で書き出すファイルへのpath
+  #[clap(long)]
+  export_text_jsonl: Option<String>,
+  /// 単語が含まれていた条のテキストを、見出しの括弧などの記号を除いた読み上げ（TTS）用のテキストにして、条ごとに1ファイルずつ書き出すディレクトリへのpath
+  #[clap(long)]
+  export_speech: Option<String>,
+  /// 単語が含まれていた項のテキストと単語の出現範囲を、アノテーションツール向けのJSONLで書き出すファイルへのpath
+  #[clap(long)]
+  export_annotation: Option<String>,
+  /// `--export-annotation`で書き出す形式
+  #[clap(long, value_enum, default_value_t = AnnotationFormat::Doccano)]
+  annotation_format: AnnotationFormat,
+  /// 単語が含まれていた条項の引用の表記（「会社法（平成十七年法律第八十六号）第三百三十一条第一項第三号」など）を、重複を除いて一行に一つずつ書き出すテキストファイルへのpath
+  #[clap(long)]
+  citation_list: Option<String>,
+  /// 単語が含まれていた法令の文献情報（法令名・法令番号・公布日・e-Gov法令検索のURL）を書き出すファイルへのpath
+  #[clap(long)]
+  bibliography: Option<String>,
+  /// `--bibliography`で書き出す形式
+  #[clap(long, value_enum, default_value_t = BibliographyFormat::CslJson)]
+  bibliography_format: BibliographyFormat,
+  /// 書き出すテキストの範囲。`article`・`paragraph`・`sentences:N`（単語を含む文と前後N文）のいずれか
+  #[clap(long, default_value = "paragraph")]
+  context: ContextScope,
+  /// 単語が含まれていた条項から指定した件数を無作為に抽出し、前後のテキストと共にJSONLで書き出す
+  #[clap(long, num_args = 2, value_names = ["N", "FILE"])]
+  qa_sample: Vec<String>,
+  /// 取り出したテキストで、ルビの読み仮名を取り除かずに`漢字(かんじ)`のように残す書き出し先（カンマ区切りで複数指定できる）
+  #[clap(long, value_enum, value_delimiter = ',')]
+  furigana: Vec<FuriganaTarget>,
+  /// 検索語と同じ文によく現れる語（文字n-gram）の一覧を書き出すJSONファイルへのpath
+  #[clap(long)]
+  cooccurrence: Option<String>,
+  /// `--cooccurrence`で数える文字n-gramの最大の長さ
+  #[clap(long, default_value_t = 3)]
+  cooccurrence_ngram: usize,
+  /// `--cooccurrence`で検索語ごとに書き出すn-gramの数
+  #[clap(long, default_value_t = 50)]
+  cooccurrence_top: usize,
+  /// 検索語と同じ文によく現れ、検索語に無い語（文字n-gram）をN個、検索語に加える候補として`--stats`に書き出す
+  #[clap(long, value_name = "N", requires = "stats")]
+  suggest_terms: Option<usize>,
+  /// `snapshot`で作った作業ディレクトリの目録のJSONファイルへのpath
+  #[clap(long)]
+  snapshot: Option<String>,
+  /// コードの付いたエラーをJSONLで書き出すファイルへのpath
+  #[clap(long)]
+  error_report: Option<String>,
+  /// 取り出したテキストなどに使うメモリの上限（`512M`・`2G`など）。超えたらテキストの取り出しをやめ、位置情報だけを書き出す
+  #[clap(long)]
+  max_memory: Option<ByteSize>,
+  /// 指定した数の法令を検索するごとに、書き出しているファイルをflushする
+  #[clap(long)]
+  flush_every: Option<usize>,
+  /// flushするときに、ディスクへの書き込みが終わるまで待つ（fsync）
+  #[clap(long)]
+  fsync: bool,
+  /// 検索結果を埋め込んで報告書を作るhandlebars形式のテンプレートファイルへのpath
+  #[clap(long, requires = "report_output")]
+  report_template: Option<String>,
+  /// `--report-template`で作った報告書を書き出すファイルへのpath
+  #[clap(long, requires = "report_template")]
+  report_output: Option<String>,
+  /// 報告書の法令と条へのリンクと絞り込み欄を持つHTMLの索引を書き出すファイルへのpath。
+  /// 報告書と同じディレクトリに置く
+  #[clap(long, requires = "report_output")]
+  report_index: Option<String>,
+  /// `--output`に書き出す形式
+  #[clap(long, value_enum, default_value_t = OutputFormat::Json)]
+  format: OutputFormat,
+  /// `--output`に書き出すJSONの形式の版。2では法令ごとに検索語ごとの見つかった条項の一覧（`hits`）も書き出す
+  #[clap(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=LATEST_SCHEMA_VERSION as i64))]
+  schema_version: u32,
+  /// `pretty`形式と報告書で、条項の番号をどう書くか
+  #[clap(long, value_enum, default_value_t = NumberStyle::Kanji)]
+  number_style: NumberStyle,
+  /// 法令番号をキー、法令の略称を値とするJSONファイルへのpath。`pretty`・`grep`形式と報告書で使う
+  #[clap(long)]
+  abbreviations: Option<String>,
+  /// 同じ法令の版が作業ディレクトリに複数あるときに、どの版を検索するか
+  #[clap(long, value_enum, default_value_t = Versions::Latest)]
+  versions: Versions,
+  /// 法令ファイルを読むときのバッファの大きさ（`512K`や`4M`のように指定する）
+  #[clap(long, default_value = "256K", value_parser = ByteSize::parse_nonzero)]
+  read_buffer_size: ByteSize,
+  /// この大きさ以下の法令ファイルは、一度に全てメモリに読み込んでから検索する
+  #[clap(long)]
+  in_memory_max_size: Option<ByteSize>,
+  /// 法令ファイルをメモリマップし、同期的に検索する。手元の速いディスクにある法令ファイルを検索するときに向く
+  #[clap(long, conflicts_with = "in_memory_max_size")]
+  mmap: bool,
+  /// 同じ箇所で何度も見つかった検索語をどうまとめるか
+  #[clap(long, value_enum, default_value_t = Dedup::Chapter)]
+  dedup: Dedup,
+  /// 全ての検索語がこの範囲（条・項・文）に揃って現れたときだけ、見つかった箇所として記録する
+  #[clap(long, value_enum)]
+  all_words: Option<WordScope>,
+  /// 検索語を正規表現として扱う
+  #[clap(long)]
+  regex: bool,
+  /// 検索語とテキストの照らし合わせ方。`morpheme`（`morpheme` feature）では形態素の境界で始まって終わるところだけを探す
+  #[clap(long, value_enum, default_value_t = MatchMode::Substring, conflicts_with = "regex")]
+  match_mode: MatchMode,
+  /// 空白を除いたテキストで、検索語との編集距離（Levenshtein距離）がN以下の部分文字列も見つかったものとする
+  #[clap(long, value_name = "N", conflicts_with_all = ["regex", "match_mode"])]
+  fuzzy: Option<usize>,
+  /// 検索語の`＊`を任意の長さの文字列、`？`を任意の一文字として扱う
+  #[clap(long, conflicts_with_all = ["regex", "match_mode", "fuzzy"])]
+  wildcard: bool,
+  /// 全角と半角の英数字・記号、英字の大文字と小文字を区別せずに探す
+  #[clap(long, conflicts_with = "regex")]
+  fold_width: bool,
+  /// 漢数字と全角・半角のアラビア数字で書かれた数を同じものとして探す（「第二種」と「第２種」を同じとみなす）
+  #[clap(long, conflicts_with = "regex")]
+  fold_numerals: bool,
+  /// 漢字に続く送り仮名を除いて探す（「取り消し」「取消し」「取消」を同じとみなす）
+  #[clap(long, conflicts_with = "regex")]
+  fold_okurigana: bool,
+  /// 片仮名と平仮名を区別せずに探す（「スル」と「する」を同じとみなす）
+  #[clap(long, conflicts_with = "regex")]
+  fold_kana: bool,
+  /// 旧字体と新字体の漢字を区別せずに探す（「國」と「国」を同じとみなす）
+  #[clap(long, conflicts_with = "regex")]
+  fold_old_kanji: bool,
+  /// 条と項の見出し（`ArticleCaption`・`ParagraphCaption`）のテキストだけを検索する
+  #[clap(long, conflicts_with_all = ["text_unit", "near"])]
+  captions_only: bool,
+  /// 「…とは、…をいう。」の形の定義規定で、定義される語の部分に検索語があるところだけを記録する
+  #[clap(long, conflicts_with_all = ["near", "captions_only"])]
+  definitions_only: bool,
+  /// 「」で囲まれた部分の中にある検索語だけを記録する。法令が語句を引用したり、言い換えたりしているところを探すのに使う
+  #[clap(long)]
+  quoted_only: bool,
+  /// 見つかった箇所に、見つかったテキストとその前後それぞれN文字のテキストを`snippet`として付ける
+  #[clap(long, value_name = "N")]
+  snippet: Option<usize>,
+  /// `--snippet`で付けるテキストの中で、見つかった部分の前後に入れる印（`【 】`や`<em> </em>`など）
+  #[clap(long, num_args = 2, value_names = ["OPEN", "CLOSE"], requires = "snippet")]
+  highlight: Vec<String>,
+  /// 見つかった箇所に、見つかった文（`Sentence`要素）の番号（`sentence_num`）と、文の中での文字単位の位置（`offset`）を付ける
+  #[clap(long)]
+  offsets: bool,
+  /// 条ごとに最初に見つかった箇所だけを記録し、その条の残りのテキストは調べない。検索語が現れる条だけを知りたいときに速くなる
+  #[clap(long)]
+  first_match_per_article: bool,
+  /// 検索語が見つかった回数の合計がN以上の条（`--all-words`があればその範囲）だけを記録する
+  #[clap(long, value_name = "N", conflicts_with = "first_match_per_article")]
+  min_hits: Option<usize>,
+  /// 見つかった箇所のある条それぞれに、文字数・文の数・文の平均の長さ（`article_metrics`）を付ける
+  #[clap(long)]
+  article_metrics: bool,
+  /// `許可 AND (取消 OR 停止) NOT 附則`のような検索式。条ごと（`--all-words`があればその範囲ごと）に評価し、成り立つ箇所だけを記録する
+  #[clap(long, conflicts_with = "search_words")]
+  query: Option<String>,
+  /// 二つの単語が、間にN文字以下しか挟まずに同じ文（`--near-unit`で変えられる）に現れたところだけを記録する
+  #[clap(
+    long,
+    num_args = 3,
+    value_names = ["WORD1", "WORD2", "N"],
+    conflicts_with_all = ["search_words", "query"]
+  )]
+  near: Vec<String>,
+  /// この単位（文か項）の中のテキストを、ルビの読みを除いてつなげてから検索する。ルビや文の区切りをまたぐ語も見つけられる
+  #[clap(long, value_enum)]
+  text_unit: Option<TextUnit>,
+  /// 要素の入れ子の深さの上限。これより深い法令XMLや、開始タグと終了タグが対応していない法令XMLはエラー（`E005`）にする
+  #[clap(long, default_value_t = DEFAULT_MAX_DEPTH)]
+  max_depth: usize,
+  /// `--near`の二つの単語が現れなければならない範囲
+  #[clap(long, value_enum, default_value_t = TextUnit::Sentence, requires = "near")]
+  near_unit: TextUnit,
+  /// 単語が含まれていなかった法令も、空の`chapter_data`と共に書き出す
+  #[clap(long)]
+  include_empty: bool,
+  /// 処理した法令ファイルごとの状態・かかった時間・単語が含まれていた条項の数を、JSONLで書き出すファイルへのpath
+  #[clap(long)]
+  manifest: Option<String>,
+  /// インデックスファイルに同じ法令ファイル（シンボリックリンクを辿って同じになるものも含む）や、同じ法令番号の法令が二度以上あったときにどうするか
+  #[clap(long, value_enum, default_value_t = OnDuplicate::Warn)]
+  on_duplicate: OnDuplicate,
+  /// 同時に検索する法令ファイルの数
+  #[clap(long, default_value_t = 1)]
+  concurrency: usize,
+  /// 設定・インデックスファイルと目録のSHA-256・かかった時間・書き出したファイルのSHA-256を、一つのJSONにまとめて書き出すファイルへのpath
+  #[clap(long)]
+  session_log: Option<String>,
+  /// 検索のオプションを書いたTOMLの設定ファイルへのpath。キーはオプションの長い名前で、コマンドラインで指定したものが優先される
+  #[clap(long)]
+  config: Option<String>,
+  /// ヘルプ・警告・報告書の文言の言語（`ja`か`en`）。省略したときはロケールに従う
+  #[clap(long, value_enum, global = true)]
+  lang: Option<Lang>,
+  /// 無作為抽出に使う乱数の種。指定すると`--qa-sample`・`verify`・`sample-paragraphs`で毎回同じものを抽出する。
+  /// `sample-paragraphs`で省略したときは0を使う
+  #[clap(long, global = true)]
+  seed: Option<u64>,
+  /// 検索できた法令ごとに検索結果を送るNATSサーバーのURL
+  #[cfg(feature = "nats")]
+  #[clap(long, requires = "nats_subject")]
+  nats_url: Option<String>,
+  /// 見つかった箇所を一つずつ渡し、捨てたりタグ・属性を付けたりさせるWASMのプラグインへのpath。
+  /// 複数指定でき、指定した順に呼ぶ（`plugin` feature）
+  #[cfg(feature = "plugin")]
+  #[clap(long)]
+  plugin: Vec<String>,
+  /// 見つかった箇所一つごとに評価し、`true`になった箇所だけを残すrhaiの式（`scope == "suppl" && count > 2`など、`filter-expr` feature）
+  #[cfg(feature = "filter-expr")]
+  #[clap(long)]
+  filter_expr: Option<String>,
+  /// 見つかった箇所を採点するcross-encoderの`model.onnx`と`tokenizer.json`を置いたディレクトリへのpath（`relevance` feature）
+  #[cfg(feature = "relevance")]
+  #[clap(long, requires_all = ["relevance_query", "relevance_output"])]
+  relevance_model: Option<String>,
+  /// `--relevance-model`で見つかった箇所と比べる、調べたいことを説明する文
+  #[cfg(feature = "relevance")]
+  #[clap(long, requires = "relevance_model")]
+  relevance_query: Option<String>,
+  /// 採点した見つかった箇所を、点数の高い順にJSONLで書き出すファイルへのpath
+  #[cfg(feature = "relevance")]
+  #[clap(long, requires = "relevance_model")]
+  relevance_output: Option<String>,
+  /// `--nats-url`で検索結果を送るsubject
+  #[cfg(feature = "nats")]
+  #[clap(long, requires = "nats_url")]
+  nats_subject: Option<String>,
+  /// `--format postgres`で検索結果を書き込むPostgreSQLのデータベースの接続文字列
+  #[cfg(feature = "postgres")]
+  #[clap(long, required_if_eq("format", "postgres"))]
+  dsn: Option<String>,
+  /// `--format postgres`で、一つのトランザクションで書き込む法令の数
+  #[cfg(feature = "postgres")]
+  #[clap(long, default_value_t = 100)]
+  postgres_batch_size: usize,
+}
+
+/// 同じ法令の版が作業ディレクトリに複数あるときに、どの版を検索するか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+enum Versions {
+  /// インデックスファイルに書かれた版だけ
+  Latest,
+  /// 作業ディレクトリにある全ての版。結果には版を付ける
+  All,
+}
+
+/// `--furigana`でルビの読み仮名を残す書き出し先
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+enum FuriganaTarget {
+  /// `--export-text`
+  ExportText,
+  /// `--export-text-jsonl`
+  ExportTextJsonl,
+  /// `--export-annotation`
+  ExportAnnotation,
+  /// `--qa-sample`
+  QaSample,
+  /// `--format site`
+  Site,
+  /// `--format xlsx`
+  #[cfg(feature = "xlsx")]
+  Xlsx,
+}
+
+/// 書き出し先`target`でのルビの読み仮名の扱い
+fn ruby_style(furigana: &[FuriganaTarget], target: FuriganaTarget) -> RubyStyle {
+  if furigana.contains(&target) {
+    RubyStyle::Parenthesized
+  } else {
+    RubyStyle::Strip
+  }
+}
+
+/// コマンドラインの定義。英語のときは引数のヘルプを英語のカタログのものに差し替える
+fn args_command() -> clap::Command {
+  let mut command = Args::command();
+  if lang() == Lang::En {
+    command = command.about(ABOUT_EN);
+    let ids = command
+      .get_arguments()
+      .map(|arg| arg.get_id().to_string())
+      .collect::<Vec<_>>();
+    for id in ids.iter() {
+      if let Some(help) = help_en(id) {
+        command = command.mut_arg(id, |arg| arg.help(help));
+      }
+    }
+  }
+  command
+}
+
+/// コマンドライン引数の`--config`の値
+fn config_path(raw_args: &[String]) -> Option<&str> {
+  raw_args.iter().enumerate().find_map(|(i, arg)| {
+    if arg == "--config" {
+      raw_args.get(i + 1).map(|value| value.as_str())
+    } else {
+      arg.strip_prefix("--config=")
+    }
+  })
+}
+
+/// コマンドライン引数から検索の設定を作る。検索式・`--near`・同義語の辞書に合わせて`args.search_words`も書き換え、
+/// 同義語として加えた語からそれを加えた元の検索語への対応も返す
+async fn search_config_of(args: &mut Args) -> Result<(SearchConfig, HashMap<String, String>)> {
+  // 検索式があれば、検索式に現れる語を検索語にする
+  let query = match &args.query {
+    Some(query_str) => {
+      let query = Query::parse(query_str)?;
+      args.search_words = query.terms();
+      Some(query)
+    }
+    None => None,
+  };
+  let proximity = match args.near.as_slice() {
+    [word1, word2, distance] => {
+      let distance = distance
+        .parse()
+        .map_err(|_| anyhow!("`--near`の文字数が数ではありません: {distance}"))?;
+      args.search_words = vec![word1.clone(), word2.clone()];
+      Some(Proximity {
+        distance,
+        unit: args.near_unit,
+      })
+    }
+    _ => None,
+  };
+
+  // 同義語の辞書があれば、検索語をその同義語にも広げる
+  let synonym_origins = match &args.synonyms {
+    Some(path) => {
+      let (search_words, origins) = Synonyms::load(Path::new(path))
+        .await?
+        .expand(&args.search_words);
+      args.search_words = search_words;
+      origins
+    }
+    None => HashMap::new(),
+  };
+
+  let search_config = SearchConfig {
+    dedup: args.dedup,
+    all_words: args.all_words,
+    is_regex: args.regex,
+    match_mode: args.match_mode,
+    fuzzy: args.fuzzy,
+    is_wildcard: args.wildcard,
+    folding: Folding {
+      is_width_case: args.fold_width,
+      is_numerals: args.fold_numerals,
+      is_okurigana: args.fold_okurigana,
+      is_kana: args.fold_kana,
+      is_old_kanji: args.fold_old_kanji,
+    },
+    is_captions_only: args.captions_only,
+    is_definitions_only: args.definitions_only,
+    is_quoted_only: args.quoted_only,
+    snippet_context: args.snippet,
+    highlight: match args.highlight.as_slice() {
+      [open, close] => Some(Highlight {
+        open: open.clone(),
+        close: close.clone(),
+      }),
+      _ => None,
+    },
+    is_offsets: args.offsets,
+    is_first_match_per_article: args.first_match_per_article,
+    min_hits: args.min_hits,
+    is_article_metrics: args.article_metrics,
+    query,
+    exclude_words: args.exclude_words.clone(),
+    proximity,
+    text_unit: args.text_unit,
+    max_depth: args.max_depth,
+  };
+  // 正規表現として解釈できない検索語があれば、法令ファイルごとにエラーにせず始めに止める。
+  // ここで作った判定の仕方は、全ての法令ファイルの検索で使い回す
+  Matchers::shared(&args.search_words, &search_config)?;
+  Ok((search_config, synonym_origins))
+}
+
+/// コマンドライン引数を解析する。`--config`があれば、設定ファイルの内容をコマンドライン引数の前に置き、
+/// 同じオプションがコマンドラインにもあればそちらを使う
+async fn parse_args(raw_args: Vec<String>) -> Result<Args> {
+  let mut command = args_command();
+  let raw_args = match config_path(&raw_args) {
+    Some(path) => {
+      command.build();
+      let config = load_config(Path::new(path)).await?;
+      let (config_args, issues) = config_to_args(&config, &command);
+      if !issues.is_empty() {
+        let issues = issues
+          .iter()
+          .map(|issue| issue.to_string())
+          .collect::<Vec<_>>();
+        return Err(anyhow!(
+          "設定ファイルに問題があります: {}",
+          issues.join("; ")
+        ));
+      }
+      command = command.args_override_self(true);
+      raw_args[..1]
+        .iter()
+        .cloned()
+        .chain(config_args)
+        .chain(raw_args[1..].iter().cloned())
+        .collect()
+    }
+    None => raw_args,
+  };
+  let matches = command.try_get_matches_from(raw_args)?;
+  Ok(Args::from_arg_matches(&matches)?)
+}
+
+/// 設定ファイルを検索を始める前に確かめ、知らないキー・型の誤り・同時に使えないオプションの組み合わせを書き出す。
+/// 必須のオプションが無いことは、コマンドラインで指定できるので問題にしない
+async fn config_validate_command(file: &str) -> Result<()> {
+  let mut command = args_command();
+  command.build();
+  let config = load_config(Path::new(file)).await?;
+  let (config_args, mut issues) = config_to_args(&config, &command);
+  let argv = std::iter::once(command.get_name().to_string()).chain(config_args);
+  if let Err(e) = command.args_override_self(true).try_get_matches_from(argv) {
+    if e.kind() != ErrorKind::MissingRequiredArgument {
+      issues.push(ConfigIssue {
+        key: None,
+        message: e.to_string().lines().next().unwrap_or_default().to_string(),
+      });
+    }
+  }
+  for issue in issues.iter() {
+    println!("{issue}");
+  }
+  if issues.is_empty() {
+    info!("config ok: {file}");
+    Ok(())
+  } else {
+    Err(anyhow!(
+      "設定ファイルに{}件の問題があります: {file}",
+      issues.len()
+    ))
+  }
+}
+
+/// インデックスファイルに同じ法令が二度以上あったときにどうするか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+enum OnDuplicate {
+  /// 警告を出し、どちらも書き出す
+  Warn,
+  /// 警告を出し、始めのものだけを書き出す
+  Skip,
+  /// エラーにして止める
+  Error,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+  /// 検索結果のJSONファイルに書かれた条項それぞれについて、条文のテキストを取り出してJSONLで書き出す
+  Hydrate {
+    /// 検索結果のJSONファイルへのpath
+    results: String,
+    /// 条文のテキストを書き出すJSONLファイルへのpath
+    #[clap(short, long)]
+    output: String,
+    /// 法令XMLファイル群が置かれている作業ディレクトリへのpath
+    #[clap(short, long, required_unless_present = "api")]
+    work: Option<String>,
+    /// 作業ディレクトリの代わりにe-Gov法令APIから条文を取得する
+    #[clap(long)]
+    api: bool,
+    #[clap(flatten)]
+    http: HttpArgs,
+  },
+  /// 指定した日以降にe-Gov法令APIで更新された法令を調べ、作業ディレクトリにある古いファイルを報告する
+  CheckUpdates {
+    /// この日（YYYYMMDD）から今日までに更新された法令を調べる
+    #[clap(long)]
+    since: String,
+    /// 法令XMLファイル群が置かれている作業ディレクトリへのpath
+    #[clap(short, long)]
+    work: String,
+    /// 確認結果を書き出すJSONファイルへのpath
+    #[clap(short, long)]
+    output: String,
+    /// 古いファイルや作業ディレクトリに無いファイルについて、最新の法令XMLをこのディレクトリにダウンロードする
+    #[clap(long)]
+    download: Option<String>,
+    #[clap(flatten)]
+    http: HttpArgs,
+  },
+  /// 作業ディレクトリにある法令XMLファイルの一覧とそれぞれのSHA-256を記録した目録を作る
+  Snapshot {
+    /// 法令XMLファイル群が置かれている作業ディレクトリへのpath
+    #[clap(short, long)]
+    work: String,
+    /// 目録を書き出すJSONファイルへのpath
+    #[clap(short, long)]
+    output: String,
+  },
+  /// 検索結果のJSONファイルに書かれた条項を無作為に抽出し、今の作業ディレクトリで検索し直しても見つかるかどうかを確かめる
+  Verify {
+    /// 検索結果のJSONファイルへのpath
+    results: String,
+    /// 法令XMLファイル群が置かれている作業ディレクトリへのpath
+    #[clap(short, long)]
+    work: String,
+    /// 確認結果を書き出すJSONファイルへのpath
+    #[clap(short, long)]
+    output: Option<String>,
+    /// 確かめる条項の数
+    #[clap(long, default_value_t = 100)]
+    sample: usize,
+    /// 検索し直すときに使う単語。省略したときは検索結果に記録されている単語を使う
+    #[clap(short, long)]
+    search_words: Vec<String>,
+    /// 検索結果を作ったときの`--session-log`の記録。記録されたコマンドライン引数と同じ検索語・照らし合わせ方で検索し直す
+    #[clap(long)]
+    session_log: Option<String>,
+  },
+  /// 作業ディレクトリにある全ての法令の項から、決まった数の項を一様に無作為抽出し、位置とテキストをJSONLで書き出す
+  SampleParagraphs {
+    /// 法令XMLファイル群が置かれている作業ディレクトリへのpath
+    #[clap(short, long)]
+    work: String,
+    /// 抽出した項を書き出すJSONLファイルへのpath
+    #[clap(short, long)]
+    output: String,
+    /// 抽出する項の数
+    #[clap(long, default_value_t = 100)]
+    sample: usize,
+    /// 同時に読む法令ファイルの数
+    #[clap(long, default_value_t = 1)]
+    concurrency: usize,
+  },
+  /// 人手で正解を付けた箇所の一覧と検索結果を比べ、適合率と再現率を書き出す
+  Evaluate {
+    /// 見つかった箇所のIDと正解かどうかを一行に一つずつ書いたJSONLファイルへのpath
+    #[clap(long)]
+    gold: String,
+    /// 検索結果のJSONファイルへのpath
+    #[clap(long)]
+    results: String,
+    /// 評価の結果を書き出すJSONファイルへのpath。省略したときは標準出力に書き出す
+    #[clap(short, long)]
+    output: Option<String>,
+  },
+  /// 警告・エラーのコードとその説明の一覧をJSONで標準出力に書き出す
+  Codes,
+  /// `--config`で読み込むTOMLの設定ファイルを扱う
+  Config {
+    #[clap(subcommand)]
+    command: ConfigCommand,
+  },
+  /// 二つの検索結果のJSONファイルを比べ、検索語が見つかった箇所の追加・削除・移動を書き出す
+  Diff {
+    /// 古い検索結果のJSONファイルへのpath
+    old: String,
+    /// 新しい検索結果のJSONファイルへのpath
+    new: String,
+    /// 比較結果を書き出すJSONファイルへのpath
+    #[clap(short, long)]
+    output: String,
+    /// 古い検索結果を得たときの作業ディレクトリへのpath。`--new-work`と共に指定すると、条の見出しと条文の類似度から条を対応付けて移動を見つける
+    #[clap(long, requires = "new_work")]
+    old_work: Option<String>,
+    /// 新しい検索結果を得たときの作業ディレクトリへのpath
+    #[clap(long, requires = "old_work")]
+    new_work: Option<String>,
+    /// 条を対応付けるのに必要な類似度の下限
+    #[clap(long, default_value_t = DEFAULT_THRESHOLD)]
+    align_threshold: f64,
+  },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+  /// 設定ファイルに知らないキー・型の誤り・同時に使えないオプションの組み合わせが無いかを確かめる
+  Validate {
+    /// 設定ファイルへのpath
+    file: String,
+  },
+  /// 設定ファイルに書けるキーと値の型のJSON Schemaを標準出力に書き出す
+  Schema,
+}
+
+/// 外部のAPIを使うときの設定
+#[derive(clap::Args, Debug)]
+struct HttpArgs {
+  /// リクエストが失敗したときに再試行する回数
+  #[clap(long, default_value_t = 3)]
+  http_retries: usize,
+  /// リクエストとリクエストの間に最低限空ける時間（ミリ秒）
+  #[clap(long, default_value_t = 1000)]
+  http_interval_ms: u64,
+  /// レスポンスを保存しておくディレクトリへのpath
+  #[clap(long)]
+  http_cache_dir: Option<String>,
+}
+
+impl HttpArgs {
+  fn to_config(&self) -> HttpConfig {
+    HttpConfig {
+      max_retries: self.http_retries,
+      min_interval: Duration::from_millis(self.http_interval_ms),
+      cache_dir: self.http_cache_dir.as_ref().map(PathBuf::from),
+      ..Default::default()
+    }
+  }
+}
+
+/// 項のテキストを書き出すファイルの名前を、法令ファイルの名前と項の位置情報から作る
+fn export_file_name(
+  file_stem: &str,
+  chapter: &Chapter,
+  used_names: &mut HashSet<String>,
+) -> String {
+  let mut name = file_stem.to_string();
+  if let Some(title) = &chapter.suppl_provision_title {
+    name.push_str(&format!("_suppl_{title}"));
+  }
+  if !chapter.article.is_empty() {
+    name.push_str(&format!("_{}", chapter.article));
+  }
+  if let Some(paragraph) = &chapter.paragraph {
+    name.push_str(&format!("_{paragraph}"));
+  }
+  let mut file_name = format!("{name}.txt");
+  let mut n = 1;
+  while used_names.contains(&file_name) {
+    n += 1;
+    file_name = format!("{name}_{n}.txt");
+  }
+  used_names.insert(file_name.clone());
+  file_name
+}
+
+/// 抽出した条項それぞれについて、その条項を含む項のテキストを取り出す
+async fn hydrate_samples(
+  mut samples: Vec<(PathBuf, String, Chapter, Vec<String>)>,
+  search_words: &[String],
+  context_scope: ContextScope,
+  ruby: RubyStyle,
+) -> Result<Vec<SampledMatch>> {
+  samples.sort();
+  let mut sampled_matches = vec![];
+  let mut i = 0;
+  while i < samples.len() {
+    let file_path = samples[i].0.clone();
+    let j = i
+      + samples[i..]
+        .iter()
+        .take_while(|(path, _, _, _)| path == &file_path)
+        .count();
+    let paragraphs = samples[i..j]
+      .iter()
+      .map(|(_, _, chapter, _)| context_scope.target(chapter))
+      .collect::<Vec<_>>();
+    let mut reader = Reader::from_reader(BufReader::new(File::open(&file_path).await?));
+    let texts = hydrate_chapters_with(&paragraphs, &mut reader, ruby).await?;
+    for ((_, num, chapter, match_ids), text) in samples[i..j].iter().zip(texts) {
+      let context = context_scope.trim(&text, search_words);
+      let spans = find_spans(&context, search_words);
+      sampled_matches.push(SampledMatch {
+        num: num.clone(),
+        chapter: chapter.clone(),
+        context,
+        spans,
+        match_ids: match_ids.clone(),
+      });
+    }
+    i = j;
+  }
+  Ok(sampled_matches)
+}
+
+/// 作業ディレクトリにある全ての法令の項から、`sample`個の項を一様に無作為抽出してJSONLで書き出す。
+/// 項ごとに種と位置から決まる乱数を割り当て、その小さいものを選ぶので、同時に読むファイルの数によらず同じ項を選ぶ
+async fn sample_paragraphs_command(
+  work: &str,
+  output: &str,
+  sample: usize,
+  seed: u64,
+  concurrency: usize,
+) -> Result<()> {
+  info!("[START] find law files: {:?}", work);
+  let mut law_file_paths = find_law_files(Path::new(work))
+    .await?
+    .into_values()
+    .collect::<Vec<_>>();
+  info!("[END] find law files: {:?}", work);
+  law_file_paths.sort();
+
+  let (search_words, search_config) = paragraph_search();
+  let config = SearchManyConfig {
+    search_words,
+    search_config,
+    concurrency,
+    ..Default::default()
+  };
+  let mut results = Box::pin(search_many(law_file_paths.clone(), config));
+  let mut titles = vec![];
+  let mut bottom_k = BottomK::new(sample);
+  let mut paragraph_count = 0;
+  info!("[START] sample paragraphs: {:?}", work);
+  for (file_index, file_path) in law_file_paths.iter().enumerate() {
+    let law_paragraph = match results.next().await {
+      Some(Ok(law_paragraph)) => law_paragraph,
+      Some(Err(e)) => {
+        report_warning(Code::XmlParse, format!("{:?}: {}", file_path, e));
+        titles.push(None);
+        continue;
+      }
+      None => break,
+    };
+    // 条の見出しなど、項の外のテキストで見つかったものは除く
+    for (chapter_index, chapter) in law_paragraph
+      .chapter_data
+      .into_iter()
+      .enumerate()
+      .filter(|(_, chapter)| chapter.paragraph.is_some())
+    {
+      let key = format!(
+        "{}\u{1f}{}",
+        law_paragraph.num,
+        serde_json::to_string(&chapter)?
+      );
+      let priority = seeded_priority(seed, &key);
+      bottom_k.add(
+        priority,
+        (
+          file_index,
+          chapter_index,
+          law_paragraph.num.clone(),
+          chapter,
+        ),
+      );
+      paragraph_count += 1;
+    }
+    titles.push(law_paragraph.title);
+  }
+  info!("[END] sample paragraphs: {:?}", work);
+  let mut samples = bottom_k.into_sorted_vec();
+  info!(
+    "sample paragraphs: {} of {} paragraphs",
+    samples.len(),
+    paragraph_count
+  );
+  // 法令ファイルごと、法令の中の順に並べ直す
+  samples.sort();
+
+  let mut output_file = File::create(output).await?;
+  let mut i = 0;
+  while i < samples.len() {
+    let file_index = samples[i].0;
+    let j = i
+      + samples[i..]
+        .iter()
+        .take_while(|(index, _, _, _)| *index == file_index)
+        .count();
+    let chapters = samples[i..j]
+      .iter()
+      .map(|(_, _, _, chapter)| chapter.clone())
+      .collect::<Vec<_>>();
+    let file_path = &law_file_paths[file_index];
+    let mut reader = Reader::from_reader(BufReader::new(File::open(file_path).await?));
+    let texts = hydrate_chapters_with(&chapters, &mut reader, RubyStyle::Strip).await?;
+    for ((_, _, num, chapter), text) in samples[i..j].iter().zip(texts) {
+      let sampled_paragraph = SampledParagraph {
+        num: num.clone(),
+        title: titles[file_index].clone(),
+        chapter: chapter.clone(),
+        text,
+      };
+      let sampled_paragraph_json_str = serde_json::to_string(&sampled_paragraph)?;
+      output_file
+        .write_all(sampled_paragraph_json_str.as_bytes())
+        .await?;
+      output_file.write_all("\n".as_bytes()).await?;
+    }
+    i = j;
+  }
+  output_file.flush().await?;
+  Ok(())
+}
+
+/// 検索結果を正解の一覧と比べ、適合率と再現率を書き出す
+async fn evaluate_command(gold: &str, results: &str, output: Option<&str>) -> Result<()> {
+  info!("[START] read gold: {:?}", gold);
+  let labels = read_gold(Path::new(gold)).await?;
+  info!("[END] read gold: {:?}", gold);
+  info!("[START] read results: {:?}", results);
+  let law_paragraphs = search_article_with_word::get_law_from_artcile_info(results).await?;
+  info!("[END] read results: {:?}", results);
+  let report = evaluate(&law_paragraphs, &labels);
+  info!(
+    "evaluate: precision {:?}, recall {:?} ({} unjudged)",
+    report.precision, report.recall, report.unjudged
+  );
+  let report_json_str = serde_json::to_string_pretty(&report)?;
+  match output {
+    Some(output) => {
+      let mut output_file = File::create(output).await?;
+      output_file.write_all(report_json_str.as_bytes()).await?;
+      output_file.flush().await?;
+    }
+    None => println!("{report_json_str}"),
+  }
+  Ok(())
+}
+
+/// 検索結果の条項それぞれについて条文のテキストを取り出し、JSONLで書き出す
+async fn hydrate_command(
+  results: &str,
+  output: &str,
+  work: Option<&str>,
+  is_use_api: bool,
+  http_config: HttpConfig,
+) -> Result<()> {
+  info!("[START] read results: {:?}", results);
+  let law_paragraphs = search_article_with_word::get_law_from_artcile_info(results).await?;
+  info!("[END] read results: {:?}", results);
+
+  let mut output_file = File::create(output).await?;
+  let mut chapter_texts = vec![];
+  if is_use_api {
+    let client = HttpClient::new(http_config)?;
+    for law_paragraph in law_paragraphs.iter() {
+      for chapter in law_paragraph.chapter_data.iter() {
+        info!("[START] fetch: {} {:?}", law_paragraph.num, chapter);
+        match fetch_chapter_text(&client, &law_paragraph.num, chapter).await {
+          Ok(text) => chapter_texts.push(ChapterText {
+            num: law_paragraph.num.clone(),
+            chapter: chapter.clone(),
+            text,
+            match_ids: law_paragraph.match_ids_within(chapter),
+          }),
+          Err(e) => report_warning(
+            Code::ApiFetchFailed,
+            format!("fetch error: {} {:?}: {}", law_paragraph.num, chapter, e),
+          ),
+        }
+        info!("[END] fetch: {} {:?}", law_paragraph.num, chapter);
+      }
+    }
+  } else {
+    // `required_unless_present = "api"`なので、APIを使わないときは必ず値がある
+    let work_dir_path = Path::new(work.unwrap());
+    info!("[START] find law files: {:?}", work_dir_path);
+    let law_files = find_law_files(work_dir_path).await?;
+    info!("[END] find law files: {:?}", work_dir_path);
+    for law_paragraph in law_paragraphs.iter() {
+      let file_path = match law_files.get(&law_paragraph.num) {
+        Some(file_path) => file_path,
+        None => {
+          report_warning(
+            Code::LawFileNotFound,
+            format!("law file not found: {}", law_paragraph.num),
+          );
+          continue;
+        }
+      };
+      info!("[START] hydrate: {:?}", file_path);
+      let mut reader = Reader::from_reader(BufReader::new(File::open(file_path).await?));
+      let texts = hydrate_chapters(&law_paragraph.chapter_data, &mut reader).await?;
+      for (chapter, text) in law_paragraph.chapter_data.iter().zip(texts) {
+        chapter_texts.push(ChapterText {
+          num: law_paragraph.num.clone(),
+          chapter: chapter.clone(),
+          text,
+          match_ids: law_paragraph.match_ids_within(chapter),
+        });
+      }
+      info!("[END] hydrate: {:?}", file_path);
+    }
+  }
+
+  for chapter_text in chapter_texts.iter() {
+    let chapter_text_json_str = serde_json::to_string(chapter_text)?;
+    output_file
+      .write_all(chapter_text_json_str.as_bytes())
+      .await?;
+    output_file.write_all("\n".as_bytes()).await?;
+  }
+  output_file.flush().await?;
+  Ok(())
+}
+
+/// 指定した日から今日までに更新された法令について、作業ディレクトリのファイルが最新かどうかを調べる
+async fn check_updates_command(
+  since: &str,
+  work: &str,
+  output: &str,
+  download: Option<&str>,
+  http_config: HttpConfig,
+) -> Result<()> {
+  let since_date =
+    Date::parse(since).ok_or_else(|| anyhow!("日付の形式が正しくありません: {}", since))?;
+  let today = Date::today();
+  let client = HttpClient::new(http_config)?;
+
+  info!("[START] find law files: {:?}", work);
+  let law_files = find_law_files(Path::new(work)).await?;
+  info!("[END] find law files: {:?}", work);
+
+  let mut reports = vec![];
+  let mut date = since_date;
+  while date <= today {
+    info!("[START] fetch updated laws: {}", date);
+    match fetch_updated_laws(&client, &date).await {
+      Ok(updated_laws) => {
+        for updated_law in updated_laws {
+          let local_file = law_files.get(&updated_law.law_num);
+          let status = classify(&updated_law, &date, local_file.map(|path| path.as_path()));
+          reports.push(UpdateReport {
+            law: updated_law,
+            updated_date: date.to_string(),
+            status,
+            local_file: local_file.cloned(),
+          });
+        }
+      }
+      Err(e) => report_warning(
+        Code::UpdateListFetchFailed,
+        format!("fetch updated laws error: {}: {}", date, e),
+      ),
+    }
+    info!("[END] fetch updated laws: {}", date);
+    date = date.succ();
+  }
+
+  // 同じ法令が何度も更新されているときは、最後の更新の結果だけを残す
+  let mut law_ids = HashSet::new();
+  reports.reverse();
+  reports.retain(|report| law_ids.insert(report.law.law_id.clone()));
+  reports.reverse();
+
+  let stale_count = reports
+    .iter()
+    .filter(|report| report.status == UpdateStatus::Stale)
+    .count();
+  info!("stale files: {}", stale_count);
+
+  // ダウンロードに失敗しても確認の結果は残るように、先に書き出す
+  let mut output_file = File::create(output).await?;
+  let reports_json_str = serde_json::to_string(&reports)?;
+  output_file.write_all(reports_json_str.as_bytes()).await?;
+  output_file.flush().await?;
+
+  if let Some(download_dir) = download {
+    create_dir_all(download_dir).await?;
+    for report in reports
+      .iter()
+      .filter(|report| report.status != UpdateStatus::UpToDate)
+    {
+      info!("[START] download: {}", report.law.law_id);
+      let xml = match fetch_law_xml(&client, &report.law.law_id).await {
+        Ok(xml) => xml,
+        Err(e) => {
+          report_warning(
+            Code::ApiFetchFailed,
+            format!("download error: {}: {}", report.law.law_id, e),
+          );
+          continue;
+        }
+      };
+      let file_name = download_file_name(&report.law, &report.updated_date);
+      let file_path = Path::new(download_dir).join(file_name);
+      let mut xml_file = File::create(&file_path).await?;
+      xml_file.write_all(xml.as_bytes()).await?;
+      xml_file.flush().await?;
+      info!("[END] download: {}", report.law.law_id);
+    }
+  }
+  Ok(())
+}
+
+/// 作業ディレクトリの目録を作り、JSONで書き出す
+async fn snapshot_command(work: &str, output: &str) -> Result<()> {
+  info!("[START] create snapshot: {:?}", work);
+  let snapshot = create_snapshot(Path::new(work)).await?;
+  info!("[END] create snapshot: {:?}", work);
+  info!("snapshot: {} files", snapshot.files.len());
+  let mut output_file = File::create(output).await?;
+  let snapshot_json_str = serde_json::to_string(&snapshot)?;
+  output_file.write_all(snapshot_json_str.as_bytes()).await?;
+  output_file.flush().await?;
+  Ok(())
+}
+
+/// 検索結果から無作為に抽出した条項を検索し直し、結果が変わっていないかどうかを確かめる
+async fn verify_command(
+  results: &str,
+  work: &str,
+  output: Option<&str>,
+  sample: usize,
+  search_words: &[String],
+  session_log: Option<&str>,
+  seed: Option<u64>,
+) -> Result<()> {
+  // 記録があれば、検索結果を作ったときのコマンドライン引数から検索語と照らし合わせ方を作り直す
+  let (session_words, search_config) = match session_log {
+    Some(path) => {
+      let mut session_args = parse_args(read_session_arguments(path).await?)
+        .await
+        .map_err(|e| anyhow!("記録されたコマンドライン引数を解析できません: {path}: {e}"))?;
+      let (search_config, _) = search_config_of(&mut session_args).await?;
+      (session_args.search_words, search_config)
+    }
+    None => {
+      warn!(
+        "verify without --session-log: re-searching with the default matching options, \
+         so results made with --regex, --fuzzy, --wildcard, --fold-* or --dedup are reported as mismatched"
+      );
+      (vec![], SearchConfig::default())
+    }
+  };
+  let search_words = if search_words.is_empty() {
+    &session_words
+  } else {
+    search_words
+  };
+  info!("[START] read results: {:?}", results);
+  let law_paragraphs = search_article_with_word::get_law_from_artcile_info(results).await?;
+  info!("[END] read results: {:?}", results);
+
+  info!("[START] find law files: {:?}", work);
+  let law_files = find_law_files(Path::new(work)).await?;
+  info!("[END] find law files: {:?}", work);
+
+  let mut reservoir = Reservoir::new(sample);
+  let mut rng = seeded_rng(seed);
+  for (law_index, law_paragraph) in law_paragraphs.iter().enumerate() {
+    for chapter in law_paragraph.chapter_data.iter() {
+      reservoir.add((law_index, chapter.clone()), &mut rng);
+    }
+  }
+  let mut samples = reservoir.into_vec();
+  samples.sort();
+
+  let mut reports = vec![];
+  let mut i = 0;
+  while i < samples.len() {
+    let law_index = samples[i].0;
+    let j = i
+      + samples[i..]
+        .iter()
+        .take_while(|(index, _)| *index == law_index)
+        .count();
+    let law_paragraph = &law_paragraphs[law_index];
+    let chapters = samples[i..j]
+      .iter()
+      .map(|(_, chapter)| chapter.clone())
+      .collect::<Vec<_>>();
+    let statuses = match law_files.get(&law_paragraph.num) {
+      Some(file_path) => {
+        let words = if search_words.is_empty() {
+          &law_paragraph.matched_words
+        } else {
+          search_words
+        };
+        info!("[START] verify: {:?}", file_path);
+        let statuses = verify_chapters(file_path, words, &search_config, &chapters).await?;
+        info!("[END] verify: {:?}", file_path);
+        statuses
+      }
+      None => vec![VerifyStatus::LawFileNotFound; chapters.len()],
+    };
+    for (chapter, status) in chapters.into_iter().zip(statuses) {
+      if status != VerifyStatus::Matched {
+        let code = match status {
+          VerifyStatus::LawFileNotFound => Code::LawFileNotFound,
+          _ => Code::VerifyMismatch,
+        };
+        report_warning(
+          code,
+          format!("verify {:?}: {} {:?}", status, law_paragraph.num, chapter),
+        );
+      }
+      reports.push(VerifyReport {
+        num: law_paragraph.num.clone(),
+        chapter,
+        status,
+      });
+    }
+    i = j;
+  }
+
+  if let Some(output) = output {
+    let mut output_file = File::create(output).await?;
+    let reports_json_str = serde_json::to_string(&reports)?;
+    output_file.write_all(reports_json_str.as_bytes()).await?;
+    output_file.flush().await?;
+  }
+
+  let mismatch_count = reports
+    .iter()
+    .filter(|report| report.status != VerifyStatus::Matched)
+    .count();
+  info!(
+    "verify: {} / {} matches reproduced",
+    reports.len() - mismatch_count,
+    reports.len()
+  );
+  if mismatch_count > 0 {
+    return Err(anyhow!("{}件の条項が再現できませんでした", mismatch_count));
+  }
+  Ok(())
+}
+
+/// 二つの検索結果を比べ、変化した箇所の一覧をJSONで書き出す
+async fn diff_command(
+  old: &str,
+  new: &str,
+  output: &str,
+  work_dirs: Option<(&str, &str)>,
+  align_threshold: f64,
+) -> Result<()> {
+  info!("[START] read results: {:?} {:?}", old, new);
+  let old_results = search_article_with_word::get_law_from_artcile_info(old).await?;
+  let new_results = search_article_with_word::get_law_from_artcile_info(new).await?;
+  info!("[END] read results: {:?} {:?}", old, new);
+  let mut entries = diff_results(&old_results, &new_results);
+
+  if let Some((old_work, new_work)) = work_dirs {
+    let old_law_files = find_law_files(Path::new(old_work)).await?;
+    let new_law_files = find_law_files(Path::new(new_work)).await?;
+    let mut nums = entries
+      .iter()
+      .filter(|entry| entry.kind == DiffKind::Removed)
+      .map(|entry| entry.num.clone())
+      .collect::<Vec<_>>();
+    nums.dedup();
+    for num in nums.iter() {
+      let (old_file, new_file) = match (old_law_files.get(num), new_law_files.get(num)) {
+        (Some(old_file), Some(new_file)) => (old_file, new_file),
+        _ => {
+          report_warning(
+            Code::LawFileNotFound,
+            format!("law file not found: {}", num),
+          );
+          continue;
+        }
+      };
+      info!("[START] align articles: {}", num);
+      let mut old_reader = Reader::from_reader(BufReader::new(File::open(old_file).await?));
+      let old_articles = read_article_texts(&mut old_reader).await?;
+      let mut new_reader = Reader::from_reader(BufReader::new(File::open(new_file).await?));
+      let new_articles = read_article_texts(&mut new_reader).await?;
+      let pairs = align_articles(&old_articles, &new_articles, align_threshold);
+      entries = apply_alignment(entries, num, &pairs);
+      info!("[END] align articles: {}", num);
+    }
+  }
+  for kind in [DiffKind::Added, DiffKind::Removed, DiffKind::Moved] {
+    let count = entries.iter().filter(|entry| entry.kind == kind).count();
+    info!("diff {:?}: {}", kind, count);
+  }
+  let mut output_file = File::create(output).await?;
+  let entries_json_str = serde_json::to_string(&entries)?;
+  output_file.write_all(entries_json_str.as_bytes()).await?;
+  output_file.flush().await?;
+  Ok(())
+}
+
+/// ファイルに書いた内容をflushし、`is_fsync`のときはディスクへの書き込みが終わるまで待つ
+async fn flush_file(file: &mut File, is_fsync: bool) -> Result<()> {
+  file.flush().await?;
+  if is_fsync {
+    file.sync_all().await?;
+  }
+  Ok(())
+}
+
+/// 開いた法令ファイルを検索しながら、見つかった箇所を`Grep`形式の行にしていく。
+/// 見つかった箇所は行にしたら捨てるので、返す検索結果の`matches`は空になる。
+/// 行は、ファイルを最後まで読めてから書き出せるように、検索結果と共に返す
+async fn stream_grep_law_file(
+  file: File,
+  search_words: &[String],
+  search_config: &SearchConfig,
+  read_options: &ReadOptions,
+  version: Option<&str>,
+  abbreviations: &Abbreviations,
+  number_style: NumberStyle,
+) -> Result<(LawParagraph, String)> {
+  let reader = Reader::from_reader(BufReader::with_capacity(read_options.buffer_size, file));
+  let mut stream = MatchStream::new(search_words, search_config, reader)?;
+  let mut grep_writer = None;
+  let mut match_count = 0;
+  let mut grep_lines = String::new();
+  while let Some(mut matches) = stream.next_matches().await? {
+    for m in matches.iter_mut() {
+      m.id = versioned_match_id(&m.id, version);
+    }
+    let grep_writer = grep_writer.get_or_insert_with(|| {
+      GrepStreamWriter::new(
+        stream.law_num(),
+        version,
+        abbreviations,
+        search_config.dedup != Dedup::None,
+        number_style,
+      )
+    });
+    match_count += matches.len();
+    grep_lines.push_str(&grep_writer.lines(&matches));
+  }
+  Span::current().record("matches", match_count);
+  Ok((stream.finish(), grep_lines))
+}
+
+/// 法令一つ分の見つかった条項を、`context`の範囲のテキストと共に`Site`形式のページの内容にする。
+/// 同じ範囲に見つかった条項が複数あっても、テキストは一度だけ載せる。
+/// 印を付ける範囲は、検索と同じ判定の仕方`matchers`で探す
+async fn site_law(
+  file_path: &Path,
+  law_paragraph: &LawParagraph,
+  abbreviations: &Abbreviations,
+  matchers: &Matchers,
+  context: ContextScope,
+  number_style: NumberStyle,
+  ruby: RubyStyle,
+) -> Result<SiteLaw> {
+  let mut targets: Vec<Chapter> = vec![];
+  for chapter in law_paragraph.chapter_data.iter() {
+    let target = context.target(chapter);
+    if !targets.contains(&target) {
+      targets.push(target);
+    }
+  }
+  let mut reader = Reader::from_reader(BufReader::new(File::open(file_path).await?));
+  let texts = hydrate_chapters_with(&targets, &mut reader, ruby).await?;
+  let chapters = targets
+    .iter()
+    .zip(texts)
+    .map(|(target, text)| {
+      let text = context.trim(&text, &law_paragraph.matched_words);
+      SiteChapter {
+        title: chapter_title_with(target, number_style),
+        marks: matchers.find_ranges(&text, &law_paragraph.matched_words),
+        text,
+      }
+    })
+    .collect();
+  Ok(SiteLaw {
+    num: law_paragraph.num.clone(),
+    version: law_paragraph.version.clone(),
+    name: abbreviations.display_name(&law_paragraph.num).to_string(),
+    matched_words: law_paragraph.matched_words.clone(),
+    match_count: law_paragraph.matches.len(),
+    chapters,
+  })
+}
+
+/// 法令一つ分の見つかった箇所を、その箇所を含むテキストと共にxlsxの行にする
+#[cfg(feature = "xlsx")]
+async fn xlsx_match_rows(
+  file_path: &Path,
+  law_paragraph: &LawParagraph,
+  abbreviations: &Abbreviations,
+  context: ContextScope,
+  search_words: &[String],
+  ruby: RubyStyle,
+) -> Result<Vec<XlsxMatchRow>> {
+  let targets = law_paragraph
+    .matches
+    .iter()
+    .map(|m| context.target(&m.chapter))
+    .collect::<Vec<_>>();
+  let mut reader = Reader::from_reader(BufReader::new(File::open(file_path).await?));
+  let texts = hydrate_chapters_with(&targets, &mut reader, ruby).await?;
+  let law_name = xlsx_law_name(law_paragraph, abbreviations);
+  let rows = law_paragraph
+    .matches
+    .iter()
+    .zip(texts)
+    .map(|(m, text)| XlsxMatchRow {
+      law_name: law_name.clone(),
+      law_num: law_paragraph.num.clone(),
+      citation: chapter_title(&m.chapter),
+      word: m.word.clone(),
+      snippet: context.trim(&text, search_words),
+      link: article_url(&law_paragraph.num, &m.chapter).unwrap_or_default(),
+      id: m.id.clone(),
+    })
+    .collect();
+  Ok(rows)
+}
+
+/// xlsxの法令名の列に書く名前。略称があれば略称、無ければ法令名、それも無ければ法令番号にする
+#[cfg(feature = "xlsx")]
+fn xlsx_law_name(law_paragraph: &LawParagraph, abbreviations: &Abbreviations) -> String {
+  abbreviations
+    .get(&law_paragraph.num)
+    .or(law_paragraph.title.as_deref())
+    .unwrap_or(&law_paragraph.num)
+    .to_string()
+}
+
+/// 処理した法令ファイルの記録を、マニフェストのファイルがあればそこに書き出す
+async fn write_manifest_entry(
+  manifest_file: &mut Option<File>,
+  entry: &ManifestEntry,
+) -> Result<()> {
+  if let Some(file) = manifest_file {
+    let entry_json_str = serde_json::to_string(entry)?;
+    file.write_all(entry_json_str.as_bytes()).await?;
+    file.write_all("\n".as_bytes()).await?;
+  }
+  Ok(())
 }
 
 async fn init_logger() -> Result<()> {
@@ -34,50 +1427,1003 @@ async fn init_logger() -> Result<()> {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-  let args = Args::parse();
+  let raw_args = std::env::args().collect::<Vec<_>>();
+  // ヘルプはclapで解析するときに書き出されるので、その前に言語を決める
+  set_lang(Lang::from_args(&raw_args).unwrap_or_else(Lang::from_env));
+  // ヘルプの表示や引数の誤りは、clapの表示のまま終了する
+  let mut args = match parse_args(raw_args).await {
+    Ok(args) => args,
+    Err(e) => match e.downcast::<clap::Error>() {
+      Ok(e) => e.exit(),
+      Err(e) => return Err(e),
+    },
+  };
+  if let Some(lang) = args.lang {
+    set_lang(lang);
+  }
 
   init_logger().await?;
+  if let Some(config) = &args.config {
+    info!("config: {config}");
+  }
+
+  if let Some(command) = args.command {
+    return match command {
+      Command::Hydrate {
+        results,
+        output,
+        work,
+        api,
+        http,
+      } => hydrate_command(&results, &output, work.as_deref(), api, http.to_config()).await,
+      Command::CheckUpdates {
+        since,
+        work,
+        output,
+        download,
+        http,
+      } => {
+        check_updates_command(
+          &since,
+          &work,
+          &output,
+          download.as_deref(),
+          http.to_config(),
+        )
+        .await
+      }
+      Command::Snapshot { work, output } => snapshot_command(&work, &output).await,
+      Command::Verify {
+        results,
+        work,
+        output,
+        sample,
+        search_words,
+        session_log,
+      } => {
+        verify_command(
+          &results,
+          &work,
+          output.as_deref(),
+          sample,
+          &search_words,
+          session_log.as_deref(),
+          args.seed,
+        )
+        .await
+      }
+      Command::SampleParagraphs {
+        work,
+        output,
+        sample,
+        concurrency,
+      } => {
+        // 種を省略しても、同じ作業ディレクトリからは同じ項を抽出する
+        let seed = args.seed.unwrap_or(0);
+        sample_paragraphs_command(&work, &output, sample, seed, concurrency).await
+      }
+      Command::Evaluate {
+        gold,
+        results,
+        output,
+      } => evaluate_command(&gold, &results, output.as_deref()).await,
+      Command::Diff {
+        old,
+        new,
+        output,
+        old_work,
+        new_work,
+        align_threshold,
+      } => {
+        let work_dirs = old_work.as_deref().zip(new_work.as_deref());
+        diff_command(&old, &new, &output, work_dirs, align_threshold).await
+      }
+      Command::Codes => {
+        println!("{}", serde_json::to_string_pretty(&code_catalog())?);
+        Ok(())
+      }
+      Command::Config {
+        command: ConfigCommand::Validate { file },
+      } => config_validate_command(&file).await,
+      Command::Config {
+        command: ConfigCommand::Schema,
+      } => {
+        let mut command = args_command();
+        command.build();
+        println!(
+          "{}",
+          serde_json::to_string_pretty(&config_schema(&command))?
+        );
+        Ok(())
+      }
+    };
+  }
+
+  let started_at = unix_time_now();
+  let session_started = Instant::now();
+
+  let (search_config, synonym_origins) = search_config_of(&mut args).await?;
+
+  // サブコマンドが無いときは`required = true`なので必ず値がある
+  let output = args.output.unwrap();
+  let work = args.work.unwrap();
+  let index_file = args.index_file.unwrap();
 
-  info!("[START] get law data: {:?}", &args.index_file);
-  let raw_data_lst = listup_law::get_law_from_index(&args.index_file).await?;
-  info!("[END] get law data: {:?}", &args.index_file);
+  info!("[START] get law data: {:?}", &index_file);
+  let raw_data_lst = listup_law::get_law_from_index(&index_file).await?;
+  info!("[END] get law data: {:?}", &index_file);
+
+  // `Site`形式では`--output`をディレクトリとし、その中の`index.html`に法令の一覧を書く
+  let mut output_file = if args.format == OutputFormat::Site {
+    create_dir_all(&output).await?;
+    File::create(Path::new(&output).join("index.html")).await?
+  } else {
+    File::create(&output).await?
+  };
+  let abbreviations = match &args.abbreviations {
+    Some(path) => Abbreviations::load(Path::new(path)).await?,
+    None => Abbreviations::default(),
+  };
 
-  let mut output_file = File::create(&args.output).await?;
   info!("[START] write json file");
-  output_file.write_all("[".as_bytes()).await?;
+  if args.format == OutputFormat::Json {
+    output_file.write_all("[".as_bytes()).await?;
+  }
 
-  let mut law_data_stream = tokio_stream::iter(raw_data_lst);
+  let work_dir_path = Path::new(&work);
+
+  let law_versions = match args.versions {
+    Versions::All => {
+      info!("[START] find law versions: {:?}", work_dir_path);
+      let law_versions = find_law_versions(work_dir_path).await?;
+      info!("[END] find law versions: {:?}", work_dir_path);
+      law_versions
+    }
+    Versions::Latest => HashMap::new(),
+  };
+  let mut law_file_lst = vec![];
+  for law_data in raw_data_lst {
+    let file_path = work_dir_path.join(law_data.file);
+    match law_id_of(&file_path).and_then(|law_id| law_versions.get(&law_id)) {
+      Some(version_paths) => law_file_lst.extend(
+        version_paths
+          .iter()
+          .map(|path| (path.clone(), version_of(path))),
+      ),
+      None if args.versions == Versions::All => {
+        let version = version_of(&file_path);
+        law_file_lst.push((file_path, version))
+      }
+      None => law_file_lst.push((file_path, None)),
+    }
+  }
+
+  // 同じ法令ファイルを指すものは、検索する前に見つける
+  let mut canonical_paths = HashSet::new();
+  let mut unique_law_file_lst = vec![];
+  for (file_path, version) in law_file_lst {
+    let canonical_path = canonicalize(&file_path)
+      .await
+      .unwrap_or_else(|_| file_path.clone());
+    if !canonical_paths.insert(canonical_path) {
+      let message = format!("duplicate law file: {}", file_path.display());
+      match args.on_duplicate {
+        OnDuplicate::Warn => report_warning(Code::DuplicateLaw, message.as_str()),
+        OnDuplicate::Skip => {
+          report_warning(Code::DuplicateLaw, format!("{message}: skipped"));
+          continue;
+        }
+        OnDuplicate::Error => return Err(Diagnostic::new(Code::DuplicateLaw, message).into()),
+      }
+    }
+    unique_law_file_lst.push((file_path, version));
+  }
+  let law_file_lst = unique_law_file_lst;
+
+  let law_file_paths = law_file_lst
+    .iter()
+    .map(|(path, _)| path.clone())
+    .collect::<Vec<_>>();
+  let mut law_data_stream = tokio_stream::iter(law_file_lst);
 
   let mut is_head = true;
 
-  let work_dir_path = Path::new(&args.work);
+  let mut stats =
+    search_article_with_word::stats::Stats::with_synonym_origins(synonym_origins.clone());
+  if let Some(snapshot_path) = &args.snapshot {
+    let snapshot_sha256 = read_snapshot_hash(Path::new(snapshot_path)).await?;
+    info!("snapshot: {}", snapshot_sha256);
+    stats.snapshot_sha256 = Some(snapshot_sha256);
+  }
 
-  while let Some(law_data) = law_data_stream.next().await {
-    let file_path = work_dir_path.join(law_data.file);
-    info!("[START] work file: {:?}", file_path);
-    let mut reader = Reader::from_reader(BufReader::new(File::open(&file_path).await?));
-    let chapter_data =
-      search_article_with_word::search_xml(&args.search_words, &mut reader).await?;
-    if !chapter_data.chapter_data.is_empty() {
-      let chapter_data_lst_json_str = serde_json::to_string(&chapter_data)?;
-      info!("[END] work file: {:?}", file_path);
-      info!("[START] data write: {:?}", file_path);
-      if is_head {
-        output_file.write_all("\n".as_bytes()).await?;
-        is_head = false;
+  if let Some(export_dir) = &args.export_text {
+    create_dir_all(export_dir).await?;
+  }
+  if let Some(export_dir) = &args.export_speech {
+    create_dir_all(export_dir).await?;
+  }
+  let mut export_jsonl_file = match &args.export_text_jsonl {
+    Some(path) => Some(File::create(path).await?),
+    None => None,
+  };
+  let mut export_annotation_file = match &args.export_annotation {
+    Some(path) => Some(File::create(path).await?),
+    None => None,
+  };
+
+  if let Some(path) = &args.error_report {
+    diagnostic::set_report_file(std::fs::File::create(path)?);
+  }
+  let mut manifest_file = match &args.manifest {
+    Some(path) => Some(File::create(path).await?),
+    None => None,
+  };
+  let mut citation_list_file = match &args.citation_list {
+    Some(path) => Some(File::create(path).await?),
+    None => None,
+  };
+  let mut written_citations = HashSet::new();
+  let mut bib_entries = vec![];
+  let mut bib_law_nums = HashSet::new();
+  #[cfg(feature = "postgres")]
+  let mut postgres_sink = match (args.format, &args.dsn) {
+    (OutputFormat::Postgres, Some(dsn)) => {
+      Some(PostgresSink::connect(dsn, args.postgres_batch_size).await?)
+    }
+    _ => None,
+  };
+  let mut site_laws = vec![];
+  #[cfg(feature = "xlsx")]
+  let mut xlsx_rows = vec![];
+  #[cfg(feature = "xlsx")]
+  let mut xlsx_summary = vec![];
+  #[cfg(feature = "nats")]
+  let nats_sink = match (&args.nats_url, &args.nats_subject) {
+    (Some(url), Some(subject)) => Some(NatsSink::connect(url, subject).await?),
+    _ => None,
+  };
+
+  let mut qa_reservoir = match args.qa_sample.first() {
+    Some(n) => Some(Reservoir::new(n.parse::<usize>()?)),
+    None => None,
+  };
+  let mut rng = seeded_rng(args.seed);
+
+  let mut cooccurrence_counter = CooccurrenceCounter::new(args.cooccurrence_ngram);
+  // 検索語に加える語の候補も、検索語と同じ文に現れるn-gramの回数から選ぶ
+  let is_counting_cooccurrence = args.cooccurrence.is_some() || args.suggest_terms.is_some();
+  // 検索語を含む文かどうかや、`Site`形式のページで印を付ける範囲は、検索と同じ判定の仕方で調べる
+  let search_matchers = Matchers::shared(&args.search_words, &search_config)?;
+  let mut memory_guard = MemoryGuard::new(args.max_memory.map(|size| size.0));
+  // 報告書や`Site`形式などのために、法令ごとの見つかった箇所を最後まで持っておくかどうか
+  let is_collecting_laws = args.report_template.is_some() || args.format == OutputFormat::Site;
+  #[cfg(feature = "xlsx")]
+  let is_collecting_laws = is_collecting_laws || args.format == OutputFormat::Xlsx;
+  // 最後まで持っておく見つかった箇所とテキストのおおよそのバイト数
+  let mut collected_bytes = 0;
+  let read_options = ReadOptions {
+    buffer_size: args.read_buffer_size.0,
+    in_memory_max_size: args.in_memory_max_size.map(|size| size.0),
+    is_mmap: args.mmap,
+    available_memory: available_memory(),
+  };
+
+  // 見つかった箇所を全て使う出力が無ければ、`Grep`形式では法令ファイルを一つ読み終えるごとに書き出す。
+  // そのときは一つずつバッファを通して読むので、読み方や同時に検索する数を指定したときはそうしない
+  let is_streaming = args.format == OutputFormat::Grep
+    && args.concurrency <= 1
+    && !args.mmap
+    && args.in_memory_max_size.is_none()
+    && qa_reservoir.is_none()
+    && args.report_template.is_none()
+    && args.export_text.is_none()
+    && args.export_speech.is_none()
+    && export_jsonl_file.is_none()
+    && export_annotation_file.is_none()
+    && !is_counting_cooccurrence;
+  #[cfg(feature = "plugin")]
+  let mut plugins = args
+    .plugin
+    .iter()
+    .map(|path| Plugin::load(Path::new(path)))
+    .collect::<Result<Vec<_>>>()?;
+  #[cfg(feature = "plugin")]
+  let is_streaming = is_streaming && plugins.is_empty();
+  #[cfg(feature = "filter-expr")]
+  let filter_expr = args
+    .filter_expr
+    .as_deref()
+    .map(FilterExpr::compile)
+    .transpose()?;
+  #[cfg(feature = "filter-expr")]
+  let is_streaming = is_streaming && filter_expr.is_none();
+  #[cfg(feature = "relevance")]
+  let mut relevance_model = match (&args.relevance_model, &args.relevance_query) {
+    (Some(model_dir), Some(query)) => Some(RelevanceModel::load(Path::new(model_dir), query)?),
+    _ => None,
+  };
+  #[cfg(feature = "relevance")]
+  let mut relevance_scores = vec![];
+  #[cfg(feature = "relevance")]
+  let is_streaming = is_streaming && relevance_model.is_none();
+  let mut search_results = if is_streaming {
+    info!("streaming grep output: reading law files one at a time through a buffer");
+    None
+  } else {
+    let config = SearchManyConfig {
+      search_words: args.search_words.clone(),
+      search_config: search_config.clone(),
+      read_options,
+      concurrency: args.concurrency,
+      memory_guard: memory_guard.clone(),
+    };
+    Some(Box::pin(search_many(law_file_paths, config)))
+  };
+
+  let mut unflushed_law_count = 0;
+  let mut report_laws = vec![];
+  let mut seen_laws = HashSet::new();
+
+  while let Some((file_path, version)) = law_data_stream.next().await {
+    if matches!(args.flush_every, Some(n) if unflushed_law_count >= n) {
+      flush_file(&mut output_file, args.fsync).await?;
+      if let Some(jsonl_file) = &mut export_jsonl_file {
+        flush_file(jsonl_file, args.fsync).await?;
+      }
+      if let Some(annotation_file) = &mut export_annotation_file {
+        flush_file(annotation_file, args.fsync).await?;
+      }
+      if let Some(manifest_file) = &mut manifest_file {
+        flush_file(manifest_file, args.fsync).await?;
+      }
+      unflushed_law_count = 0;
+    }
+    unflushed_law_count += 1;
+    let span = info_span!(
+      "law_file",
+      file = %file_path.display(),
+      law_num = field::Empty,
+      matches = field::Empty
+    );
+    async {
+      debug!("[START] work file: {:?}", file_path);
+      let started = Instant::now();
+      // 少しずつ検索したときの`Grep`形式の行。ファイルを最後まで読めて、重複でもなかったときに書き出す
+      let mut streamed_grep_lines = None;
+      let search_result = match &mut search_results {
+        Some(search_results) => match search_results.next().await {
+          Some(search_result) => search_result,
+          None => Err(anyhow!("検索結果が途中で終わりました")),
+        },
+        None => match open_law_file(&file_path).await {
+          Ok(file) => {
+            let positions_only;
+            let search_config = if memory_guard.is_exceeded() {
+              positions_only = search_config.positions_only();
+              &positions_only
+            } else {
+              &search_config
+            };
+            stream_grep_law_file(
+              file,
+              &args.search_words,
+              search_config,
+              &read_options,
+              version.as_deref(),
+              &abbreviations,
+              args.number_style,
+            )
+            .await
+            .map(|(chapter_data, grep_lines)| {
+              streamed_grep_lines = Some(grep_lines);
+              chapter_data
+            })
+          }
+          Err(diagnostic) => Err(diagnostic.into()),
+        },
+      };
+      let chapter_data = match search_result {
+        Ok(chapter_data) => {
+          let mut chapter_data = LawParagraph {
+            snapshot_sha256: stats.snapshot_sha256.clone(),
+            ..chapter_data
+          };
+          chapter_data.set_version(version);
+          chapter_data
+        }
+        Err(e) => {
+          let diagnostic = Diagnostic::from_error(Code::XmlParse, &e).with_file(&file_path);
+          diagnostic::report(&diagnostic);
+          stats.skipped_file_count += 1;
+          let status = match diagnostic.code {
+            Code::FileOpen | Code::EmptyFile | Code::NotLawXml => ManifestStatus::Skipped,
+            _ => ManifestStatus::Error,
+          };
+          let entry = ManifestEntry {
+            file: file_path.clone(),
+            law_num: None,
+            version,
+            status,
+            duration_ms: started.elapsed().as_millis() as u64,
+            hit_count: 0,
+            code: Some(diagnostic.code),
+          };
+          write_manifest_entry(&mut manifest_file, &entry).await?;
+          return Ok(());
+        }
+      };
+      #[cfg(feature = "plugin")]
+      let chapter_data = if plugins.is_empty() || chapter_data.matches.is_empty() {
+        chapter_data
       } else {
-        output_file.write_all(",\n".as_bytes()).await?;
+        let targets = chapter_data
+          .matches
+          .iter()
+          .map(|m| m.chapter.paragraph_level())
+          .collect::<Vec<_>>();
+        let mut reader = Reader::from_reader(BufReader::new(File::open(&file_path).await?));
+        let texts = hydrate_chapters_with(&targets, &mut reader, RubyStyle::Strip).await?;
+        apply_plugins(&mut plugins, chapter_data, &texts)?
+      };
+      let mut chapter_data = chapter_data;
+      // 上限を超える前に検索し始めたファイルでも、見つかった箇所の位置だけを残す
+      if memory_guard.is_exceeded() {
+        chapter_data.drop_snippets();
       }
-      output_file
-        .write_all(chapter_data_lst_json_str.as_bytes())
-        .await?;
+      mark_synonyms(&mut chapter_data, &synonym_origins);
+      #[cfg(feature = "filter-expr")]
+      let mut chapter_data = match &filter_expr {
+        Some(filter) => apply_filter(filter, chapter_data)?,
+        None => chapter_data,
+      };
+      chapter_data.set_schema_version(args.schema_version);
+      Span::current().record("law_num", chapter_data.num.as_str());
+      // 別のファイルでも、法令番号と版が同じなら同じ法令とみなす。
+      // 少しずつ検索したときも、`Grep`形式の行はこれを確かめた後で書き出す
+      if !seen_laws.insert((chapter_data.num.clone(), chapter_data.version.clone())) {
+        let message = format!(
+          "duplicate law: {} ({})",
+          chapter_data.num,
+          file_path.display()
+        );
+        match args.on_duplicate {
+          OnDuplicate::Warn => report_warning(Code::DuplicateLaw, message.as_str()),
+          OnDuplicate::Skip => {
+            report_warning(Code::DuplicateLaw, format!("{message}: skipped"));
+            let entry = ManifestEntry {
+              file: file_path.clone(),
+              law_num: Some(chapter_data.num.clone()),
+              version: chapter_data.version.clone(),
+              status: ManifestStatus::Skipped,
+              duration_ms: started.elapsed().as_millis() as u64,
+              hit_count: 0,
+              code: Some(Code::DuplicateLaw),
+            };
+            write_manifest_entry(&mut manifest_file, &entry).await?;
+            return Ok(());
+          }
+          OnDuplicate::Error => {
+            return Err(Diagnostic::new(Code::DuplicateLaw, message).into());
+          }
+        }
+      }
+      #[cfg(feature = "relevance")]
+      if let (Some(model), false) = (&mut relevance_model, chapter_data.matches.is_empty()) {
+        let targets = chapter_data
+          .matches
+          .iter()
+          .map(|m| m.chapter.paragraph_level())
+          .collect::<Vec<_>>();
+        let mut reader = Reader::from_reader(BufReader::new(File::open(&file_path).await?));
+        let texts = hydrate_chapters_with(&targets, &mut reader, RubyStyle::Strip).await?;
+        for (m, text) in chapter_data.matches.iter().zip(texts) {
+          let text = m.snippet.clone().unwrap_or(text);
+          let score = model.score(&text)?;
+          collected_bytes += text.len();
+          relevance_scores.push(RelevanceScore {
+            id: m.id.clone(),
+            num: chapter_data.num.clone(),
+            chapter: m.chapter.clone(),
+            word: m.word.clone(),
+            score,
+            text,
+          });
+        }
+      }
+      if !is_streaming {
+        Span::current().record("matches", chapter_data.matches.len());
+      }
+      stats.add(&chapter_data);
+      if let Some(reservoir) = &mut qa_reservoir {
+        for chapter in chapter_data.chapter_data.iter() {
+          reservoir.add(
+            (
+              file_path.clone(),
+              chapter_data.num.clone(),
+              chapter.clone(),
+              chapter_data.match_ids_at(chapter),
+            ),
+            &mut rng,
+          );
+        }
+      }
+      if !chapter_data.chapter_data.is_empty() && args.report_template.is_some() {
+        report_laws.push(ReportLaw::new(
+          &chapter_data,
+          &abbreviations,
+          args.number_style,
+        ));
+      }
+      if !chapter_data.chapter_data.is_empty() || args.include_empty {
+        debug!("[END] work file: {:?}", file_path);
+        debug!("[START] data write: {:?}", file_path);
+        match args.format {
+          OutputFormat::Json => {
+            let chapter_data_lst_json_str = serde_json::to_string(&chapter_data)?;
+            if is_head {
+              output_file.write_all("\n".as_bytes()).await?;
+              is_head = false;
+            } else {
+              output_file.write_all(",\n".as_bytes()).await?;
+            }
+            output_file
+              .write_all(chapter_data_lst_json_str.as_bytes())
+              .await?;
+          }
+          OutputFormat::Pretty => {
+            if is_head {
+              is_head = false;
+            } else {
+              output_file.write_all("\n".as_bytes()).await?;
+            }
+            let pretty_str = to_pretty(&chapter_data, &abbreviations, args.number_style);
+            output_file.write_all(pretty_str.as_bytes()).await?;
+          }
+          OutputFormat::Grep => {
+            let grep_str = match streamed_grep_lines.take() {
+              Some(grep_lines) => grep_lines,
+              None => to_grep(&chapter_data, &abbreviations, args.number_style),
+            };
+            output_file.write_all(grep_str.as_bytes()).await?;
+          }
+          OutputFormat::Lawtext => {
+            if is_head {
+              is_head = false;
+            } else {
+              output_file.write_all("\n".as_bytes()).await?;
+            }
+            let lawtext_str = to_lawtext(&chapter_data, &abbreviations);
+            output_file.write_all(lawtext_str.as_bytes()).await?;
+          }
+          OutputFormat::Site => {
+            let site_law = site_law(
+              &file_path,
+              &chapter_data,
+              &abbreviations,
+              &search_matchers,
+              args.context,
+              args.number_style,
+              ruby_style(&args.furigana, FuriganaTarget::Site),
+            )
+            .await?;
+            // ページのテキストも、全ての法令を読み終えるまで持っておく
+            collected_bytes += site_law
+              .chapters
+              .iter()
+              .map(|chapter| chapter.text.len())
+              .sum::<usize>();
+            site_laws.push(site_law);
+          }
+          #[cfg(feature = "xlsx")]
+          OutputFormat::Xlsx => {
+            xlsx_rows.extend(
+              xlsx_match_rows(
+                &file_path,
+                &chapter_data,
+                &abbreviations,
+                args.context,
+                &args.search_words,
+                ruby_style(&args.furigana, FuriganaTarget::Xlsx),
+              )
+              .await?,
+            );
+            xlsx_summary.push(XlsxSummaryRow {
+              law_name: xlsx_law_name(&chapter_data, &abbreviations),
+              law_num: chapter_data.num.clone(),
+              matched_words: chapter_data.matched_words.clone(),
+              match_count: chapter_data.matches.len(),
+            });
+          }
+          #[cfg(feature = "postgres")]
+          OutputFormat::Postgres => {
+            if let Some(postgres_sink) = &mut postgres_sink {
+              postgres_sink.push(&chapter_data).await?;
+            }
+          }
+        }
+        #[cfg(feature = "nats")]
+        if let Some(nats_sink) = &nats_sink {
+          nats_sink.publish(&chapter_data).await?;
+        }
+      }
+      if is_collecting_laws && !chapter_data.chapter_data.is_empty() {
+        collected_bytes += chapter_data.approx_bytes();
+      }
+      memory_guard.check(cooccurrence_counter.approx_bytes() + collected_bytes);
+      if args.bibliography.is_some()
+        && !chapter_data.chapter_data.is_empty()
+        && bib_law_nums.insert(chapter_data.num.clone())
+      {
+        if let Some(law_id) = law_id_of(&file_path) {
+          bib_entries.push(BibEntry::new(&law_id, &chapter_data));
+        }
+      }
+      if let Some(citation_list_file) = &mut citation_list_file {
+        for chapter in chapter_data.chapter_data.iter() {
+          let citation = law_citation(chapter_data.title.as_deref(), &chapter_data.num, chapter);
+          if written_citations.insert(citation.clone()) {
+            citation_list_file
+              .write_all(format!("{citation}\n").as_bytes())
+              .await?;
+          }
+        }
+      }
+      if !chapter_data.chapter_data.is_empty()
+        && (args.export_text.is_some()
+          || export_jsonl_file.is_some()
+          || export_annotation_file.is_some()
+          || is_counting_cooccurrence)
+        && memory_guard.check(cooccurrence_counter.approx_bytes() + collected_bytes)
+      {
+        debug!("[START] export text: {:?}", file_path);
+        let mut paragraphs = chapter_data
+          .chapter_data
+          .iter()
+          .map(|chapter| args.context.target(chapter))
+          .collect::<Vec<_>>();
+        paragraphs.sort();
+        paragraphs.dedup();
+        // 書き出し先ごとに読み仮名の扱いが違うときは、扱いごとにテキストを取り出す
+        let mut ruby_styles = vec![];
+        if args.export_text.is_some() {
+          ruby_styles.push(ruby_style(&args.furigana, FuriganaTarget::ExportText));
+        }
+        if export_jsonl_file.is_some() {
+          ruby_styles.push(ruby_style(&args.furigana, FuriganaTarget::ExportTextJsonl));
+        }
+        if export_annotation_file.is_some() {
+          ruby_styles.push(ruby_style(&args.furigana, FuriganaTarget::ExportAnnotation));
+        }
+        if is_counting_cooccurrence {
+          ruby_styles.push(RubyStyle::Strip);
+        }
+        ruby_styles.sort();
+        ruby_styles.dedup();
+        let mut texts_by_style = vec![];
+        for ruby in ruby_styles {
+          let mut reader = Reader::from_reader(BufReader::with_capacity(
+            read_options.buffer_size,
+            File::open(&file_path).await?,
+          ));
+          let texts = hydrate_chapters_with(&paragraphs, &mut reader, ruby).await?;
+          texts_by_style.push((ruby, texts));
+        }
+        let used_bytes = cooccurrence_counter.approx_bytes()
+          + collected_bytes
+          + texts_by_style
+            .iter()
+            .flat_map(|(_, texts)| texts.iter())
+            .map(|text| text.len())
+            .sum::<usize>();
+        if !memory_guard.check(used_bytes) {
+          texts_by_style.clear();
+        }
+        let chapter_text_of = |ruby: RubyStyle, k: usize| {
+          texts_by_style
+            .iter()
+            .find(|(style, _)| *style == ruby)
+            .map(|(_, texts)| ChapterText {
+              num: chapter_data.num.clone(),
+              match_ids: chapter_data.match_ids_within(&paragraphs[k]),
+              chapter: paragraphs[k].clone(),
+              text: args.context.trim(&texts[k], &args.search_words),
+            })
+        };
+        let file_stem = file_path
+          .file_stem()
+          .map(|stem| stem.to_string_lossy().to_string())
+          .unwrap_or_default();
+        let mut used_names = HashSet::new();
+        for k in 0..paragraphs.len() {
+          if let (Some(export_dir), Some(chapter_text)) = (
+            &args.export_text,
+            chapter_text_of(ruby_style(&args.furigana, FuriganaTarget::ExportText), k),
+          ) {
+            let file_name = export_file_name(&file_stem, &chapter_text.chapter, &mut used_names);
+            let mut text_file = File::create(Path::new(export_dir).join(file_name)).await?;
+            text_file.write_all(chapter_text.text.as_bytes()).await?;
+            text_file.flush().await?;
+          }
+          if let (Some(jsonl_file), Some(chapter_text)) = (
+            &mut export_jsonl_file,
+            chapter_text_of(
+              ruby_style(&args.furigana, FuriganaTarget::ExportTextJsonl),
+              k,
+            ),
+          ) {
+            let chapter_text_json_str = serde_json::to_string(&chapter_text)?;
+            jsonl_file
+              .write_all(chapter_text_json_str.as_bytes())
+              .await?;
+            jsonl_file.write_all("\n".as_bytes()).await?;
+          }
+          if let (Some(annotation_file), Some(chapter_text)) = (
+            &mut export_annotation_file,
+            chapter_text_of(
+              ruby_style(&args.furigana, FuriganaTarget::ExportAnnotation),
+              k,
+            ),
+          ) {
+            let spans = find_spans(&chapter_text.text, &args.search_words);
+            let annotation = to_annotation_json(&chapter_text, &spans, args.annotation_format);
+            let annotation_json_str = serde_json::to_string(&annotation)?;
+            annotation_file
+              .write_all(annotation_json_str.as_bytes())
+              .await?;
+            annotation_file.write_all("\n".as_bytes()).await?;
+          }
+          if let (true, Some(chapter_text)) = (
+            is_counting_cooccurrence,
+            chapter_text_of(RubyStyle::Strip, k),
+          ) {
+            cooccurrence_counter.add_text(
+              &chapter_text.text,
+              &args.search_words,
+              &search_matchers.word_matchers,
+            );
+          }
+        }
+        debug!("[END] export text: {:?}", file_path);
+      }
+      if let (false, Some(export_dir)) = (chapter_data.chapter_data.is_empty(), &args.export_speech)
+      {
+        let mut articles = chapter_data
+          .chapter_data
+          .iter()
+          .map(|chapter| ContextScope::Article.target(chapter))
+          .collect::<Vec<_>>();
+        articles.sort();
+        articles.dedup();
+        let mut reader = Reader::from_reader(BufReader::with_capacity(
+          read_options.buffer_size,
+          File::open(&file_path).await?,
+        ));
+        let texts = hydrate_chapters(&articles, &mut reader).await?;
+        let file_stem = file_path
+          .file_stem()
+          .map(|stem| stem.to_string_lossy().to_string())
+          .unwrap_or_default();
+        let mut used_names = HashSet::new();
+        for (chapter, text) in articles.iter().zip(texts) {
+          let file_name = export_file_name(&file_stem, chapter, &mut used_names);
+          let mut speech_file = File::create(Path::new(export_dir).join(file_name)).await?;
+          speech_file
+            .write_all(to_speech_text(&text).as_bytes())
+            .await?;
+          speech_file.flush().await?;
+        }
+      }
+      debug!("[END] data write: {:?}", file_path);
+      let entry = ManifestEntry {
+        file: file_path.clone(),
+        law_num: Some(chapter_data.num.clone()),
+        version: chapter_data.version.clone(),
+        // 見つかった箇所があっても、条件に合わずに全て捨てたときは`Filtered`にする
+        status: if chapter_data.is_filtered && chapter_data.chapter_data.is_empty() {
+          ManifestStatus::Filtered
+        } else {
+          ManifestStatus::Ok
+        },
+        duration_ms: started.elapsed().as_millis() as u64,
+        hit_count: chapter_data.chapter_data.len(),
+        code: None,
+      };
+      write_manifest_entry(&mut manifest_file, &entry).await?;
+      Ok::<(), anyhow::Error>(())
     }
-    info!("[END] data write: {:?}", file_path);
+    .instrument(span)
+    .await?;
   }
 
-  output_file.write_all("\n]".as_bytes()).await?;
+  if args.format == OutputFormat::Json {
+    output_file.write_all("\n]".as_bytes()).await?;
+  }
+  if args.format == OutputFormat::Site {
+    info!("[START] write site: {:?}", output);
+    for site_law in site_laws.iter() {
+      let page = render_law_page(site_law);
+      let mut page_file = File::create(Path::new(&output).join(site_law.file_name())).await?;
+      page_file.write_all(page.as_bytes()).await?;
+      flush_file(&mut page_file, args.fsync).await?;
+    }
+    let index = render_site_index(
+      &site_laws,
+      &args.search_words,
+      stats.snapshot_sha256.as_deref(),
+    );
+    output_file.write_all(index.as_bytes()).await?;
+    info!("[END] write site: {:?}", output);
+  }
   info!("[END write json file");
-  output_file.flush().await?;
+  flush_file(&mut output_file, args.fsync).await?;
+
+  if let Some(jsonl_file) = &mut export_jsonl_file {
+    flush_file(jsonl_file, args.fsync).await?;
+  }
+  if let Some(annotation_file) = &mut export_annotation_file {
+    flush_file(annotation_file, args.fsync).await?;
+  }
+  if let Some(manifest_file) = &mut manifest_file {
+    flush_file(manifest_file, args.fsync).await?;
+  }
+  if let Some(citation_list_file) = &mut citation_list_file {
+    flush_file(citation_list_file, args.fsync).await?;
+  }
+  #[cfg(feature = "nats")]
+  if let Some(nats_sink) = &nats_sink {
+    nats_sink.flush().await?;
+  }
+  #[cfg(feature = "postgres")]
+  if let Some(postgres_sink) = &mut postgres_sink {
+    postgres_sink.flush().await?;
+  }
+  #[cfg(feature = "xlsx")]
+  if args.format == OutputFormat::Xlsx {
+    info!("[START] write xlsx file: {:?}", output);
+    write_xlsx(Path::new(&output), &xlsx_rows, &xlsx_summary)?;
+    info!("[END] write xlsx file: {:?}", output);
+  }
+
+  if let (Some(reservoir), Some(qa_path)) = (qa_reservoir, args.qa_sample.get(1)) {
+    info!("[START] write qa sample: {:?}", qa_path);
+    info!("qa sample: {} matches seen", reservoir.seen());
+    let sampled_matches = hydrate_samples(
+      reservoir.into_vec(),
+      &args.search_words,
+      args.context,
+      ruby_style(&args.furigana, FuriganaTarget::QaSample),
+    )
+    .await?;
+    let mut qa_file = File::create(qa_path).await?;
+    for sampled_match in sampled_matches.iter() {
+      let sampled_match_json_str = serde_json::to_string(sampled_match)?;
+      qa_file.write_all(sampled_match_json_str.as_bytes()).await?;
+      qa_file.write_all("\n".as_bytes()).await?;
+    }
+    flush_file(&mut qa_file, args.fsync).await?;
+    info!("[END] write qa sample: {:?}", qa_path);
+  }
+
+  #[cfg(feature = "relevance")]
+  if let Some(relevance_path) = &args.relevance_output {
+    info!("[START] write relevance file: {:?}", relevance_path);
+    sort_by_score(&mut relevance_scores);
+    let mut relevance_file = File::create(relevance_path).await?;
+    for relevance_score in relevance_scores.iter() {
+      let relevance_score_json_str = serde_json::to_string(relevance_score)?;
+      relevance_file
+        .write_all(relevance_score_json_str.as_bytes())
+        .await?;
+      relevance_file.write_all("\n".as_bytes()).await?;
+    }
+    flush_file(&mut relevance_file, args.fsync).await?;
+    info!("[END] write relevance file: {:?}", relevance_path);
+  }
+  if let Some(top) = args.suggest_terms {
+    stats.suggested_terms = cooccurrence_counter.suggestions(&args.search_words, top);
+  }
+  if let Some(cooccurrence_path) = &args.cooccurrence {
+    info!("[START] write cooccurrence file: {:?}", cooccurrence_path);
+    let mut cooccurrence_file = File::create(cooccurrence_path).await?;
+    let cooccurrence_json_str =
+      serde_json::to_string(&cooccurrence_counter.top(args.cooccurrence_top))?;
+    cooccurrence_file
+      .write_all(cooccurrence_json_str.as_bytes())
+      .await?;
+    flush_file(&mut cooccurrence_file, args.fsync).await?;
+    info!("[END] write cooccurrence file: {:?}", cooccurrence_path);
+  }
+
+  if let (Some(template_path), Some(report_path)) = (&args.report_template, &args.report_output) {
+    info!("[START] write report: {:?}", report_path);
+    let template = read_to_string(template_path).await?;
+    let report_data = ReportData {
+      search_words: args.search_words.clone(),
+      laws: report_laws,
+      stats: stats.clone(),
+    };
+    let report = render_report(&template, &report_data)?;
+    let mut report_file = File::create(report_path).await?;
+    report_file.write_all(report.as_bytes()).await?;
+    flush_file(&mut report_file, args.fsync).await?;
+    info!("[END] write report: {:?}", report_path);
+    if let Some(index_path) = &args.report_index {
+      // 索引は報告書と同じディレクトリに置くものとする
+      let report_href = Path::new(report_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+      let index = render_report_index(&report_data, &report_href);
+      let mut index_file = File::create(index_path).await?;
+      index_file.write_all(index.as_bytes()).await?;
+      flush_file(&mut index_file, args.fsync).await?;
+    }
+  }
+
+  if let Some(bibliography_path) = &args.bibliography {
+    info!("[START] write bibliography: {:?}", bibliography_path);
+    let bibliography = render_bibliography(&bib_entries, args.bibliography_format)?;
+    let mut bibliography_file = File::create(bibliography_path).await?;
+    bibliography_file.write_all(bibliography.as_bytes()).await?;
+    flush_file(&mut bibliography_file, args.fsync).await?;
+    info!("[END] write bibliography: {:?}", bibliography_path);
+  }
+
+  if let Some(stats_path) = &args.stats {
+    info!("[START] write stats file: {:?}", stats_path);
+    let mut stats_file = File::create(stats_path).await?;
+    let stats_json_str = serde_json::to_string(&stats)?;
+    stats_file.write_all(stats_json_str.as_bytes()).await?;
+    flush_file(&mut stats_file, args.fsync).await?;
+    info!("[END] write stats file: {:?}", stats_path);
+  }
+
+  if let Some(session_log_path) = &args.session_log {
+    info!("[START] write session log: {:?}", session_log_path);
+    let mut output_paths = vec![output.as_str()];
+    for path in [
+      &args.stats,
+      &args.manifest,
+      &args.error_report,
+      &args.export_text_jsonl,
+      &args.export_annotation,
+      &args.citation_list,
+      &args.bibliography,
+      &args.report_output,
+      &args.cooccurrence,
+      &args.export_text,
+      &args.export_speech,
+    ]
+    .into_iter()
+    .flatten()
+    {
+      output_paths.push(path.as_str());
+    }
+    if let Some(qa_path) = args.qa_sample.get(1) {
+      output_paths.push(qa_path.as_str());
+    }
+    let session_log = SessionLog {
+      tool_version: env!("CARGO_PKG_VERSION").to_string(),
+      arguments: redact_arguments(absolute_path_arguments(std::env::args()).into_iter()),
+      search_words: args.search_words.clone(),
+      search_config: search_config.clone(),
+      read_options,
+      index_file: index_file.clone(),
+      index_file_sha256: file_sha256(&index_file).await?,
+      snapshot_sha256: stats.snapshot_sha256.clone(),
+      started_at,
+      duration_ms: session_started.elapsed().as_millis() as u64,
+      stats: stats.clone(),
+      outputs: hash_outputs(&output_paths).await?,
+    };
+    let mut session_log_file = File::create(session_log_path).await?;
+    let session_log_json_str = serde_json::to_string_pretty(&session_log)?;
+    session_log_file
+      .write_all(session_log_json_str.as_bytes())
+      .await?;
+    flush_file(&mut session_log_file, args.fsync).await?;
+    info!("[END] write session log: {:?}", session_log_path);
+  }
 
   Ok(())
 }
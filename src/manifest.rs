@@ -0,0 +1,42 @@
+//! 処理した法令ファイルごとの記録（マニフェスト）
+//!
+//! 大量の法令ファイルを処理したときに、どのファイルをどう処理したのかを後から確かめられるよう、
+//! 検索結果とは別に、ファイルごとの状態・かかった時間・単語が含まれていた条項の数を記録する。
+
+use crate::diagnostic::Code;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// 法令ファイルをどう処理したか
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestStatus {
+  /// 検索できた
+  Ok,
+  /// 法令XMLの解析に失敗した
+  Error,
+  /// 開けなかったり空だったりして検索しなかった
+  Skipped,
+  /// 見つかった箇所はあったが、`--min-hits`や`--filter-expr`の条件に合わないので全て捨てた
+  Filtered,
+}
+
+/// 法令ファイル一つ分の記録
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ManifestEntry {
+  pub file: PathBuf,
+  /// 法令番号。検索できなかったときは無い
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub law_num: Option<String>,
+  /// 法令の版
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub version: Option<String>,
+  pub status: ManifestStatus,
+  /// 処理にかかった時間（ミリ秒）
+  pub duration_ms: u64,
+  /// 単語が含まれていた条項の数
+  pub hit_count: usize,
+  /// 検索できなかったときの、警告・エラーのコード
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub code: Option<Code>,
+}
@@ -0,0 +1,437 @@
+//! 検索語がテキストに含まれているかどうかの判定
+
+use crate::{
+  fold::{original_range, Folding},
+  search_config::SearchConfig,
+};
+#[cfg(feature = "morpheme")]
+use crate::{i18n::Message, morpheme::MorphemeTokenizer, search_config::MatchMode};
+use aho_corasick::AhoCorasick;
+use anyhow::{anyhow, Result};
+use memchr::memmem::Finder;
+use regex::Regex;
+use std::sync::{Arc, Mutex};
+
+/// 作った判定の仕方のうち、使い回すために持っておく数
+const SHARED_MATCHERS_CAPACITY: usize = 4;
+
+/// これまでに作った判定の仕方。新しく作ったものほど後ろに置く
+static SHARED_MATCHERS: Mutex<Vec<Arc<Matchers>>> = Mutex::new(Vec::new());
+
+/// 検索語一つ分の判定の仕方
+#[derive(Debug, Clone)]
+pub enum WordMatcher<'a> {
+  /// 検索語をそのまま部分文字列として探す
+  Substring(Finder<'a>),
+  /// 検索語を正規表現として探す
+  Regex(Regex),
+  /// 検索語との編集距離が`distance`以下の部分文字列を探す
+  Fuzzy { word: Vec<char>, distance: usize },
+  /// 表記の違いをそろえたテキストから、そろえた検索語を探す
+  Folded(Box<WordMatcher<'static>>, Folding),
+  /// 検索語を部分文字列として探し、形態素の境界で始まって終わるものだけを残す
+  #[cfg(feature = "morpheme")]
+  Morpheme(Finder<'a>, MorphemeTokenizer),
+}
+
+impl<'a> WordMatcher<'a> {
+  /// 検索の仕方の設定に従って、検索語`word`の判定の仕方を作る。正規表現として解釈できないときはエラーを返す
+  pub fn new(word: &'a str, config: &SearchConfig) -> Result<Self> {
+    if !config.folding.is_empty() {
+      // ワイルドカードの`＊`・`？`は半角にしない
+      let folded = config
+        .folding
+        .fold_word(word, |c| config.is_wildcard && (c == '＊' || c == '？'));
+      let inner_config = SearchConfig {
+        folding: Folding::default(),
+        ..config.clone()
+      };
+      let inner = WordMatcher::new(&folded, &inner_config)?.into_owned();
+      return Ok(WordMatcher::Folded(Box::new(inner), config.folding));
+    }
+    if config.is_regex {
+      let regex =
+        Regex::new(word).map_err(|e| anyhow!("正規表現として解釈できません: {word}: {e}"))?;
+      Ok(WordMatcher::Regex(regex))
+    } else if config.is_wildcard && word.contains(|c| c == '＊' || c == '？') {
+      Ok(WordMatcher::Regex(wildcard_to_regex(word)?))
+    } else if let Some(distance) = config.fuzzy {
+      let word = normalize(word)
+        .into_iter()
+        .map(|(_, c)| c)
+        .collect::<Vec<_>>();
+      if distance >= word.len() {
+        return Err(anyhow!(
+          "編集距離の上限（{distance}）が検索語の文字数（{}）以上なので、どのテキストにも当てはまってしまいます",
+          word.len()
+        ));
+      }
+      Ok(WordMatcher::Fuzzy { word, distance })
+    } else {
+      #[cfg(feature = "morpheme")]
+      if config.match_mode == MatchMode::Morpheme {
+        return Ok(WordMatcher::Morpheme(
+          Finder::new(word.as_bytes()),
+          MorphemeTokenizer::shared()?,
+        ));
+      }
+      Ok(WordMatcher::Substring(Finder::new(word.as_bytes())))
+    }
+  }
+
+  /// 検索語を借りずに持つようにする
+  fn into_owned(self) -> WordMatcher<'static> {
+    match self {
+      WordMatcher::Substring(finder) => WordMatcher::Substring(finder.into_owned()),
+      WordMatcher::Regex(regex) => WordMatcher::Regex(regex),
+      WordMatcher::Fuzzy { word, distance } => WordMatcher::Fuzzy { word, distance },
+      WordMatcher::Folded(inner, folding) => WordMatcher::Folded(inner, folding),
+      #[cfg(feature = "morpheme")]
+      WordMatcher::Morpheme(finder, tokenizer) => {
+        WordMatcher::Morpheme(finder.into_owned(), tokenizer)
+      }
+    }
+  }
+
+  /// テキストに含まれているかどうか
+  pub fn is_match(&self, text: &str) -> bool {
+    match self {
+      WordMatcher::Substring(finder) => finder.find(text.as_bytes()).is_some(),
+      WordMatcher::Regex(regex) => regex.is_match(text),
+      WordMatcher::Fuzzy { .. } => !self.find_ranges(text).is_empty(),
+      WordMatcher::Folded(_, folding) if folding.is_numerals => !self.find_ranges(text).is_empty(),
+      WordMatcher::Folded(inner, folding) => inner.is_match(&folding.fold(text)),
+      #[cfg(feature = "morpheme")]
+      WordMatcher::Morpheme(..) => !self.find_ranges(text).is_empty(),
+    }
+  }
+
+  /// テキストの中で見つかった範囲（バイト単位、終端は含まない）
+  pub fn find_ranges(&self, text: &str) -> Vec<(usize, usize)> {
+    match self {
+      WordMatcher::Substring(finder) => {
+        let len = finder.needle().len();
+        finder
+          .find_iter(text.as_bytes())
+          .map(|start| (start, start + len))
+          .collect()
+      }
+      WordMatcher::Regex(regex) => regex
+        .find_iter(text)
+        .map(|m| (m.start(), m.end()))
+        .collect(),
+      WordMatcher::Fuzzy { word, distance } => find_fuzzy(text, word, *distance),
+      WordMatcher::Folded(inner, folding) => {
+        let (folded, offsets) = folding.fold_with_offsets(text);
+        inner
+          .find_ranges(&folded)
+          .into_iter()
+          .filter(|range| folding.is_whole_numbers(&folded, *range))
+          .map(|range| original_range(&offsets, range))
+          .collect()
+      }
+      #[cfg(feature = "morpheme")]
+      WordMatcher::Morpheme(finder, tokenizer) => {
+        let len = finder.needle().len();
+        let ranges = finder
+          .find_iter(text.as_bytes())
+          .map(|start| (start, start + len))
+          .collect::<Vec<_>>();
+        // 形態素解析は時間がかかるので、部分文字列として見つかったときだけ行う
+        if ranges.is_empty() {
+          return ranges;
+        }
+        match tokenizer.boundaries(text) {
+          Ok(boundaries) => ranges
+            .into_iter()
+            .filter(|(start, end)| {
+              boundaries.binary_search(start).is_ok() && boundaries.binary_search(end).is_ok()
+            })
+            .collect(),
+          Err(e) => {
+            tracing::warn!("{}: {e}", Message::MorphemeFallback.text());
+            ranges
+          }
+        }
+      }
+    }
+  }
+
+  /// テキストに含まれている回数（重ならないように数える）
+  pub fn count(&self, text: &str) -> usize {
+    match self {
+      WordMatcher::Substring(finder) => finder.find_iter(text.as_bytes()).count(),
+      WordMatcher::Regex(regex) => regex.find_iter(text).count(),
+      WordMatcher::Fuzzy { .. } => self.find_ranges(text).len(),
+      WordMatcher::Folded(_, folding) if folding.is_numerals => self.find_ranges(text).len(),
+      WordMatcher::Folded(inner, folding) => inner.count(&folding.fold(text)),
+      #[cfg(feature = "morpheme")]
+      WordMatcher::Morpheme(..) => self.find_ranges(text).len(),
+    }
+  }
+}
+
+/// 検索語と除外する語の判定の仕方をまとめたもの。
+/// 正規表現のコンパイルやAho-Corasickのオートマトンを作るのは時間がかかるので、
+/// [`Matchers::shared`]で一度だけ作り、全ての法令ファイルと断片の検索で共有する
+#[derive(Debug)]
+pub struct Matchers {
+  words: Vec<String>,
+  config: SearchConfig,
+  /// 検索語ごとの判定の仕方
+  pub word_matchers: Vec<WordMatcher<'static>>,
+  /// 全ての検索語を部分文字列として探すときに、一度の走査でまとめて探すためのもの
+  pub multi_matcher: Option<MultiMatcher>,
+  /// 除外する語ごとの判定の仕方
+  pub exclude_matchers: Vec<WordMatcher<'static>>,
+}
+
+impl Matchers {
+  /// 検索語`words`と設定`config`から判定の仕方を作る。正規表現として解釈できない語があればエラーを返す
+  pub fn new(words: &[String], config: &SearchConfig) -> Result<Self> {
+    Ok(Matchers {
+      words: words.to_vec(),
+      config: config.clone(),
+      word_matchers: words
+        .iter()
+        .map(|word| Ok(WordMatcher::new(word, config)?.into_owned()))
+        .collect::<Result<_>>()?,
+      multi_matcher: MultiMatcher::new(words, config),
+      exclude_matchers: config
+        .exclude_words
+        .iter()
+        .map(|word| Ok(WordMatcher::new(word, config)?.into_owned()))
+        .collect::<Result<_>>()?,
+    })
+  }
+
+  /// 検索語のうち`words`に含まれるものがテキストの中で見つかった範囲（バイト単位、終端は含まない）を、始まりの順に返す
+  pub fn find_ranges(&self, text: &str, words: &[String]) -> Vec<(usize, usize)> {
+    let mut ranges = self
+      .words
+      .iter()
+      .zip(self.word_matchers.iter())
+      .filter(|(word, _)| words.contains(word))
+      .flat_map(|(_, matcher)| matcher.find_ranges(text))
+      .collect::<Vec<_>>();
+    ranges.sort_unstable();
+    ranges.dedup();
+    ranges
+  }
+
+  /// 検索語`words`と設定`config`の判定の仕方を返す。同じ検索語と設定で前に作ったものがあれば、作り直さずにそれを返す
+  pub fn shared(words: &[String], config: &SearchConfig) -> Result<Arc<Self>> {
+    let mut shared = SHARED_MATCHERS
+      .lock()
+      .map_err(|_| anyhow!("検索語の判定の仕方を取り出せません"))?;
+    if let Some(matchers) = shared
+      .iter()
+      .find(|matchers| matchers.words == words && &matchers.config == config)
+    {
+      return Ok(matchers.clone());
+    }
+    let matchers = Arc::new(Matchers::new(words, config)?);
+    if shared.len() >= SHARED_MATCHERS_CAPACITY {
+      shared.remove(0);
+    }
+    shared.push(matchers.clone());
+    Ok(matchers)
+  }
+}
+
+/// 部分文字列として探す複数の検索語を、Aho-Corasickのオートマトンでテキストを一度走査するだけで探す
+#[derive(Debug, Clone)]
+pub struct MultiMatcher {
+  automaton: AhoCorasick,
+  /// オートマトンのパターンごとの、それに当たる検索語の番号。同じ検索語が複数あってもパターンは一つにする
+  word_indexes: Vec<Vec<usize>>,
+  word_count: usize,
+  folding: Folding,
+}
+
+impl MultiMatcher {
+  /// 検索語が二つ以上あり、全てを部分文字列として探すときだけ作る。
+  /// 正規表現・ワイルドカード・編集距離・形態素の境界を使うときは、検索語ごとに探す
+  pub fn new(words: &[String], config: &SearchConfig) -> Option<Self> {
+    let is_substring = !config.is_regex
+      && config.fuzzy.is_none()
+      && !(config.is_wildcard
+        && words
+          .iter()
+          .any(|word| word.contains(|c| c == '＊' || c == '？')));
+    #[cfg(feature = "morpheme")]
+    let is_substring = is_substring && config.match_mode == MatchMode::Substring;
+    if !is_substring || words.len() < 2 || words.iter().any(|word| word.is_empty()) {
+      return None;
+    }
+    let mut patterns: Vec<String> = vec![];
+    let mut word_indexes: Vec<Vec<usize>> = vec![];
+    for (i, word) in words.iter().enumerate() {
+      let pattern = config.folding.fold(word);
+      match patterns.iter().position(|p| p == &pattern) {
+        Some(j) => word_indexes[j].push(i),
+        None => {
+          patterns.push(pattern);
+          word_indexes.push(vec![i]);
+        }
+      }
+    }
+    Some(MultiMatcher {
+      automaton: AhoCorasick::new(&patterns),
+      word_indexes,
+      word_count: words.len(),
+      folding: config.folding,
+    })
+  }
+
+  /// 検索語ごとの、テキストに含まれている回数。
+  /// [`WordMatcher::count`]と同じく、検索語ごとに重ならないように数える
+  pub fn counts(&self, text: &str) -> Vec<usize> {
+    let folded;
+    let text = if self.folding.is_empty() {
+      text
+    } else {
+      folded = self.folding.fold(text);
+      &folded
+    };
+    let mut pattern_counts = vec![0; self.word_indexes.len()];
+    let mut last_ends = vec![0; self.word_indexes.len()];
+    // 重なりも含めて終わりの位置の順に見つかるので、同じパターンの前の出現と重なるものを飛ばす
+    for m in self.automaton.find_overlapping_iter(text) {
+      if !self.folding.is_whole_numbers(text, (m.start(), m.end())) {
+        continue;
+      }
+      let pattern = m.pattern();
+      if pattern_counts[pattern] == 0 || m.start() >= last_ends[pattern] {
+        pattern_counts[pattern] += 1;
+        last_ends[pattern] = m.end();
+      }
+    }
+    let mut counts = vec![0; self.word_count];
+    for (pattern, indexes) in self.word_indexes.iter().enumerate() {
+      for index in indexes.iter() {
+        counts[*index] = pattern_counts[pattern];
+      }
+    }
+    counts
+  }
+}
+
+/// `＊`を任意の長さ（0文字も含む）の文字列、`？`を任意の一文字とする検索語を正規表現にする。
+/// `＊`はできるだけ短く当てはめる
+fn wildcard_to_regex(word: &str) -> Result<Regex> {
+  let mut pattern = String::new();
+  let mut literal = String::new();
+  for c in word.chars() {
+    let wildcard = match c {
+      '＊' => ".*?",
+      '？' => ".",
+      _ => {
+        literal.push(c);
+        continue;
+      }
+    };
+    pattern.push_str(&regex::escape(&literal));
+    literal.clear();
+    pattern.push_str(wildcard);
+  }
+  pattern.push_str(&regex::escape(&literal));
+  Regex::new(&pattern)
+    .map_err(|e| anyhow!("ワイルドカードを含む検索語を解釈できません: {word}: {e}"))
+}
+
+/// 編集距離を求める前に、空白を取り除く。残った文字と、その元のテキストでの位置（バイト単位）を返す
+fn normalize(text: &str) -> Vec<(usize, char)> {
+  text
+    .char_indices()
+    .filter(|(_, c)| !c.is_whitespace())
+    .collect()
+}
+
+/// テキストの中で、`word`との編集距離（Levenshtein距離）が`distance`以下の部分文字列の範囲を、
+/// 重ならないように前から順に返す。重なり合う候補の中では編集距離が最も小さいものを選ぶ
+fn find_fuzzy(text: &str, word: &[char], distance: usize) -> Vec<(usize, usize)> {
+  let chars = normalize(text);
+  // `columns[i]`は、検索語の先頭`i`文字とテキストの今の位置で終わる部分文字列との編集距離と、その部分文字列の始まり
+  let mut columns = (0..=word.len()).map(|i| (i, 0)).collect::<Vec<_>>();
+  let mut ranges = vec![];
+  // 重なり合う候補のうち、今のところ最も編集距離が小さいもの
+  let mut best: Option<(usize, usize, usize)> = None;
+  for (j, &(_, c)) in chars.iter().enumerate() {
+    let mut next = Vec::with_capacity(columns.len());
+    next.push((0, j + 1));
+    for i in 1..=word.len() {
+      let substitution = (
+        columns[i - 1].0 + usize::from(word[i - 1] != c),
+        columns[i - 1].1,
+      );
+      let insertion = (columns[i].0 + 1, columns[i].1);
+      let deletion = (next[i - 1].0 + 1, next[i - 1].1);
+      // 編集距離が同じなら、短い（始まりが後ろの）部分文字列を選ぶ
+      let cell = [substitution, insertion, deletion]
+        .into_iter()
+        .min_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))
+        .unwrap();
+      next.push(cell);
+    }
+    columns = next;
+    let (cost, start) = columns[word.len()];
+    let is_after_last = ranges.last().map(|&(_, end)| end <= start).unwrap_or(true);
+    if cost <= distance && is_after_last {
+      match best {
+        Some((_, best_start, best_end)) if best_end <= start => {
+          ranges.push((best_start, best_end));
+          best = Some((cost, start, j + 1));
+        }
+        Some((best_cost, _, _)) if best_cost <= cost => (),
+        _ => best = Some((cost, start, j + 1)),
+      }
+    } else if let Some((_, best_start, best_end)) = best.take() {
+      ranges.push((best_start, best_end));
+    }
+  }
+  if let Some((_, start, end)) = best {
+    ranges.push((start, end));
+  }
+  ranges
+    .into_iter()
+    .map(|(start, end)| {
+      let byte_start = chars[start].0;
+      let (last, c) = chars[end - 1];
+      (byte_start, last + c.len_utf8())
+    })
+    .collect()
+}
+
+/// テキストの中で、`a`と`b`が間に`distance`文字以下しか挟まずに現れるかどうか。重なって現れたときも近いとみなす
+pub fn is_near(text: &str, a: &WordMatcher, b: &WordMatcher, distance: usize) -> bool {
+  let char_starts = text.char_indices().map(|(i, _)| i).collect::<Vec<_>>();
+  let to_chars = |(start, end): (usize, usize)| {
+    (
+      char_starts.partition_point(|&i| i < start),
+      char_starts.partition_point(|&i| i < end),
+    )
+  };
+  let a_ranges = a
+    .find_ranges(text)
+    .into_iter()
+    .map(to_chars)
+    .collect::<Vec<_>>();
+  let b_ranges = b
+    .find_ranges(text)
+    .into_iter()
+    .map(to_chars)
+    .collect::<Vec<_>>();
+  a_ranges.iter().any(|&(a_start, a_end)| {
+    b_ranges.iter().any(|&(b_start, b_end)| {
+      let gap = if a_end <= b_start {
+        b_start - a_end
+      } else if b_end <= a_start {
+        a_start - b_end
+      } else {
+        0
+      };
+      gap <= distance
+    })
+  })
+}
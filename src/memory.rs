@@ -0,0 +1,97 @@
+//! 取り出したテキストなどが使うメモリの量のおおよその見積もりと上限
+
+use crate::diagnostic::{report_warning, Code};
+use std::{
+  str::FromStr,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+};
+
+/// `512M`や`2G`のように指定されたバイト数。`K`・`M`・`G`は1024倍ずつ
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ByteSize(pub usize);
+
+impl ByteSize {
+  /// 0バイトを受け付けない`ByteSize`の読み取り。バッファの大きさのように、0では困るものにclapで使う
+  pub fn parse_nonzero(s: &str) -> Result<Self, String> {
+    match s.parse::<ByteSize>()? {
+      ByteSize(0) => Err(format!("1バイト以上を指定してください: {s}")),
+      size => Ok(size),
+    }
+  }
+}
+
+impl FromStr for ByteSize {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let err = || format!("バイト数の形式が正しくありません: {s}");
+    let s_upper = s.trim().to_ascii_uppercase();
+    let s_upper = s_upper.strip_suffix('B').unwrap_or(&s_upper);
+    let (digits, unit) = match s_upper.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+      Some((i, _)) => s_upper.split_at(i),
+      None => (s_upper, ""),
+    };
+    let n = digits.parse::<usize>().map_err(|_| err())?;
+    let scale: usize = match unit {
+      "" => 1,
+      "K" => 1 << 10,
+      "M" => 1 << 20,
+      "G" => 1 << 30,
+      _ => return Err(err()),
+    };
+    n.checked_mul(scale).map(ByteSize).ok_or_else(err)
+  }
+}
+
+/// 見積もったメモリの量が上限を超えていないかどうかを見張る。
+/// 複製したものとは上限を超えたかどうかを共有するので、同時に検索しているところにも伝わる
+#[derive(Debug, Clone)]
+pub struct MemoryGuard {
+  limit: Option<usize>,
+  is_exceeded: Arc<AtomicBool>,
+}
+
+impl MemoryGuard {
+  pub fn new(limit: Option<usize>) -> Self {
+    MemoryGuard {
+      limit,
+      is_exceeded: Arc::new(AtomicBool::new(false)),
+    }
+  }
+
+  /// 見積もったメモリの量`used`が上限以下かどうかを返す。
+  /// 一度上限を超えたら、その後は常に`false`を返す
+  pub fn check(&mut self, used: usize) -> bool {
+    if self.is_exceeded() {
+      return false;
+    }
+    match self.limit {
+      Some(limit) if used > limit => {
+        report_warning(
+          Code::MemoryLimitExceeded,
+          format!(
+            "memory limit exceeded ({} > {} bytes): stop capturing text, keep only positions",
+            used, limit
+          ),
+        );
+        self.is_exceeded.store(true, Ordering::Relaxed);
+        false
+      }
+      _ => true,
+    }
+  }
+
+  pub fn is_exceeded(&self) -> bool {
+    self.is_exceeded.load(Ordering::Relaxed)
+  }
+}
+
+impl PartialEq for MemoryGuard {
+  fn eq(&self, other: &Self) -> bool {
+    self.limit == other.limit && self.is_exceeded() == other.is_exceeded()
+  }
+}
+
+impl Eq for MemoryGuard {}
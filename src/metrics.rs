@@ -0,0 +1,48 @@
+//! 見つかった箇所のある条の、文章の長さの指標（文字数・文の数・文の平均の長さ）
+//!
+//! 条文の複雑さを大まかに比べるためのもので、文は`Sentence`要素、文字数はそのテキストのルビの読み仮名を除いた文字の数で数える。
+
+use crate::Chapter;
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+
+/// 条一つの文章の長さの指標
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub struct ArticleMetrics {
+  /// 条の位置情報。項以下の情報は持たない。条の無い附則では附則全体を一つの条とする
+  pub chapter: Chapter,
+  /// 文のテキストの文字数
+  pub char_count: usize,
+  /// 文の数
+  pub sentence_count: usize,
+}
+
+impl ArticleMetrics {
+  pub fn new(chapter: Chapter) -> Self {
+    ArticleMetrics {
+      chapter,
+      char_count: 0,
+      sentence_count: 0,
+    }
+  }
+
+  /// 文の平均の文字数。文が無ければ無い
+  pub fn average_sentence_length(&self) -> Option<f64> {
+    if self.sentence_count == 0 {
+      None
+    } else {
+      Some(self.char_count as f64 / self.sentence_count as f64)
+    }
+  }
+}
+
+// 文の平均の長さは、文字数と文の数から求めて一緒に書き出す
+impl Serialize for ArticleMetrics {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut state = serializer.serialize_struct("ArticleMetrics", 4)?;
+    state.serialize_field("chapter", &self.chapter)?;
+    state.serialize_field("char_count", &self.char_count)?;
+    state.serialize_field("sentence_count", &self.sentence_count)?;
+    state.serialize_field("average_sentence_length", &self.average_sentence_length())?;
+    state.end()
+  }
+}
@@ -0,0 +1,62 @@
+//! 形態素解析（lindera）による語の境界の判定
+
+use anyhow::{anyhow, Result};
+use lindera::tokenizer::Tokenizer;
+use std::{
+  fmt,
+  sync::{Arc, Mutex},
+};
+
+/// 一度読み込んだ辞書を使い回すための、共有する形態素解析器
+static SHARED_TOKENIZER: Mutex<Option<MorphemeTokenizer>> = Mutex::new(None);
+
+/// 文のテキストを形態素に分け、語の境界を求める
+#[derive(Clone)]
+pub struct MorphemeTokenizer {
+  tokenizer: Arc<Tokenizer>,
+}
+
+impl fmt::Debug for MorphemeTokenizer {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("MorphemeTokenizer").finish_non_exhaustive()
+  }
+}
+
+impl MorphemeTokenizer {
+  /// IPADICの辞書で形態素に分ける
+  pub fn new() -> Result<Self> {
+    Ok(MorphemeTokenizer {
+      tokenizer: Arc::new(Tokenizer::new()?),
+    })
+  }
+
+  /// 全ての検索語と法令ファイルで共有する形態素解析器。辞書の読み込みは時間がかかるので、最初に呼んだときに一度だけ読み込む
+  pub fn shared() -> Result<Self> {
+    let mut shared = SHARED_TOKENIZER
+      .lock()
+      .map_err(|_| anyhow!("形態素解析器を取り出せません"))?;
+    if let Some(tokenizer) = shared.as_ref() {
+      return Ok(tokenizer.clone());
+    }
+    let tokenizer = MorphemeTokenizer::new()?;
+    *shared = Some(tokenizer.clone());
+    Ok(tokenizer)
+  }
+
+  /// テキストの形態素の境界の位置（バイト単位）を昇順に返す。先頭と末尾も含む
+  pub fn boundaries(&self, text: &str) -> Result<Vec<usize>> {
+    let mut boundaries = vec![0];
+    let mut position = 0;
+    for token in self.tokenizer.tokenize(text)? {
+      // 形態素はテキストの順に現れるので、今の位置から後ろだけを探せばよい
+      if let Some(start) = text[position..].find(token.text) {
+        position += start + token.text.len();
+        boundaries.push(position);
+      }
+    }
+    if boundaries.last() != Some(&text.len()) {
+      boundaries.push(text.len());
+    }
+    Ok(boundaries)
+  }
+}
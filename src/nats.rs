@@ -0,0 +1,43 @@
+//! NATSへの検索結果の送信（`nats` feature）
+//!
+//! 検索できた法令ごとに、検索結果をJSONにしてNATSのsubjectに送る。
+
+use crate::LawParagraph;
+use anyhow::{anyhow, Result};
+
+/// 検索結果を送るNATSのsubject
+pub struct NatsSink {
+  client: async_nats::Client,
+  subject: String,
+}
+
+impl NatsSink {
+  pub async fn connect(url: &str, subject: &str) -> Result<Self> {
+    let client = async_nats::connect(url)
+      .await
+      .map_err(|e| anyhow!("NATSに接続できませんでした: {e}"))?;
+    Ok(NatsSink {
+      client,
+      subject: subject.to_string(),
+    })
+  }
+
+  /// 法令一つ分の検索結果をJSONにして送る
+  pub async fn publish(&self, law_paragraph: &LawParagraph) -> Result<()> {
+    let payload = serde_json::to_vec(law_paragraph)?;
+    self
+      .client
+      .publish(self.subject.clone(), payload.into())
+      .await
+      .map_err(|e| anyhow!("NATSに送れませんでした: {e}"))
+  }
+
+  /// 送った検索結果がサーバーに届くまで待つ
+  pub async fn flush(&self) -> Result<()> {
+    self
+      .client
+      .flush()
+      .await
+      .map_err(|e| anyhow!("NATSへの送信を終えられませんでした: {e}"))
+  }
+}
@@ -0,0 +1,84 @@
+//! 条・項・号などの`Num`属性の値の正規化
+//!
+//! `Num`属性は「3」や枝番号付きの「3_2」のほか、削られた条をまとめた「3:5」のような範囲にもなる。
+//! これを一度だけ読み、機械的に扱う鍵（`3-2`）と表示用の表記（「第三条の二」）のどちらにもできるようにする。
+
+use crate::citation::NumberStyle;
+use std::fmt;
+
+/// 正規化した`Num`属性の値
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Num {
+  /// 番号と枝番号（「3_2」なら`[3, 2]`）
+  pub start: Vec<usize>,
+  /// 範囲のときの終わりの番号と枝番号
+  pub end: Option<Vec<usize>>,
+}
+
+fn parse_parts(s: &str) -> Option<Vec<usize>> {
+  s.split('_').map(|n| n.parse().ok()).collect()
+}
+
+fn key_of(parts: &[usize]) -> String {
+  parts
+    .iter()
+    .map(|n| n.to_string())
+    .collect::<Vec<_>>()
+    .join("-")
+}
+
+impl Num {
+  /// `Num`属性の値を読む。数として読めないときは`None`を返す
+  pub fn parse(s: &str) -> Option<Num> {
+    match s.split_once(':') {
+      Some((start, end)) => Some(Num {
+        start: parse_parts(start)?,
+        end: Some(parse_parts(end)?),
+      }),
+      None => Some(Num {
+        start: parse_parts(s)?,
+        end: None,
+      }),
+    }
+  }
+
+  /// 枝番号を`-`でつないだ鍵（「3_2」なら`3-2`、「3:5」なら`3:5`）
+  pub fn key(&self) -> String {
+    match &self.end {
+      Some(end) => format!("{}:{}", key_of(&self.start), key_of(end)),
+      None => key_of(&self.start),
+    }
+  }
+
+  /// 「十一の二」のように、番号と枝番号を「の」でつないだ表記。範囲のときは始めの番号だけを書く
+  pub fn number(&self, style: NumberStyle) -> String {
+    self
+      .start
+      .iter()
+      .map(|n| style.number(*n))
+      .collect::<Vec<_>>()
+      .join("の")
+  }
+
+  /// 「第十一条の二」のように、単位`unit`を付けた表記。範囲のときは「第三条から第五条まで」のようにする
+  pub fn display(&self, unit: char, style: NumberStyle) -> String {
+    let title = |parts: &[usize]| {
+      let mut s = format!("第{}{unit}", style.number(parts[0]));
+      for n in parts[1..].iter() {
+        s.push('の');
+        s.push_str(&style.number(*n));
+      }
+      s
+    };
+    match &self.end {
+      Some(end) => format!("{}から{}まで", title(&self.start), title(end)),
+      None => title(&self.start),
+    }
+  }
+}
+
+impl fmt::Display for Num {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.key())
+  }
+}
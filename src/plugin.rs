@@ -0,0 +1,161 @@
+//! WASMのプラグインによる、見つかった箇所の後処理（`plugin` feature）
+//!
+//! プラグインは次のものをexportするWASMのモジュールで、見つかった箇所一つごとに呼ばれる。
+//!
+//! - `memory`：線形メモリ
+//! - `alloc(len: i32) -> i32`：入力のJSONを書き込む`len`バイトの領域を確保し、その先頭の位置を返す
+//! - `process(ptr: i32, len: i32) -> i64`：入力のJSONを受け取り、出力のJSONの位置を上位32ビットに、長さを下位32ビットにして返す
+//! - `dealloc(ptr: i32, len: i32)`：`alloc`で確保した入力の領域と、`process`が返した出力の領域を解放する。読み終えたら呼ぶ
+//!
+//! 一度の呼び出しで使える計算の量には上限（[`FUEL_PER_CALL`]）があり、超えたプラグインはエラーにして止める。
+//!
+//! 入力は`{"num": 法令番号, "match": 見つかった箇所, "text": その箇所を含む項のテキスト}`、
+//! 出力は`{"reject": 捨てるかどうか, "tags": [付けるタグ], "attributes": {付ける属性}}`で、出力のキーはどれも省略できる。
+//! プラグインを複数指定したときは指定した順に呼び、どれかが捨てた箇所はそれ以降のプラグインに渡さない。
+
+use crate::{LawParagraph, Match};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+use wasmtime::{Config, Engine, Instance, Memory, Module, Store, TypedFunc};
+
+/// 見つかった箇所一つについてプラグインを呼ぶときに使える燃料（実行する命令のおおよその数）の上限
+pub const FUEL_PER_CALL: u64 = 1_000_000_000;
+
+/// プラグインに渡す、見つかった箇所一つ分の入力
+#[derive(Debug, Clone, Serialize)]
+struct PluginInput<'a> {
+  num: &'a str,
+  #[serde(rename = "match")]
+  m: &'a Match,
+  text: &'a str,
+}
+
+/// プラグインが返す、見つかった箇所一つ分の処理の結果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct PluginOutput {
+  /// 見つかった箇所を捨てるかどうか
+  #[serde(default)]
+  pub reject: bool,
+  /// 見つかった箇所に付けるタグ
+  #[serde(default)]
+  pub tags: Vec<String>,
+  /// 見つかった箇所に付ける属性。同じキーがすでにあれば上書きする
+  #[serde(default)]
+  pub attributes: BTreeMap<String, String>,
+}
+
+/// 読み込んだプラグイン一つ
+pub struct Plugin {
+  name: String,
+  store: Store<()>,
+  memory: Memory,
+  alloc: TypedFunc<i32, i32>,
+  process: TypedFunc<(i32, i32), i64>,
+  dealloc: TypedFunc<(i32, i32), ()>,
+}
+
+impl Plugin {
+  /// WASMのモジュールのファイルを読み込む
+  pub fn load(path: &Path) -> Result<Self> {
+    let name = path.display().to_string();
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config)?;
+    let module = Module::from_file(&engine, path)
+      .map_err(|e| anyhow!("プラグインを読み込めません: {name}: {e}"))?;
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])
+      .map_err(|e| anyhow!("プラグインを初期化できません: {name}: {e}"))?;
+    let memory = instance
+      .get_memory(&mut store, "memory")
+      .ok_or_else(|| anyhow!("プラグインが`memory`をexportしていません: {name}"))?;
+    let alloc = instance
+      .get_typed_func::<i32, i32, _>(&mut store, "alloc")
+      .map_err(|e| anyhow!("プラグインの`alloc`を使えません: {name}: {e}"))?;
+    let process = instance
+      .get_typed_func::<(i32, i32), i64, _>(&mut store, "process")
+      .map_err(|e| anyhow!("プラグインの`process`を使えません: {name}: {e}"))?;
+    let dealloc = instance
+      .get_typed_func::<(i32, i32), (), _>(&mut store, "dealloc")
+      .map_err(|e| anyhow!("プラグインの`dealloc`を使えません: {name}: {e}"))?;
+    Ok(Plugin {
+      name,
+      store,
+      memory,
+      alloc,
+      process,
+      dealloc,
+    })
+  }
+
+  /// 燃料を、一度の呼び出しで使える上限まで足す
+  fn refuel(&mut self) -> Result<()> {
+    let remaining = self.store.consume_fuel(0)?;
+    self
+      .store
+      .add_fuel(FUEL_PER_CALL.saturating_sub(remaining))?;
+    Ok(())
+  }
+
+  /// 入力のJSONを渡して`process`を呼び、出力のJSONを読む。入力と出力の領域は読み終えたら解放させる
+  fn call(&mut self, input: &[u8]) -> Result<PluginOutput> {
+    self.refuel()?;
+    let name = &self.name;
+    let call_error = |e: anyhow::Error| {
+      anyhow!("プラグインの実行に失敗しました（計算の量の上限を超えたときも含む）: {name}: {e}")
+    };
+    let len = i32::try_from(input.len())?;
+    let ptr = self.alloc.call(&mut self.store, len).map_err(call_error)?;
+    self
+      .memory
+      .write(&mut self.store, ptr as u32 as usize, input)?;
+    let packed = self
+      .process
+      .call(&mut self.store, (ptr, len))
+      .map_err(call_error)? as u64;
+    let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+    let mut output = vec![0; out_len];
+    self.memory.read(&self.store, out_ptr, &mut output)?;
+    self
+      .dealloc
+      .call(&mut self.store, (ptr, len))
+      .map_err(call_error)?;
+    self
+      .dealloc
+      .call(&mut self.store, (out_ptr as i32, out_len as i32))
+      .map_err(call_error)?;
+    serde_json::from_slice(&output)
+      .map_err(|e| anyhow!("プラグインの出力を解析できません: {}: {e}", self.name))
+  }
+}
+
+/// 法令一つ分の見つかった箇所それぞれをプラグインに順に渡し、捨てられた箇所を除き、タグと属性を付ける。
+/// `texts`は見つかった箇所それぞれを含む項のテキストで、`matches`と同じ順に並べる
+pub fn apply_plugins(
+  plugins: &mut [Plugin],
+  mut law_paragraph: LawParagraph,
+  texts: &[String],
+) -> Result<LawParagraph> {
+  let mut is_rejected = vec![false; law_paragraph.matches.len()];
+  for (i, text) in texts.iter().enumerate().take(law_paragraph.matches.len()) {
+    for plugin in plugins.iter_mut() {
+      let input = serde_json::to_vec(&PluginInput {
+        num: &law_paragraph.num,
+        m: &law_paragraph.matches[i],
+        text,
+      })?;
+      let output = plugin.call(&input)?;
+      if output.reject {
+        is_rejected[i] = true;
+        break;
+      }
+      let m = &mut law_paragraph.matches[i];
+      m.tags.extend(output.tags);
+      m.attributes.extend(output.attributes);
+    }
+  }
+  let mut is_rejected = is_rejected.into_iter();
+  law_paragraph.retain_matches(|_| !is_rejected.next().unwrap_or(false));
+  Ok(law_paragraph)
+}
@@ -0,0 +1,106 @@
+//! PostgreSQLへの検索結果の書き込み（`postgres` feature）
+//!
+//! 検索できた法令を`laws`テーブルに、見つかった箇所を`matches`テーブルに書き込む。
+//! 同じ法令（と版）を書き込み直したときは、前に書き込んだものを置き換える。
+//! 書き込みは`batch_size`件の法令ごとに一つのトランザクションで行う。
+
+use crate::LawParagraph;
+use anyhow::Result;
+use sqlx::{postgres::PgPoolOptions, types::Json, PgPool};
+
+const CREATE_LAWS_TABLE: &str = "CREATE TABLE IF NOT EXISTS laws (
+  num TEXT NOT NULL,
+  version TEXT NOT NULL DEFAULT '',
+  matched_words TEXT[] NOT NULL,
+  chapter_data JSONB NOT NULL,
+  PRIMARY KEY (num, version)
+)";
+
+const CREATE_MATCHES_TABLE: &str = "CREATE TABLE IF NOT EXISTS matches (
+  id TEXT NOT NULL,
+  version TEXT NOT NULL DEFAULT '',
+  law_num TEXT NOT NULL,
+  chapter JSONB NOT NULL,
+  word TEXT NOT NULL,
+  article_caption TEXT,
+  PRIMARY KEY (id, version)
+)";
+
+const UPSERT_LAW: &str = "INSERT INTO laws (num, version, matched_words, chapter_data)
+VALUES ($1, $2, $3, $4)
+ON CONFLICT (num, version) DO UPDATE
+SET matched_words = EXCLUDED.matched_words, chapter_data = EXCLUDED.chapter_data";
+
+const DELETE_MATCHES: &str = "DELETE FROM matches WHERE law_num = $1 AND version = $2";
+
+const INSERT_MATCH: &str =
+  "INSERT INTO matches (id, version, law_num, chapter, word, article_caption)
+VALUES ($1, $2, $3, $4, $5, $6)
+ON CONFLICT (id, version) DO NOTHING";
+
+/// 検索結果を書き込むPostgreSQLのデータベース
+pub struct PostgresSink {
+  pool: PgPool,
+  batch_size: usize,
+  pending: Vec<LawParagraph>,
+}
+
+impl PostgresSink {
+  /// データベースに接続し、テーブルが無ければ作る
+  pub async fn connect(dsn: &str, batch_size: usize) -> Result<Self> {
+    let pool = PgPoolOptions::new().max_connections(1).connect(dsn).await?;
+    sqlx::query(CREATE_LAWS_TABLE).execute(&pool).await?;
+    sqlx::query(CREATE_MATCHES_TABLE).execute(&pool).await?;
+    Ok(PostgresSink {
+      pool,
+      batch_size: batch_size.max(1),
+      pending: vec![],
+    })
+  }
+
+  /// 法令一つ分の検索結果を書き込む。`batch_size`件たまるまでは書き込まずに持っておく
+  pub async fn push(&mut self, law_paragraph: &LawParagraph) -> Result<()> {
+    self.pending.push(law_paragraph.clone());
+    if self.pending.len() >= self.batch_size {
+      self.flush().await?;
+    }
+    Ok(())
+  }
+
+  /// 持っている検索結果を全て書き込む
+  pub async fn flush(&mut self) -> Result<()> {
+    if self.pending.is_empty() {
+      return Ok(());
+    }
+    let mut tx = self.pool.begin().await?;
+    for law_paragraph in self.pending.iter() {
+      let version = law_paragraph.version.clone().unwrap_or_default();
+      sqlx::query(UPSERT_LAW)
+        .bind(&law_paragraph.num)
+        .bind(&version)
+        .bind(&law_paragraph.matched_words)
+        .bind(Json(&law_paragraph.chapter_data))
+        .execute(&mut tx)
+        .await?;
+      sqlx::query(DELETE_MATCHES)
+        .bind(&law_paragraph.num)
+        .bind(&version)
+        .execute(&mut tx)
+        .await?;
+      for m in law_paragraph.matches.iter() {
+        sqlx::query(INSERT_MATCH)
+          .bind(&m.id)
+          .bind(&version)
+          .bind(&law_paragraph.num)
+          .bind(Json(&m.chapter))
+          .bind(&m.word)
+          .bind(&m.article_caption)
+          .execute(&mut tx)
+          .await?;
+      }
+    }
+    tx.commit().await?;
+    self.pending.clear();
+    Ok(())
+  }
+}
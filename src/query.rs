@@ -0,0 +1,181 @@
+//! `許可 AND (取消 OR 停止) NOT 附則`のような検索式
+//!
+//! 語は空白か括弧で区切る。演算子は強い順に`NOT`・`AND`・`OR`で、
+//! 語を並べただけのときは`AND`でつなげたものとみなす。`A NOT B`は`A AND NOT B`と同じ。
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// 検索式を解析した木
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Query {
+  Term(String),
+  And(Box<Query>, Box<Query>),
+  Or(Box<Query>, Box<Query>),
+  Not(Box<Query>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+  Term(String),
+  And,
+  Or,
+  Not,
+  Open,
+  Close,
+}
+
+fn tokenize(s: &str) -> Vec<Token> {
+  let mut tokens = vec![];
+  let mut term = String::new();
+  let push_term = |term: &mut String, tokens: &mut Vec<Token>| {
+    if !term.is_empty() {
+      let token = match term.as_str() {
+        "AND" => Token::And,
+        "OR" => Token::Or,
+        "NOT" => Token::Not,
+        _ => Token::Term(term.clone()),
+      };
+      tokens.push(token);
+      term.clear();
+    }
+  };
+  for c in s.chars() {
+    match c {
+      '(' | ')' => {
+        push_term(&mut term, &mut tokens);
+        tokens.push(if c == '(' { Token::Open } else { Token::Close });
+      }
+      _ if c.is_whitespace() => push_term(&mut term, &mut tokens),
+      _ => term.push(c),
+    }
+  }
+  push_term(&mut term, &mut tokens);
+  tokens
+}
+
+struct Parser {
+  tokens: Vec<Token>,
+  position: usize,
+}
+
+impl Parser {
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.position)
+  }
+
+  fn next(&mut self) -> Option<Token> {
+    let token = self.tokens.get(self.position).cloned();
+    self.position += 1;
+    token
+  }
+
+  fn parse_or(&mut self) -> Result<Query> {
+    let mut query = self.parse_and()?;
+    while self.peek() == Some(&Token::Or) {
+      self.next();
+      query = Query::Or(Box::new(query), Box::new(self.parse_and()?));
+    }
+    Ok(query)
+  }
+
+  fn parse_and(&mut self) -> Result<Query> {
+    let mut query = self.parse_not()?;
+    loop {
+      match self.peek() {
+        None | Some(Token::Or) | Some(Token::Close) => return Ok(query),
+        Some(Token::And) => {
+          self.next();
+        }
+        _ => (),
+      }
+      query = Query::And(Box::new(query), Box::new(self.parse_not()?));
+    }
+  }
+
+  fn parse_not(&mut self) -> Result<Query> {
+    if self.peek() == Some(&Token::Not) {
+      self.next();
+      return Ok(Query::Not(Box::new(self.parse_not()?)));
+    }
+    match self.next() {
+      Some(Token::Term(term)) => Ok(Query::Term(term)),
+      Some(Token::Open) => {
+        let query = self.parse_or()?;
+        match self.next() {
+          Some(Token::Close) => Ok(query),
+          _ => Err(anyhow!("検索式の括弧が閉じられていません")),
+        }
+      }
+      Some(token) => Err(anyhow!(
+        "検索式の{}番目の語の位置に{:?}があります",
+        self.position,
+        token
+      )),
+      None => Err(anyhow!("検索式が途中で終わっています")),
+    }
+  }
+}
+
+impl Query {
+  /// 検索式を解析する
+  pub fn parse(s: &str) -> Result<Query> {
+    let mut parser = Parser {
+      tokens: tokenize(s),
+      position: 0,
+    };
+    let query = parser.parse_or()?;
+    if parser.position < parser.tokens.len() {
+      return Err(anyhow!("検索式の括弧が対応していません: {s}"));
+    }
+    if query.positive_terms().is_empty() {
+      return Err(anyhow!("検索式に`NOT`の付いていない語がありません: {s}"));
+    }
+    Ok(query)
+  }
+
+  /// 検索式に現れる語を、現れた順に重複を除いて返す
+  pub fn terms(&self) -> Vec<String> {
+    let mut lst = vec![];
+    self.collect_terms(false, &mut |term, _| {
+      if !lst.contains(&term.to_string()) {
+        lst.push(term.to_string());
+      }
+    });
+    lst
+  }
+
+  /// `NOT`の付いていない語。見つかった箇所として記録するのはこれらの語だけにする
+  pub fn positive_terms(&self) -> HashSet<String> {
+    let mut set = HashSet::new();
+    self.collect_terms(false, &mut |term, is_negated| {
+      if !is_negated {
+        set.insert(term.to_string());
+      }
+    });
+    set
+  }
+
+  fn collect_terms(&self, is_negated: bool, f: &mut impl FnMut(&str, bool)) {
+    match self {
+      Query::Term(term) => f(term, is_negated),
+      Query::And(a, b) | Query::Or(a, b) => {
+        a.collect_terms(is_negated, f);
+        b.collect_terms(is_negated, f);
+      }
+      Query::Not(a) => a.collect_terms(!is_negated, f),
+    }
+  }
+
+  /// 含まれていた語の集合`found`に対して検索式を評価する
+  pub fn evaluate(&self, found: &HashSet<&str>) -> bool {
+    match self {
+      Query::Term(term) => found.contains(term.as_str()),
+      Query::And(a, b) => a.evaluate(found) && b.evaluate(found),
+      Query::Or(a, b) => a.evaluate(found) || b.evaluate(found),
+      Query::Not(a) => !a.evaluate(found),
+    }
+  }
+}
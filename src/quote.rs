@@ -0,0 +1,30 @@
+//! 「」で囲まれた語句の切り出し
+
+/// テキストの中で「」に囲まれた部分（括弧そのものは含まない）を、外側の括弧ごとに、テキストの何バイト目から始まるかと共に返す。
+/// 入れ子になった括弧は外側の括弧の中に含め、閉じられていない括弧はテキストの終わりまでとする
+pub fn quoted_segments(text: &str) -> Vec<(usize, &str)> {
+  let mut segments = vec![];
+  let mut depth = 0;
+  let mut start = 0;
+  for (i, c) in text.char_indices() {
+    match c {
+      '「' => {
+        if depth == 0 {
+          start = i + c.len_utf8();
+        }
+        depth += 1;
+      }
+      '」' if depth > 0 => {
+        depth -= 1;
+        if depth == 0 {
+          segments.push((start, &text[start..i]));
+        }
+      }
+      _ => (),
+    }
+  }
+  if depth > 0 {
+    segments.push((start, &text[start..]));
+  }
+  segments
+}
@@ -0,0 +1,124 @@
+//! 法令ファイルの読み方の選択
+//!
+//! 小さいファイルは全てメモリに読み込み、大きいファイルはメモリマップし、
+//! 空いているメモリが足りないときはバッファを通して少しずつ読む。
+//! メモリ上にある大きいファイルは、本則と附則の境目で分けて並列に検索する。
+
+use serde::Serialize;
+use std::fmt;
+
+/// この大きさ以下のファイルは、全てメモリに読み込む
+const IN_MEMORY_MAX_SIZE: u64 = 4 << 20;
+
+/// 空いているメモリのうち、ファイル一つを読むのに使ってよい割合の逆数
+const AVAILABLE_MEMORY_DIVISOR: u64 = 8;
+
+/// この大きさ以上のファイルは、本則と附則の境目で分けて並列に検索する
+const PARALLEL_MIN_SIZE: u64 = 1 << 20;
+
+/// 法令ファイルの読み方
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ReadStrategy {
+  /// バッファを通して少しずつ非同期に読む
+  Stream,
+  /// 全てメモリに読み込んでから同期的に検索する
+  InMemory,
+  /// メモリマップして同期的に検索する
+  Mmap,
+}
+
+impl ReadStrategy {
+  /// ファイルの大きさと空いているメモリの量から読み方を選ぶ。
+  /// 空いているメモリの量が分からないときは、バッファを通して読む
+  pub fn choose(file_size: u64, available_memory: Option<u64>) -> Self {
+    match available_memory {
+      Some(available) if file_size <= available / AVAILABLE_MEMORY_DIVISOR => {
+        if file_size <= IN_MEMORY_MAX_SIZE {
+          ReadStrategy::InMemory
+        } else {
+          ReadStrategy::Mmap
+        }
+      }
+      _ => ReadStrategy::Stream,
+    }
+  }
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ReadStrategy::Stream => "stream",
+      ReadStrategy::InMemory => "in_memory",
+      ReadStrategy::Mmap => "mmap",
+    }
+  }
+}
+
+impl fmt::Display for ReadStrategy {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+/// 法令ファイルの読み方についての指定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ReadOptions {
+  /// バッファを通して読むときのバッファの大きさ
+  pub buffer_size: usize,
+  /// この大きさ以下のファイルは全てメモリに読み込み、それより大きいファイルはバッファを通して読む
+  pub in_memory_max_size: Option<usize>,
+  /// 全てのファイルをメモリマップする
+  pub is_mmap: bool,
+  /// 空いているメモリの量（バイト）
+  pub available_memory: Option<u64>,
+}
+
+impl Default for ReadOptions {
+  fn default() -> Self {
+    ReadOptions {
+      buffer_size: 256 << 10,
+      in_memory_max_size: None,
+      is_mmap: false,
+      available_memory: None,
+    }
+  }
+}
+
+impl ReadOptions {
+  /// `is_mmap`か`in_memory_max_size`が指定されていればそれに従い、
+  /// そうでなければファイルの大きさと空いているメモリの量から読み方を選ぶ
+  pub fn strategy(&self, file_size: u64) -> ReadStrategy {
+    match (self.is_mmap, self.in_memory_max_size) {
+      (true, _) => ReadStrategy::Mmap,
+      (false, Some(max_size)) if file_size <= max_size as u64 => ReadStrategy::InMemory,
+      (false, Some(_)) => ReadStrategy::Stream,
+      (false, None) => ReadStrategy::choose(file_size, self.available_memory),
+    }
+  }
+}
+
+/// 全てメモリに読み込むかメモリマップした法令ファイルを、いくつに分けて並列に検索するか
+pub fn parallel_parts(file_size: u64) -> usize {
+  if file_size < PARALLEL_MIN_SIZE {
+    return 1;
+  }
+  std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// `/proc/meminfo`の`MemAvailable`から、空いているメモリの量（バイト）を返す。
+/// 読めないとき（Linux以外など）は`None`を返す
+pub fn available_memory() -> Option<u64> {
+  let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+  parse_mem_available(&meminfo)
+}
+
+fn parse_mem_available(meminfo: &str) -> Option<u64> {
+  let line = meminfo
+    .lines()
+    .find(|line| line.starts_with("MemAvailable:"))?;
+  let mut fields = line["MemAvailable:".len()..].split_whitespace();
+  let value = fields.next()?.parse::<u64>().ok()?;
+  match fields.next() {
+    Some("kB") => value.checked_mul(1024),
+    None => Some(value),
+    Some(_) => None,
+  }
+}
@@ -0,0 +1,116 @@
+//! 手元のONNXのcross-encoderによる、見つかった箇所と調べたいことの関連の強さの採点（`relevance` feature）
+//!
+//! モデルのディレクトリには、ONNXに変換したcross-encoderの`model.onnx`と、その`tokenizer.json`を置く。
+//! 調べたいことを説明する文と見つかった箇所のテキストを組にしてモデルに渡し、その出力（ロジット）を点数にする。
+//! 点数は見つかった箇所どうしを並べるためのもので、モデルによって範囲が違う。
+
+use crate::Chapter;
+use anyhow::{anyhow, Result};
+use ndarray::Array2;
+use ort::{tensor::OrtOwnedTensor, Environment, GraphOptimizationLevel, Session, SessionBuilder};
+use serde::{Deserialize, Serialize};
+use std::{path::Path, sync::Arc};
+use tokenizers::{Tokenizer, TruncationParams};
+
+/// モデルに渡すトークンの数の上限。これより長いテキストは後ろを切り捨てる
+const MAX_TOKENS: usize = 512;
+
+/// 読み込んだcross-encoderと、調べたいことを説明する文
+pub struct RelevanceModel {
+  // `session`より長く生きている必要がある
+  _environment: Arc<Environment>,
+  session: Session,
+  tokenizer: Tokenizer,
+  query: String,
+}
+
+impl RelevanceModel {
+  /// モデルのディレクトリから`model.onnx`と`tokenizer.json`を読み込む
+  pub fn load(model_dir: &Path, query: &str) -> Result<Self> {
+    let environment = Arc::new(
+      Environment::builder()
+        .with_name("search_article_with_word")
+        .build()?,
+    );
+    let session = SessionBuilder::new(&environment)?
+      .with_optimization_level(GraphOptimizationLevel::Level1)?
+      .with_model_from_file(model_dir.join("model.onnx"))
+      .map_err(|e| anyhow!("モデルを読み込めません: {}: {e}", model_dir.display()))?;
+    let mut tokenizer = Tokenizer::from_file(model_dir.join("tokenizer.json"))
+      .map_err(|e| anyhow!("トークナイザを読み込めません: {}: {e}", model_dir.display()))?;
+    tokenizer.with_truncation(Some(TruncationParams {
+      max_length: MAX_TOKENS,
+      ..Default::default()
+    }));
+    Ok(RelevanceModel {
+      _environment: environment,
+      session,
+      tokenizer,
+      query: query.to_string(),
+    })
+  }
+
+  /// テキストと調べたいことの関連の強さ。大きいほど関連が強い
+  pub fn score(&mut self, text: &str) -> Result<f32> {
+    let encoding = self
+      .tokenizer
+      .encode((self.query.as_str(), text), true)
+      .map_err(|e| anyhow!("テキストをトークンに分けられません: {e}"))?;
+    let to_array = |values: &[u32]| {
+      Array2::from_shape_vec(
+        (1, values.len()),
+        values.iter().map(|v| *v as i64).collect(),
+      )
+    };
+    // 入力はモデルが求める名前の順に並べる
+    let inputs = self
+      .session
+      .inputs
+      .iter()
+      .map(|input| match input.name.as_str() {
+        "input_ids" => to_array(encoding.get_ids()),
+        "attention_mask" => to_array(encoding.get_attention_mask()),
+        "token_type_ids" => to_array(encoding.get_type_ids()),
+        _ => to_array(&vec![0; encoding.get_ids().len()]),
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    let outputs = self.session.run(inputs)?;
+    let logits: OrtOwnedTensor<f32, _> = outputs
+      .first()
+      .ok_or_else(|| anyhow!("モデルの出力がありません"))?
+      .try_extract()?;
+    // 出力が二つの値（関連が無い・有る）のときは、関連が有るほうのロジットを使う
+    let score = logits
+      .view()
+      .iter()
+      .next_back()
+      .copied()
+      .ok_or_else(|| anyhow!("モデルの出力が空です"))?;
+    Ok(score)
+  }
+}
+
+/// 採点した見つかった箇所一つ
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RelevanceScore {
+  /// 見つかった箇所のID
+  pub id: String,
+  /// 法令番号
+  pub num: String,
+  pub chapter: Chapter,
+  pub word: String,
+  /// 関連の強さ
+  pub score: f32,
+  /// 採点したテキスト（`snippet`があればそれ、無ければ項のテキスト）
+  pub text: String,
+}
+
+/// 点数の高い順に並べる。同じ点数ならIDの順にする
+pub fn sort_by_score(scores: &mut [RelevanceScore]) {
+  scores.sort_by(|a, b| {
+    b.score
+      .partial_cmp(&a.score)
+      .unwrap_or(std::cmp::Ordering::Equal)
+      .then_with(|| a.id.cmp(&b.id))
+  });
+}
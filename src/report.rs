@@ -0,0 +1,187 @@
+//! テンプレートを使った検索結果の報告書の作成
+
+use crate::{
+  abbreviation::Abbreviations,
+  citation::{chapter_title_with, NumberStyle},
+  i18n::{lang, Message},
+  stats::Stats,
+  Chapter, LawParagraph,
+};
+use anyhow::Result;
+use handlebars::{html_escape, Handlebars};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// 実行のたびに変わらない、HTMLのidに使えるアンカー
+pub(crate) fn anchor(prefix: &str, key: &str) -> String {
+  let digest = Sha256::digest(key.as_bytes());
+  format!("{prefix}-{}", &format!("{digest:x}")[..12])
+}
+
+/// 法令番号と版から決まる、法令のアンカー。`--versions all`で同じ法令の版が複数あっても別のものにする。
+/// 版が無ければ法令番号だけから決める
+pub(crate) fn law_anchor(num: &str, version: Option<&str>) -> String {
+  match version {
+    Some(version) => anchor("law", &format!("{num}_{version}")),
+    None => anchor("law", num),
+  }
+}
+
+/// テンプレートに渡す条項一つ分の情報
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportChapter {
+  #[serde(flatten)]
+  pub chapter: Chapter,
+  /// 「第十一条の二第一項」（`--number-style arabic`なら「第11条の2第1項」）のような表記
+  pub title: String,
+  /// この条項の中で検索語が見つかった箇所のID
+  pub match_ids: Vec<String>,
+  /// この条項を含む条のアンカー
+  pub article_anchor: String,
+  /// この条項を含む条の「第十一条の二」のような表記
+  pub article_title: String,
+  /// この条項が、`chapters`の中でその条の最初の条項かどうか。条のアンカーはここに一度だけ書く
+  pub is_article_start: bool,
+}
+
+/// テンプレートに渡す法令一つ分の情報
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportLaw {
+  /// 法令番号
+  pub num: String,
+  /// 法令名（`LawTitle`要素のテキスト）
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+  /// 法令の略称
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub short_name: Option<String>,
+  /// 法令の版。`--versions all`のときに付ける
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub version: Option<String>,
+  /// 法令のアンカー。法令番号と版から決まる
+  pub anchor: String,
+  pub matched_words: Vec<String>,
+  pub chapters: Vec<ReportChapter>,
+}
+
+impl ReportLaw {
+  pub fn new(
+    law_paragraph: &LawParagraph,
+    abbreviations: &Abbreviations,
+    number_style: NumberStyle,
+  ) -> Self {
+    let law_anchor = law_anchor(&law_paragraph.num, law_paragraph.version.as_deref());
+    let mut last_article_anchor = None;
+    let chapters = law_paragraph
+      .chapter_data
+      .iter()
+      .map(|chapter| {
+        let article = Chapter {
+          article: chapter.article.clone(),
+          suppl_provision_title: chapter.suppl_provision_title.clone(),
+          ..Default::default()
+        };
+        let article_anchor = anchor(&law_anchor, &article.key());
+        let is_article_start = last_article_anchor.as_ref() != Some(&article_anchor);
+        last_article_anchor = Some(article_anchor.clone());
+        ReportChapter {
+          chapter: chapter.clone(),
+          title: chapter_title_with(chapter, number_style),
+          match_ids: law_paragraph.match_ids_at(chapter),
+          article_anchor,
+          article_title: chapter_title_with(&article, number_style),
+          is_article_start,
+        }
+      })
+      .collect();
+    ReportLaw {
+      num: law_paragraph.num.clone(),
+      title: law_paragraph.title.clone(),
+      short_name: abbreviations
+        .get(&law_paragraph.num)
+        .map(|name| name.to_string()),
+      version: law_paragraph.version.clone(),
+      anchor: law_anchor,
+      matched_words: law_paragraph.matched_words.clone(),
+      chapters,
+    }
+  }
+}
+
+/// テンプレートに渡す情報全体
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportData {
+  pub search_words: Vec<String>,
+  /// 単語が含まれていた法令
+  pub laws: Vec<ReportLaw>,
+  pub stats: Stats,
+}
+
+/// 報告書の法令と条へのリンクの一覧と、絞り込みのための入力欄を持つHTMLの索引を作る。
+/// `report_href`は索引から見た報告書のpath
+pub fn render_report_index(data: &ReportData, report_href: &str) -> String {
+  let report_href = html_escape(report_href);
+  let mut items = String::new();
+  for law in data.laws.iter() {
+    let name = match law.short_name.as_ref().or(law.title.as_ref()) {
+      Some(name) => format!("{name}（{}）", law.num),
+      None => law.num.clone(),
+    };
+    let name = match &law.version {
+      Some(version) => format!("{name} {version}"),
+      None => name,
+    };
+    let filter_text = format!("{name} {}", law.matched_words.join(" "));
+    items.push_str(&format!(
+      "<li data-filter=\"{}\"><a href=\"{report_href}#{}\">{}</a>\n<ul>\n",
+      html_escape(&filter_text),
+      law.anchor,
+      html_escape(&name)
+    ));
+    for chapter in law
+      .chapters
+      .iter()
+      .filter(|chapter| chapter.is_article_start)
+    {
+      items.push_str(&format!(
+        "<li><a href=\"{report_href}#{}\">{}</a></li>\n",
+        chapter.article_anchor,
+        html_escape(&chapter.article_title)
+      ));
+    }
+    items.push_str("</ul>\n</li>\n");
+  }
+  format!(
+    r##"<!DOCTYPE html>
+<html lang="{html_lang}">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+</head>
+<body>
+<input id="filter" type="search" placeholder="{placeholder}">
+<ul id="laws">
+{items}</ul>
+<script>
+document.getElementById("filter").addEventListener("input", function (event) {{
+  var query = event.target.value;
+  document.querySelectorAll("#laws > li").forEach(function (li) {{
+    li.hidden = query !== "" && li.dataset.filter.indexOf(query) < 0;
+  }});
+}});
+</script>
+</body>
+</html>
+"##,
+    html_lang = lang().html_lang(),
+    title = Message::ReportIndexTitle.text(),
+    placeholder = Message::ReportIndexFilter.text(),
+  )
+}
+
+/// handlebars形式のテンプレートに検索結果を埋め込む
+pub fn render_report(template: &str, data: &ReportData) -> Result<String> {
+  let mut handlebars = Handlebars::new();
+  handlebars.register_template_string("report", template)?;
+  Ok(handlebars.render("report", data)?)
+}
@@ -0,0 +1,154 @@
+//! 検索結果からの無作為抽出
+
+use crate::{
+  annotation::Span,
+  search_config::{SearchConfig, TextUnit},
+  Chapter,
+};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BinaryHeap;
+
+/// 抽出する件数が大きくても、始めに確保しておく要素の数はこれまでにする。残りは要素を加えるにつれて確保する
+const MAX_INITIAL_CAPACITY: usize = 1024;
+
+/// 件数のわからない列から、決まった件数を一様に無作為抽出する（reservoir sampling）
+#[derive(Debug, Clone)]
+pub struct Reservoir<T> {
+  capacity: usize,
+  seen: usize,
+  items: Vec<T>,
+}
+
+impl<T> Reservoir<T> {
+  pub fn new(capacity: usize) -> Self {
+    Reservoir {
+      capacity,
+      seen: 0,
+      items: Vec::with_capacity(capacity.min(MAX_INITIAL_CAPACITY)),
+    }
+  }
+
+  /// 要素を一つ候補に加える
+  pub fn add<R: Rng + ?Sized>(&mut self, item: T, rng: &mut R) {
+    self.seen += 1;
+    if self.items.len() < self.capacity {
+      self.items.push(item);
+    } else {
+      let i = rng.gen_range(0..self.seen);
+      if i < self.capacity {
+        self.items[i] = item;
+      }
+    }
+  }
+
+  /// これまでに候補に加えられた要素の数
+  pub fn seen(&self) -> usize {
+    self.seen
+  }
+
+  pub fn into_vec(self) -> Vec<T> {
+    self.items
+  }
+}
+
+/// 目視確認用に抽出した検索結果
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct SampledMatch {
+  /// 法令番号
+  pub num: String,
+  /// 単語が含まれていた条項
+  pub chapter: Chapter,
+  /// 単語が含まれていた条項の前後のテキスト（範囲は`ContextScope`で決める）
+  pub context: String,
+  /// `context`中の検索語の出現範囲
+  pub spans: Vec<Span>,
+  /// この条項の中で検索語が見つかった箇所のID
+  #[serde(skip_serializing_if = "Vec::is_empty", default)]
+  pub match_ids: Vec<String>,
+}
+
+/// 要素ごとに決まった乱数を割り当て、その小さい順に決まった件数を抽出する（bottom-k sampling）。
+/// 抽出されるものは要素を加える順によらないので、並列に読んだ結果からでも同じ標本になる
+#[derive(Debug, Clone)]
+pub struct BottomK<T: Ord> {
+  capacity: usize,
+  heap: BinaryHeap<(u64, T)>,
+}
+
+impl<T: Ord> BottomK<T> {
+  pub fn new(capacity: usize) -> Self {
+    BottomK {
+      capacity,
+      heap: BinaryHeap::with_capacity(capacity.min(MAX_INITIAL_CAPACITY) + 1),
+    }
+  }
+
+  /// 乱数`priority`を割り当てた要素を一つ候補に加える
+  pub fn add(&mut self, priority: u64, item: T) {
+    if self.capacity == 0 {
+      return;
+    }
+    self.heap.push((priority, item));
+    if self.heap.len() > self.capacity {
+      self.heap.pop();
+    }
+  }
+
+  /// 抽出した要素を、割り当てた乱数の小さい順に並べたもの
+  pub fn into_sorted_vec(self) -> Vec<T> {
+    self
+      .heap
+      .into_sorted_vec()
+      .into_iter()
+      .map(|(_, item)| item)
+      .collect()
+  }
+}
+
+/// 無作為抽出に使う乱数の生成器。種`seed`があれば毎回同じ乱数の列になり、無ければ実行のたびに変わる。
+/// `StdRng`はrandの版によって生成の仕方が変わりうるので、生成の仕方が決まっているChaCha8を使う
+pub fn seeded_rng(seed: Option<u64>) -> ChaCha8Rng {
+  match seed {
+    Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+    None => ChaCha8Rng::from_entropy(),
+  }
+}
+
+/// 乱数の種`seed`と要素を表す文字列`key`から決まる乱数
+pub fn seeded_priority(seed: u64, key: &str) -> u64 {
+  let mut hasher = Sha256::new();
+  hasher.update(seed.to_le_bytes());
+  hasher.update(key.as_bytes());
+  let digest = hasher.finalize();
+  let mut bytes = [0; 8];
+  bytes.copy_from_slice(&digest[..8]);
+  u64::from_le_bytes(bytes)
+}
+
+/// 全ての項を見つけるための検索語と検索の設定。項ごとにテキストをつなげ、空白でない文字を一つでも含む項を見つける。
+/// 条の見出しなどの項の外のテキストも見つかるので、見つかった条項のうち項のものだけを使う
+pub fn paragraph_search() -> (Vec<String>, SearchConfig) {
+  let config = SearchConfig {
+    is_regex: true,
+    text_unit: Some(TextUnit::Paragraph),
+    ..Default::default()
+  };
+  (vec![r"\S".to_string()], config)
+}
+
+/// コーパス全体から無作為に抽出した項
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct SampledParagraph {
+  /// 法令番号
+  pub num: String,
+  /// 法令名
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub title: Option<String>,
+  /// 項の位置
+  pub chapter: Chapter,
+  /// 項のテキスト（ルビの読みを除く）
+  pub text: String,
+}
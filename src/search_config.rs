@@ -0,0 +1,213 @@
+//! 検索の仕方の設定
+
+use crate::{fold::Folding, query::Query, snippet::Highlight, Chapter};
+use serde::Serialize;
+
+/// 同じ箇所で何度も見つかった検索語をどうまとめるか
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dedup {
+  /// 項・号などの位置ごとに一つにまとめる
+  #[default]
+  Chapter,
+  /// 条ごとに一つにまとめる。位置情報は条までにする
+  Article,
+  /// まとめず、見つかった回数だけ記録する
+  None,
+}
+
+/// 全ての検索語が揃っているかどうかを確かめる範囲
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WordScope {
+  /// 条
+  Article,
+  /// 項
+  Paragraph,
+  /// 文（`Sentence`要素）
+  Sentence,
+}
+
+impl WordScope {
+  /// 条項`chapter`を含む範囲
+  pub fn target(&self, chapter: &Chapter) -> Chapter {
+    match self {
+      WordScope::Article => Chapter {
+        paragraph: None,
+        item: None,
+        sub_item: None,
+        ..chapter.clone()
+      },
+      WordScope::Paragraph => Chapter {
+        item: None,
+        sub_item: None,
+        ..chapter.clone()
+      },
+      // 同じ条項の中の文は位置情報では区別できないので、文の要素の始まりと終わりで区切る
+      WordScope::Sentence => chapter.clone(),
+    }
+  }
+
+  /// 範囲の要素の名前。この要素の始まりと終わりで、範囲の中で待っている箇所を記録するかどうかを決める
+  pub fn element_name(&self) -> &'static [u8] {
+    match self {
+      WordScope::Article => b"Article",
+      WordScope::Paragraph => b"Paragraph",
+      WordScope::Sentence => b"Sentence",
+    }
+  }
+}
+
+/// テキストをまとめて調べる単位の要素
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextUnit {
+  /// 文（`Sentence`要素）
+  #[default]
+  Sentence,
+  /// 項（`Paragraph`要素）
+  Paragraph,
+}
+
+impl TextUnit {
+  /// 単位の要素の名前
+  pub fn element_name(&self) -> &'static [u8] {
+    match self {
+      TextUnit::Sentence => b"Sentence",
+      TextUnit::Paragraph => b"Paragraph",
+    }
+  }
+}
+
+/// 検索語とテキストの照らし合わせ方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+  /// 部分文字列として探す
+  #[default]
+  Substring,
+  /// 文のテキストを形態素に分け、形態素の境界で始まって終わるところだけを探す（`morpheme` feature）
+  #[cfg(feature = "morpheme")]
+  Morpheme,
+}
+
+impl MatchMode {
+  /// 照らし合わせるのに、形態素解析のような時間のかかる同期的な処理を使うかどうか
+  pub fn is_blocking(&self) -> bool {
+    match self {
+      MatchMode::Substring => false,
+      #[cfg(feature = "morpheme")]
+      MatchMode::Morpheme => true,
+    }
+  }
+}
+
+/// 二つの検索語が近くに現れたときだけ記録するための設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct Proximity {
+  /// 二つの検索語の間の文字数の上限
+  pub distance: usize,
+  /// 二つの検索語が現れなければならない範囲
+  pub unit: TextUnit,
+}
+
+/// 要素の入れ子の深さの上限のデフォルト。e-Govの法令XMLはこれよりずっと浅い
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+/// 検索の仕方の設定
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchConfig {
+  pub dedup: Dedup,
+  /// 指定されていれば、全ての検索語がこの範囲に揃って現れたときだけ記録する
+  pub all_words: Option<WordScope>,
+  /// 検索語を正規表現として扱うかどうか
+  pub is_regex: bool,
+  /// 指定されていれば、検索式が成り立つ範囲（`all_words`が無ければ条）の箇所だけを記録する。
+  /// 検索語は検索式に現れる語にする
+  pub query: Option<Query>,
+  /// 除外する語。この語を一つでも含む範囲（`all_words`が無ければ条）の箇所は、検索語を含んでいても記録しない
+  pub exclude_words: Vec<String>,
+  /// 指定されていれば、始めの二つの検索語が同じ範囲の中で近くに現れたところだけを記録する
+  pub proximity: Option<Proximity>,
+  /// 指定されていれば、この単位の要素の中のテキストを（ルビの読みを除いて）つなげてから検索語を探す。
+  /// ルビや文の区切りをまたぐ語も見つけられる
+  pub text_unit: Option<TextUnit>,
+  /// 要素の入れ子の深さの上限。これより深い法令XMLはエラーにする
+  pub max_depth: usize,
+  /// 検索語とテキストの照らし合わせ方
+  pub match_mode: MatchMode,
+  /// 空白を除いたテキストで、検索語との編集距離がこの値以下の部分文字列も見つかったものとする
+  pub fuzzy: Option<usize>,
+  /// 検索語の`＊`を任意の長さの文字列、`？`を任意の一文字として扱う
+  pub is_wildcard: bool,
+  /// 照らし合わせる前にそろえる表記の違い
+  pub folding: Folding,
+  /// 条と項の見出し（`ArticleCaption`・`ParagraphCaption`）のテキストだけを検索する
+  pub is_captions_only: bool,
+  /// 「…とは、…をいう。」の形の定義規定で、定義される語の部分に検索語があるところだけを記録する
+  pub is_definitions_only: bool,
+  /// 「」で囲まれた部分の中にある検索語だけを記録する
+  pub is_quoted_only: bool,
+  /// 見つかった箇所に、見つかったテキストとその前後それぞれこの文字数のテキストを付ける
+  pub snippet_context: Option<usize>,
+  /// `snippet_context`で切り出したテキストの中で、見つかった部分を囲む印
+  pub highlight: Option<Highlight>,
+  /// 見つかった箇所に、見つかった文の番号と文の中での位置を付ける
+  pub is_offsets: bool,
+  /// 条ごとに最初に記録した箇所だけを記録し、その条の残りのテキストは調べない
+  pub is_first_match_per_article: bool,
+  /// 指定されていれば、検索語が見つかった回数の合計がこの値以上の範囲（`all_words`が無ければ条）の箇所だけを記録する
+  pub min_hits: Option<usize>,
+  /// 見つかった箇所のある条それぞれに、文字数・文の数・文の平均の長さを付ける
+  pub is_article_metrics: bool,
+}
+
+impl Default for SearchConfig {
+  fn default() -> Self {
+    SearchConfig {
+      dedup: Dedup::default(),
+      all_words: None,
+      is_regex: false,
+      query: None,
+      exclude_words: vec![],
+      proximity: None,
+      text_unit: None,
+      max_depth: DEFAULT_MAX_DEPTH,
+      match_mode: MatchMode::default(),
+      fuzzy: None,
+      is_wildcard: false,
+      folding: Folding::default(),
+      is_captions_only: false,
+      is_definitions_only: false,
+      is_quoted_only: false,
+      snippet_context: None,
+      highlight: None,
+      is_offsets: false,
+      is_first_match_per_article: false,
+      min_hits: None,
+      is_article_metrics: false,
+    }
+  }
+}
+
+impl SearchConfig {
+  /// 見つかったテキスト（`snippet_context`）を付けず、見つかった箇所の位置だけを記録する設定
+  pub fn positions_only(&self) -> SearchConfig {
+    SearchConfig {
+      snippet_context: None,
+      highlight: None,
+      ..self.clone()
+    }
+  }
+
+  /// 見つかった箇所の記録を範囲ごとに判断するときの範囲
+  pub fn scope(&self) -> Option<WordScope> {
+    match self.all_words {
+      Some(scope) => Some(scope),
+      None if self.query.is_some() || !self.exclude_words.is_empty() || self.min_hits.is_some() => {
+        Some(WordScope::Article)
+      }
+      None => None,
+    }
+  }
+}
@@ -0,0 +1,175 @@
+//! 研究の再現のための、一回の検索の設定・入力・結果の記録
+
+use crate::{read_strategy::ReadOptions, search_config::SearchConfig, stats::Stats};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+  path::{Path, PathBuf},
+  time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+  fs::{metadata, read_dir, read_to_string, File},
+  io::AsyncReadExt,
+};
+
+/// SHA-256を求めるときに一度に読む大きさ
+const HASH_BUFFER_SIZE: usize = 64 << 10;
+
+/// 値を記録しないオプション（接続先の認証情報などを含むことがある）
+pub const SECRET_OPTIONS: [&str; 2] = ["--dsn", "--nats-url"];
+
+/// 値がファイルのpathで、記録するときに絶対pathにするオプション。
+/// 検索し直すときに別のディレクトリから実行しても、同じファイルを読むようにする
+pub const PATH_OPTIONS: [&str; 1] = ["--config"];
+
+/// 書き出したファイル一つとそのSHA-256
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Deserialize, Serialize)]
+pub struct OutputHash {
+  pub path: String,
+  pub sha256: String,
+}
+
+/// 一回の検索の記録
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionLog {
+  /// このツールのバージョン
+  pub tool_version: String,
+  /// コマンドライン引数（`SECRET_OPTIONS`の値は伏せる）
+  pub arguments: Vec<String>,
+  pub search_words: Vec<String>,
+  pub search_config: SearchConfig,
+  pub read_options: ReadOptions,
+  pub index_file: String,
+  /// インデックスファイルのSHA-256
+  pub index_file_sha256: String,
+  /// `--snapshot`で指定した作業ディレクトリの目録のSHA-256
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub snapshot_sha256: Option<String>,
+  /// 検索を始めた時刻（UNIX時間の秒）
+  pub started_at: u64,
+  /// 検索を始めてから全ての結果を書き出し終えるまでの時間
+  pub duration_ms: u64,
+  pub stats: Stats,
+  /// 書き出したファイルとそのSHA-256
+  pub outputs: Vec<OutputHash>,
+}
+
+/// 記録のうち、検索し直すのに使うところ
+#[derive(Debug, Clone, Deserialize)]
+struct SessionArguments {
+  arguments: Vec<String>,
+}
+
+/// 書き出した記録から、記録したコマンドライン引数を読む
+pub async fn read_session_arguments(path: &str) -> Result<Vec<String>> {
+  let s = read_to_string(path).await?;
+  let session: SessionArguments = serde_json::from_str(&s)?;
+  Ok(session.arguments)
+}
+
+/// 今の時刻（UNIX時間の秒）
+pub fn unix_time_now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or(0)
+}
+
+/// コマンドライン引数のうち、`SECRET_OPTIONS`の値を伏せる
+pub fn redact_arguments(arguments: impl Iterator<Item = String>) -> Vec<String> {
+  let mut lst = vec![];
+  let mut is_secret_value = false;
+  for arg in arguments {
+    if is_secret_value {
+      lst.push("***".to_string());
+      is_secret_value = false;
+      continue;
+    }
+    match arg.split_once('=') {
+      Some((name, _)) if SECRET_OPTIONS.contains(&name) => lst.push(format!("{name}=***")),
+      _ => {
+        is_secret_value = SECRET_OPTIONS.contains(&arg.as_str());
+        lst.push(arg);
+      }
+    }
+  }
+  lst
+}
+
+/// コマンドライン引数のうち、`PATH_OPTIONS`の値の相対pathを今のディレクトリからの絶対pathにする
+pub fn absolute_path_arguments(arguments: impl Iterator<Item = String>) -> Vec<String> {
+  let absolute = |path: &str| match std::env::current_dir() {
+    Ok(dir) if Path::new(path).is_relative() => dir.join(path).to_string_lossy().into_owned(),
+    _ => path.to_string(),
+  };
+  let mut lst = vec![];
+  let mut is_path_value = false;
+  for arg in arguments {
+    if is_path_value {
+      lst.push(absolute(&arg));
+      is_path_value = false;
+      continue;
+    }
+    match arg.split_once('=') {
+      Some((name, path)) if PATH_OPTIONS.contains(&name) => {
+        lst.push(format!("{name}={}", absolute(path)))
+      }
+      _ => {
+        is_path_value = PATH_OPTIONS.contains(&arg.as_str());
+        lst.push(arg);
+      }
+    }
+  }
+  lst
+}
+
+/// ファイルのSHA-256を求める。大きいファイルでも全てをメモリに読み込まず、少しずつ読んで求める
+pub async fn file_sha256(path: impl AsRef<Path>) -> Result<String> {
+  let mut file = File::open(path).await?;
+  let mut hasher = Sha256::new();
+  let mut buffer = vec![0; HASH_BUFFER_SIZE];
+  loop {
+    let len = file.read(&mut buffer).await?;
+    if len == 0 {
+      break;
+    }
+    hasher.update(&buffer[..len]);
+  }
+  Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// `path`がディレクトリなら、その中にある全てのファイルのpathを名前の順に返す。ファイルならそのpathだけを返す
+async fn output_files(path: &Path) -> Result<Vec<PathBuf>> {
+  if !metadata(path).await?.is_dir() {
+    return Ok(vec![path.to_path_buf()]);
+  }
+  let mut files = vec![];
+  let mut dirs = vec![path.to_path_buf()];
+  while let Some(dir) = dirs.pop() {
+    let mut entries = read_dir(&dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+      if entry.file_type().await?.is_dir() {
+        dirs.push(entry.path());
+      } else {
+        files.push(entry.path());
+      }
+    }
+  }
+  files.sort();
+  Ok(files)
+}
+
+/// 書き出したファイルそれぞれのSHA-256を求める。ディレクトリ（`--format site`の`--output`など）は、その中のファイルそれぞれについて求める
+pub async fn hash_outputs(paths: &[&str]) -> Result<Vec<OutputHash>> {
+  let mut outputs = vec![];
+  for path in paths {
+    for file in output_files(Path::new(path)).await? {
+      outputs.push(OutputHash {
+        path: file.to_string_lossy().to_string(),
+        sha256: file_sha256(&file).await?,
+      });
+    }
+  }
+  Ok(outputs)
+}
@@ -0,0 +1,125 @@
+//! GitHub Pagesなどにそのまま置ける、検索結果の静的なWebサイト
+//!
+//! 法令の一覧の`index.html`と、法令ごとに見つかった条項のテキストを検索語に印を付けて並べたページを作る。
+
+use crate::{
+  i18n::{lang, list_separator, match_count, Message},
+  report::law_anchor,
+};
+use handlebars::html_escape;
+
+/// 法令一つ分のページの内容
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SiteLaw {
+  /// 法令番号
+  pub num: String,
+  /// 法令の版。`--versions all`のときに付ける
+  pub version: Option<String>,
+  /// 表示する法令名（略称があれば略称）
+  pub name: String,
+  pub matched_words: Vec<String>,
+  /// 見つかった箇所の数
+  pub match_count: usize,
+  pub chapters: Vec<SiteChapter>,
+}
+
+/// 見つかった条項一つ分の見出しとテキスト
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SiteChapter {
+  /// 「第十一条の二第一項」のような表記
+  pub title: String,
+  pub text: String,
+  /// `text`の中で検索語が見つかった範囲（バイト単位、終端は含まない）。始まりの順に並べる
+  pub marks: Vec<(usize, usize)>,
+}
+
+impl SiteLaw {
+  /// この法令のページのファイル名。法令番号と版から決まるので、実行し直してもURLが変わらない
+  pub fn file_name(&self) -> String {
+    format!("{}.html", law_anchor(&self.num, self.version.as_deref()))
+  }
+}
+
+/// テキストをHTMLにエスケープし、見つかった範囲`marks`を`<mark>`で囲む。重なる範囲は先のものを使う
+fn highlight(text: &str, marks: &[(usize, usize)]) -> String {
+  let mut s = String::new();
+  let mut position = 0;
+  for &(start, end) in marks.iter() {
+    if start < position || text.get(start..end).is_none() {
+      continue;
+    }
+    s.push_str(&html_escape(&text[position..start]));
+    s.push_str(&format!("<mark>{}</mark>", html_escape(&text[start..end])));
+    position = end;
+  }
+  s.push_str(&html_escape(&text[position..]));
+  s
+}
+
+fn page(title: &str, body: &str) -> String {
+  format!(
+    r#"<!DOCTYPE html>
+<html lang="{}">
+<head>
+<meta charset="utf-8">
+<title>{}</title>
+</head>
+<body>
+{body}</body>
+</html>
+"#,
+    lang().html_lang(),
+    html_escape(title)
+  )
+}
+
+/// 法令一つ分のページ。その法令に含まれていた検索語に印を付ける
+pub fn render_law_page(law: &SiteLaw) -> String {
+  let mut body = format!(
+    "<p><a href=\"index.html\">{}</a></p>\n<h1>{}</h1>\n<p>{}</p>\n",
+    Message::SiteBackToIndex.text(),
+    html_escape(&law.name),
+    html_escape(&law.num)
+  );
+  for chapter in law.chapters.iter() {
+    body.push_str(&format!(
+      "<h2>{}</h2>\n<p>{}</p>\n",
+      html_escape(&chapter.title),
+      highlight(&chapter.text, &chapter.marks).replace('\n', "<br>\n")
+    ));
+  }
+  page(&law.name, &body)
+}
+
+/// 検索語と、ページへのリンクを付けた法令の一覧のページ。`snapshot_sha256`があれば目録のSHA-256も載せる
+pub fn render_site_index(
+  laws: &[SiteLaw],
+  search_words: &[String],
+  snapshot_sha256: Option<&str>,
+) -> String {
+  let mut body = format!(
+    "<h1>{}</h1>\n<p>{}{}</p>\n",
+    Message::SiteTitle.text(),
+    Message::SiteSearchWords.text(),
+    html_escape(&search_words.join(list_separator()))
+  );
+  if let Some(snapshot_sha256) = snapshot_sha256 {
+    body.push_str(&format!(
+      "<p>{}<code>{}</code></p>\n",
+      Message::SiteSnapshot.text(),
+      html_escape(snapshot_sha256)
+    ));
+  }
+  body.push_str("<ul>\n");
+  for law in laws.iter() {
+    body.push_str(&format!(
+      "<li><a href=\"{}\">{}</a> ({}) {}</li>\n",
+      law.file_name(),
+      html_escape(&law.name),
+      html_escape(&law.matched_words.join(list_separator())),
+      match_count(law.match_count)
+    ));
+  }
+  body.push_str("</ul>\n");
+  page(Message::SiteTitle.text(), &body)
+}
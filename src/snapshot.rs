@@ -0,0 +1,81 @@
+//! 作業ディレクトリの法令XMLファイル群の目録（スナップショット）
+//!
+//! 検索結果がどの版の法令XMLファイル群から得られたのかを示せるよう、
+//! ファイルの一覧とそれぞれのSHA-256・法令番号を記録する。
+
+use crate::{corpus::read_law_num, date::Date};
+use anyhow::Result;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use tokio::fs::{read, read_dir};
+
+/// 目録に記録するファイル一つ分の情報
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct SnapshotFile {
+  /// 作業ディレクトリからの相対path（区切りは`/`）
+  pub path: String,
+  pub sha256: String,
+  /// 法令番号。読み取れなかったときは無い
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub law_num: Option<String>,
+}
+
+/// 作業ディレクトリの目録
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Snapshot {
+  /// 目録を作った日（YYYYMMDD）
+  pub created_date: String,
+  pub files: Vec<SnapshotFile>,
+}
+
+/// バイト列のSHA-256を16進数の文字列で返す
+pub fn sha256_hex(bytes: &[u8]) -> String {
+  format!("{:x}", Sha256::digest(bytes))
+}
+
+/// 作業ディレクトリ以下にあるXMLファイルを全て読み、目録を作る
+pub async fn create_snapshot(work_dir: &Path) -> Result<Snapshot> {
+  let mut files = vec![];
+  let mut dirs = vec![work_dir.to_path_buf()];
+  while let Some(dir) = dirs.pop() {
+    let mut entries = read_dir(&dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+      let path = entry.path();
+      if entry.file_type().await?.is_dir() {
+        dirs.push(path);
+      } else if path.extension().map(|ext| ext == "xml").unwrap_or(false) {
+        let bytes = read(&path).await?;
+        let mut reader = Reader::from_reader(&bytes[..]);
+        let law_num = read_law_num(&mut reader).await.unwrap_or(None);
+        let relative_path = path
+          .strip_prefix(work_dir)
+          .unwrap_or(&path)
+          .components()
+          .map(|component| component.as_os_str().to_string_lossy().to_string())
+          .collect::<Vec<_>>()
+          .join("/");
+        files.push(SnapshotFile {
+          path: relative_path,
+          sha256: sha256_hex(&bytes),
+          law_num,
+        });
+      }
+    }
+  }
+  files.sort();
+  Ok(Snapshot {
+    created_date: Date::today().to_string(),
+    files,
+  })
+}
+
+/// 目録のJSONファイルを読み、そのファイルのSHA-256を返す。
+/// `sha256sum`などで目録のファイルから求めた値と同じになる。
+pub async fn read_snapshot_hash(path: &Path) -> Result<String> {
+  let bytes = read(path).await?;
+  // 目録の形式になっているかどうかを確かめておく
+  serde_json::from_slice::<Snapshot>(&bytes)?;
+  Ok(sha256_hex(&bytes))
+}
@@ -0,0 +1,43 @@
+//! 見つかった箇所の前後のテキストの切り出し
+
+use serde::Serialize;
+
+/// 切り出したテキストの中で、見つかった部分の前後に入れる印
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Highlight {
+  /// 見つかった部分の前に入れる文字列（`【`や`<em>`など）
+  pub open: String,
+  /// 見つかった部分の後ろに入れる文字列（`】`や`</em>`など）
+  pub close: String,
+}
+
+/// テキストの`start`から`end`（バイト単位）の範囲と、その前後それぞれ`context`文字を切り出す。
+/// `highlight`があれば、見つかった範囲をその印で囲む
+pub fn snippet(
+  text: &str,
+  (start, end): (usize, usize),
+  context: usize,
+  highlight: Option<&Highlight>,
+) -> String {
+  let before = text[..start]
+    .char_indices()
+    .rev()
+    .take(context)
+    .last()
+    .map(|(i, _)| i)
+    .unwrap_or(start);
+  let after = text[end..]
+    .char_indices()
+    .nth(context)
+    .map(|(i, _)| end + i)
+    .unwrap_or(text.len());
+  match highlight {
+    Some(Highlight { open, close }) => format!(
+      "{}{open}{}{close}{}",
+      &text[before..start],
+      &text[start..end],
+      &text[end..after]
+    ),
+    None => text[before..after].to_string(),
+  }
+}
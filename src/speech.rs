@@ -0,0 +1,37 @@
+//! 読み上げ（TTS）に渡すための条文のテキスト
+//!
+//! [`hydrate_chapters`](crate::hydrate::hydrate_chapters)で取り出したテキストから、
+//! 見出しの括弧や番号の後の空白などの構造を表すための記号を取り除き、読む順に並べる。
+
+/// 句点などの、読み上げで区切りになる文字
+const PAUSE_CHARS: [char; 4] = ['。', '、', '？', '！'];
+
+/// 取り出したテキストを読み上げ用のテキストにする。
+/// 行の先頭の「（目的）」のような見出しは括弧を外して文にし、番号の後の全角空白は読点にし、
+/// 区切りの無い行の末尾には句点を付ける
+pub fn to_speech_text(text: &str) -> String {
+  let mut lines = vec![];
+  for line in text.lines() {
+    let mut line = line.trim();
+    if line.is_empty() {
+      continue;
+    }
+    if let Some(rest) = line.strip_prefix('（') {
+      if let Some(end) = rest.find('）') {
+        lines.push(format!("{}。", &rest[..end]));
+        line = rest[end + '）'.len_utf8()..].trim_start();
+        if line.is_empty() {
+          continue;
+        }
+      }
+    }
+    let mut s = line.trim_end_matches('　').replace('　', "、");
+    if !s.ends_with(&PAUSE_CHARS[..]) {
+      s.push('。');
+    }
+    lines.push(s);
+  }
+  let mut s = lines.join("\n");
+  s.push('\n');
+  s
+}
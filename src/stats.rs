@@ -0,0 +1,81 @@
+//! 検索結果の統計情報
+
+use crate::{cooccurrence::NgramCount, law_num::parse_law_num_year, LawParagraph};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// 検索結果の統計情報
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Stats {
+  /// 検索した法令の数。`--versions all`で同じ法令の版を複数検索しても、法令番号ごとに一つと数える
+  pub law_count: usize,
+  /// 空だったり壊れていたりして検索できなかった法令ファイルの数
+  #[serde(default)]
+  pub skipped_file_count: usize,
+  /// 単語が含まれていた法令の数
+  pub matched_law_count: usize,
+  /// 単語ごとの、その単語を含む法令の数。同義語で見つかったものは元の検索語に数える
+  pub word_law_count: BTreeMap<String, usize>,
+  /// 法令番号の年から求めた年代（西暦）ごとの、単語を含む法令の数
+  pub decade_word_law_count: BTreeMap<usize, BTreeMap<String, usize>>,
+  /// 法令番号から年代を読み取れなかった法令についての、単語を含む法令の数
+  #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+  pub unknown_decade_word_law_count: BTreeMap<String, usize>,
+  /// 検索に使った作業ディレクトリの目録のSHA-256
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub snapshot_sha256: Option<String>,
+  /// 検索語と同じ文によく現れ、検索語に加えると見つかる条項が増えそうな語の候補。`--suggest-terms`のときに付ける
+  #[serde(skip_serializing_if = "Vec::is_empty", default)]
+  pub suggested_terms: Vec<NgramCount>,
+  /// これまでに数えた法令の法令番号
+  #[serde(skip)]
+  law_nums: BTreeSet<String>,
+  /// これまでに数えた、単語が含まれていた法令の法令番号
+  #[serde(skip)]
+  matched_law_nums: BTreeSet<String>,
+  /// これまでに数えた、法令番号と単語の組
+  #[serde(skip)]
+  law_words: BTreeSet<(String, String)>,
+  /// `--synonyms`で広げた同義語と、その元の検索語
+  #[serde(skip)]
+  synonym_origins: HashMap<String, String>,
+}
+
+impl Stats {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// 同義語`origins`（同義語と、その元の検索語）で見つかったものを、元の検索語に数える集計
+  pub fn with_synonym_origins(origins: HashMap<String, String>) -> Self {
+    Stats {
+      synonym_origins: origins,
+      ..Self::default()
+    }
+  }
+
+  /// 検索した法令一つ分の結果を集計に加える。同じ法令番号の法令（別の版）は、法令の数に一度だけ数える
+  pub fn add(&mut self, law: &LawParagraph) {
+    if self.law_nums.insert(law.num.clone()) {
+      self.law_count += 1;
+    }
+    if law.matched_words.is_empty() {
+      return;
+    }
+    if self.matched_law_nums.insert(law.num.clone()) {
+      self.matched_law_count += 1;
+    }
+    let decade_count = match parse_law_num_year(&law.num) {
+      Some(year) => self.decade_word_law_count.entry(year.decade()).or_default(),
+      None => &mut self.unknown_decade_word_law_count,
+    };
+    for word in law.matched_words.iter() {
+      let word = self.synonym_origins.get(word).unwrap_or(word);
+      if !self.law_words.insert((law.num.clone(), word.clone())) {
+        continue;
+      }
+      *decade_count.entry(word.clone()).or_default() += 1;
+      *self.word_law_count.entry(word.clone()).or_default() += 1;
+    }
+  }
+}
@@ -0,0 +1,82 @@
+//! 検索語をその同義語にも広げて探すための同義語の辞書
+//!
+//! 辞書は一行に一つの語を書くTSVで、一列目に検索語、二列目にその同義語を`,`か`、`で区切って並べる
+//! （`自動車\t車両,自動車等`）。空の行と`#`で始まる行は読み飛ばす。
+
+use crate::LawParagraph;
+use anyhow::{anyhow, Result};
+use std::{collections::HashMap, path::Path};
+use tokio::fs::read_to_string;
+
+/// 検索語から同義語への対応表
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Synonyms {
+  map: HashMap<String, Vec<String>>,
+}
+
+impl Synonyms {
+  pub fn new(map: HashMap<String, Vec<String>>) -> Self {
+    Synonyms { map }
+  }
+
+  /// TSVの辞書を解析する。同じ検索語が何度も現れたときは、同義語を続けて加える
+  pub fn parse(s: &str) -> Result<Self> {
+    let mut map: HashMap<String, Vec<String>> = HashMap::new();
+    for (i, line) in s.lines().enumerate() {
+      let line = line.trim_end_matches('\r');
+      if line.trim().is_empty() || line.starts_with('#') {
+        continue;
+      }
+      let (word, synonyms) = line
+        .split_once('\t')
+        .ok_or_else(|| anyhow!("同義語の辞書の{}行目にタブがありません", i + 1))?;
+      let word = word.trim();
+      if word.is_empty() {
+        return Err(anyhow!("同義語の辞書の{}行目の検索語が空です", i + 1));
+      }
+      let entry = map.entry(word.to_string()).or_default();
+      for synonym in synonyms.split(|c| c == ',' || c == '、') {
+        let synonym = synonym.trim();
+        if !synonym.is_empty() && synonym != word && !entry.iter().any(|s| s == synonym) {
+          entry.push(synonym.to_string());
+        }
+      }
+    }
+    Ok(Synonyms { map })
+  }
+
+  /// TSVの辞書のファイルを読む
+  pub async fn load(path: &Path) -> Result<Self> {
+    let s = read_to_string(path).await?;
+    Synonyms::parse(&s)
+  }
+
+  /// 検索語の同義語
+  pub fn get(&self, word: &str) -> &[String] {
+    self.map.get(word).map(|s| s.as_slice()).unwrap_or(&[])
+  }
+
+  /// 検索語それぞれの後ろにその同義語を加えた、実際に探す語の一覧と、
+  /// 同義語として加えた語からそれを加えた元の検索語への対応。
+  /// 検索語そのものや先に加えた語と同じ同義語は加えない
+  pub fn expand(&self, words: &[String]) -> (Vec<String>, HashMap<String, String>) {
+    let mut expanded = words.to_vec();
+    let mut origins = HashMap::new();
+    for word in words.iter() {
+      for synonym in self.get(word) {
+        if !expanded.contains(synonym) {
+          expanded.push(synonym.clone());
+          origins.insert(synonym.clone(), word.clone());
+        }
+      }
+    }
+    (expanded, origins)
+  }
+}
+
+/// 見つかった箇所のうち同義語で見つかったものに、元の検索語を`synonym_of`として付ける
+pub fn mark_synonyms(law_paragraph: &mut LawParagraph, origins: &HashMap<String, String>) {
+  for m in law_paragraph.matches.iter_mut() {
+    m.synonym_of = origins.get(&m.word).cloned();
+  }
+}
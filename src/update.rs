@@ -0,0 +1,63 @@
+//! 作業ディレクトリの法令XMLファイルが最新かどうかの確認
+
+use crate::{date::Date, egov::UpdatedLaw};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 手元の法令XMLファイルの状態
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateStatus {
+  /// 更新後の版を含んでいない
+  Stale,
+  /// ファイル名の日付から、更新後の版であることがわかる
+  UpToDate,
+  /// 作業ディレクトリにその法令のファイルが無い
+  NotInCorpus,
+}
+
+/// 更新された法令一件についての確認結果
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct UpdateReport {
+  pub law: UpdatedLaw,
+  /// 更新法令一覧に載っていた日（YYYYMMDD）
+  pub updated_date: String,
+  pub status: UpdateStatus,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub local_file: Option<PathBuf>,
+}
+
+/// e-Gov法令検索からダウンロードしたファイルの名前（`{法令ID}_{施行日}_{改正法令ID}.xml`）から施行日を読む
+pub fn file_version_date(path: &Path) -> Option<Date> {
+  let stem = path.file_stem()?.to_str()?;
+  stem.split('_').nth(1).and_then(Date::parse)
+}
+
+/// 更新された法令をダウンロードするときのファイルの名前（`{法令ID}_{施行日}.xml`）。
+/// [`file_version_date`]で施行日を読めるので、次の確認では最新のものとわかる。施行日が無ければ更新法令一覧に載っていた日を使う
+pub fn download_file_name(updated_law: &UpdatedLaw, updated_date: &str) -> String {
+  let date = if updated_law.enforcement_date.is_empty() {
+    updated_date
+  } else {
+    &updated_law.enforcement_date
+  };
+  format!("{}_{}.xml", updated_law.law_id, date)
+}
+
+/// 更新された法令について、手元のファイルの状態を判定する
+pub fn classify(
+  updated_law: &UpdatedLaw,
+  updated_date: &Date,
+  local_file: Option<&Path>,
+) -> UpdateStatus {
+  match local_file {
+    None => UpdateStatus::NotInCorpus,
+    Some(path) => {
+      let target_date = Date::parse(&updated_law.enforcement_date).unwrap_or(*updated_date);
+      match file_version_date(path) {
+        Some(file_date) if file_date >= target_date => UpdateStatus::UpToDate,
+        _ => UpdateStatus::Stale,
+      }
+    }
+  }
+}
@@ -0,0 +1,58 @@
+//! 記録された検索結果が今の作業ディレクトリでも再現できるかどうかの確認
+
+use crate::{search_config::SearchConfig, search_xml_with, Chapter};
+use anyhow::Result;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::{fs::File, io::BufReader};
+
+/// 確認した条項の状態
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifyStatus {
+  /// 検索し直しても同じ条項が見つかった
+  Matched,
+  /// 検索し直すと見つからなかった
+  Mismatched,
+  /// 作業ディレクトリにその法令のファイルが無い
+  LawFileNotFound,
+  /// 検索結果に検索語が記録されておらず、検索し直せなかった
+  NoSearchWords,
+}
+
+/// 条項一つについての確認結果
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct VerifyReport {
+  /// 法令番号
+  pub num: String,
+  pub chapter: Chapter,
+  pub status: VerifyStatus,
+}
+
+/// 法令ファイルを`search_words`と`search_config`の照らし合わせ方で検索し直し、`chapters`それぞれが今も見つかるかどうかを返す。
+/// `search_config`は検索結果を作ったときと同じものでなければ、`--dedup article`の条や正規表現で見つかった条項も見つからなくなる
+pub async fn verify_chapters(
+  file_path: &Path,
+  search_words: &[String],
+  search_config: &SearchConfig,
+  chapters: &[Chapter],
+) -> Result<Vec<VerifyStatus>> {
+  if search_words.is_empty() {
+    return Ok(vec![VerifyStatus::NoSearchWords; chapters.len()]);
+  }
+  let mut reader = Reader::from_reader(BufReader::new(File::open(file_path).await?));
+  let law_paragraph =
+    search_xml_with(search_words, &search_config.positions_only(), &mut reader).await?;
+  let statuses = chapters
+    .iter()
+    .map(|chapter| {
+      if law_paragraph.chapter_data.contains(chapter) {
+        VerifyStatus::Matched
+      } else {
+        VerifyStatus::Mismatched
+      }
+    })
+    .collect();
+  Ok(statuses)
+}
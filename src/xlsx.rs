@@ -0,0 +1,119 @@
+//! Excel（xlsx）形式での検索結果の書き出し（`xlsx` feature）
+//!
+//! 見つかった箇所を1行に1箇所ずつ並べたシートと、法令ごとの件数をまとめたシートを作る。
+//! Excelの上限を超えないように、長いセルは切り詰め、行が多ければ続きのシートに分ける。
+
+use anyhow::Result;
+use rust_xlsxwriter::Workbook;
+use std::path::Path;
+
+/// 見つかった箇所のシートの1行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XlsxMatchRow {
+  /// 法令名（略称があれば略称。法令名が無ければ法令番号）
+  pub law_name: String,
+  pub law_num: String,
+  /// 条項の見出し（「第十一条第一項」など）
+  pub citation: String,
+  pub word: String,
+  /// 見つかった箇所を含むテキスト
+  pub snippet: String,
+  /// e-Gov法令APIで条文を取得するURL
+  pub link: String,
+  pub id: String,
+}
+
+/// 法令ごとの件数のシートの1行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XlsxSummaryRow {
+  pub law_name: String,
+  pub law_num: String,
+  pub matched_words: Vec<String>,
+  pub match_count: usize,
+}
+
+const MATCH_HEADERS: [&str; 7] = [
+  "法令名",
+  "法令番号",
+  "条項",
+  "検索語",
+  "テキスト",
+  "URL",
+  "ID",
+];
+
+const SUMMARY_HEADERS: [&str; 4] = ["法令名", "法令番号", "含まれていた単語", "箇所の数"];
+
+/// Excelのセル一つに入る文字数の上限
+const MAX_CELL_CHARS: usize = 32_767;
+
+/// Excelのシート一つの行数の上限
+const MAX_ROWS: usize = 1_048_576;
+
+/// 見つかった箇所と法令ごとの件数を、二つのシートにしてxlsxファイルに書き出す。
+/// 見出しの行を除いて`MAX_ROWS - 1`行を超えるときは、「箇所2」のような続きのシートに分ける
+pub fn write_xlsx(path: &Path, rows: &[XlsxMatchRow], summary: &[XlsxSummaryRow]) -> Result<()> {
+  let mut workbook = Workbook::new();
+
+  for (k, rows) in sheet_chunks(rows).into_iter().enumerate() {
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name(sheet_name("箇所", k))?;
+    for (col, header) in MATCH_HEADERS.iter().enumerate() {
+      worksheet.write_string(0, col as u16, *header)?;
+    }
+    for (i, row) in rows.iter().enumerate() {
+      let r = i as u32 + 1;
+      worksheet.write_string(r, 0, truncate_cell(&row.law_name))?;
+      worksheet.write_string(r, 1, truncate_cell(&row.law_num))?;
+      worksheet.write_string(r, 2, truncate_cell(&row.citation))?;
+      worksheet.write_string(r, 3, truncate_cell(&row.word))?;
+      worksheet.write_string(r, 4, truncate_cell(&row.snippet))?;
+      worksheet.write_string(r, 5, truncate_cell(&row.link))?;
+      worksheet.write_string(r, 6, truncate_cell(&row.id))?;
+    }
+  }
+
+  for (k, summary) in sheet_chunks(summary).into_iter().enumerate() {
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name(sheet_name("法令ごとの件数", k))?;
+    for (col, header) in SUMMARY_HEADERS.iter().enumerate() {
+      worksheet.write_string(0, col as u16, *header)?;
+    }
+    for (i, row) in summary.iter().enumerate() {
+      let r = i as u32 + 1;
+      worksheet.write_string(r, 0, truncate_cell(&row.law_name))?;
+      worksheet.write_string(r, 1, truncate_cell(&row.law_num))?;
+      worksheet.write_string(r, 2, truncate_cell(&row.matched_words.join("、")))?;
+      worksheet.write_number(r, 3, row.match_count as f64)?;
+    }
+  }
+
+  workbook.save(path)?;
+  Ok(())
+}
+
+/// 一つのシートに入る行ずつに分ける。行が無くても、見出しだけのシートを一つ作る
+fn sheet_chunks<T>(rows: &[T]) -> Vec<&[T]> {
+  if rows.is_empty() {
+    vec![rows]
+  } else {
+    rows.chunks(MAX_ROWS - 1).collect()
+  }
+}
+
+/// `k`番目（0から数える）のシートの名前。二つ目からは名前の後に番号を付ける
+fn sheet_name(name: &str, k: usize) -> String {
+  if k == 0 {
+    name.to_string()
+  } else {
+    format!("{}{}", name, k + 1)
+  }
+}
+
+/// セルに入らない長さのテキストを、先頭の`MAX_CELL_CHARS`文字に切り詰める
+fn truncate_cell(text: &str) -> &str {
+  match text.char_indices().nth(MAX_CELL_CHARS) {
+    Some((end, _)) => &text[..end],
+    None => text,
+  }
+}
@@ -0,0 +1,47 @@
+use search_article_with_word::definition::defined_terms;
+
+fn terms(sentence: &str) -> Vec<&str> {
+  defined_terms(sentence)
+    .into_iter()
+    .map(|(_, term)| term)
+    .collect()
+}
+
+#[test]
+fn quoted_term() {
+  let sentence = "この法律において「個人情報」とは、生存する個人に関する情報をいう。";
+  let found = defined_terms(sentence);
+  assert_eq!(found.len(), 1);
+  let (start, term) = found[0];
+  assert_eq!(term, "個人情報");
+  assert_eq!(&sentence[start..start + term.len()], "個人情報");
+}
+
+#[test]
+fn every_marker_in_sentence() {
+  assert_eq!(
+    terms("この法律において「事業者」とは、事業を行う者をいい、「消費者」とは、個人をいう。"),
+    vec!["事業者", "消費者"]
+  );
+}
+
+#[test]
+fn item_form() {
+  let sentence = "一　個人情報　生存する個人に関する情報をいう。";
+  let found = defined_terms(sentence);
+  assert_eq!(found.len(), 1);
+  let (start, term) = found[0];
+  assert_eq!(term, "個人情報");
+  assert_eq!(&sentence[start..start + term.len()], "個人情報");
+  assert_eq!(
+    terms("法令　法律、法律に基づく命令及び規則をいう。"),
+    vec!["法令"]
+  );
+}
+
+#[test]
+fn not_definition() {
+  assert!(terms("個人情報取扱事業者は、個人情報を取り扱うに当たっては、その利用の目的をできる限り特定しなければならない。").is_empty());
+  assert!(terms("一　個人情報の利用目的").is_empty());
+  assert!(terms("前項の規定の適用については、同項中「事業者」とあるのは、「個人」とする。").is_empty());
+}
@@ -0,0 +1,44 @@
+use search_article_with_word::{
+  fold::Folding,
+  matcher::{MultiMatcher, WordMatcher},
+  search_config::SearchConfig,
+};
+
+fn numerals_config() -> SearchConfig {
+  SearchConfig {
+    folding: Folding {
+      is_numerals: true,
+      ..Folding::default()
+    },
+    ..SearchConfig::default()
+  }
+}
+
+#[test]
+fn numerals_match_whole_numbers() {
+  let config = numerals_config();
+  let matcher = WordMatcher::new("第二種", &config).unwrap();
+  assert!(matcher.is_match("第２種"));
+  assert!(matcher.is_match("第2種"));
+  assert_eq!(matcher.count("第二種及び第２種"), 2);
+}
+
+#[test]
+fn numerals_do_not_match_part_of_number() {
+  let config = numerals_config();
+  let matcher = WordMatcher::new("二", &config).unwrap();
+  assert!(!matcher.is_match("二十一"));
+  assert_eq!(matcher.count("二十一"), 0);
+  assert!(matcher.find_ranges("第十二条").is_empty());
+  let matcher = WordMatcher::new("第二", &config).unwrap();
+  assert!(!matcher.is_match("第二十一条"));
+  assert_eq!(matcher.find_ranges("第二条"), vec![(0, "第二".len())]);
+}
+
+#[test]
+fn multi_matcher_does_not_match_part_of_number() {
+  let config = numerals_config();
+  let words = vec!["二".to_string(), "第二十一条".to_string()];
+  let multi_matcher = MultiMatcher::new(&words, &config).unwrap();
+  assert_eq!(multi_matcher.counts("第二十一条"), vec![0, 1]);
+}